@@ -110,6 +110,48 @@ impl Place {
         Self(variable.0)
     }
 
+    /// Shorthand for `Place::from_variable(Variable::from_variable_index(ix))`.
+    ///
+    /// ```
+    /// use boojum::cs::{Place, Variable};
+    ///
+    /// assert_eq!(
+    ///     Place::from_variable_index(7),
+    ///     Place::from_variable(Variable::from_variable_index(7))
+    /// );
+    /// ```
+    #[inline(always)]
+    pub const fn from_variable_index(variable_index: u64) -> Self {
+        Self::from_variable(Variable::from_variable_index(variable_index))
+    }
+
+    /// Places of `count` consecutive variables, starting with `start`. Panics
+    /// if the range ends past `u64::MAX`.
+    ///
+    /// ```
+    /// use boojum::cs::Place;
+    ///
+    /// let places = Place::range(3, 2).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     places,
+    ///     [Place::from_variable_index(3), Place::from_variable_index(4)]
+    /// );
+    /// ```
+    #[inline(always)]
+    pub fn range(start: u64, count: u64) -> impl Iterator<Item = Place> + Clone {
+        let end = start.checked_add(count);
+
+        assert!(
+            end.is_some(),
+            "Range of {} places starting at {} overflows.",
+            count,
+            start
+        );
+
+        (start..end.unwrap()).map(Self::from_variable_index)
+    }
+
     #[inline(always)]
     pub const fn from_witness(witness: Witness) -> Self {
         Self(witness.0 | WITNESS_BIT_MASK)
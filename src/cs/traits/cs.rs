@@ -20,6 +20,12 @@ impl<'set, 'tgt: 'set, T: SmallField> DstBuffer<'set, 'tgt, T> {
                 *offset += 1;
             }
             DstBuffer::MutSliceIndirect(dst, debug_track, offset) => {
+                debug_assert!(
+                    *offset < dst.len(),
+                    "Pushed more values than the {} declared outputs.",
+                    dst.len()
+                );
+
                 if cfg!(debug_track) && *debug_track {
                     log!("   set out {} <- {}", *offset, value.as_raw_u64())
                 }
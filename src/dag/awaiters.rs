@@ -2,10 +2,11 @@ use std::cell::UnsafeCell;
 use std::hint::spin_loop;
 use std::marker::PhantomData;
 use std::panic::resume_unwind;
-use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::yield_now;
 
-use crate::log;
+use crate::cs::Place;
 use crate::utils::{PipeOp, UnsafeCellEx};
 
 use super::primitives::Metadata;
@@ -17,11 +18,32 @@ pub(crate) struct AwaiterStats {
     total_registered: u64,
 }
 
+/// Gets notified about the awaiters of a resolver. Both functions receive the
+/// tracker of the awaited metadata and are called on the awaiting thread.
+pub trait AwaiterObserver: Send + Sync {
+    fn on_register(&self, md_tracker: u64);
+    /// Called once the awaited values are resolved.
+    fn on_wake(&self, md_tracker: u64);
+}
+
+/// Places awaited with `CircuitResolverOpts::prioritize_awaited`, for the
+/// resolution window to resolve ahead of the execution order.
+pub(crate) struct PrioritizedAwait {
+    pub(crate) places: Vec<Place>,
+    /// Set once the window resolved the places.
+    pub(crate) resolved: Arc<AtomicBool>,
+}
+
 /// The broker provides awaiters, which are used to wait for a particular resolution.
 pub struct AwaitersBroker<T> {
     /// Tracks the maximum resolved location.
     max_resolved: AtomicU64,
     pub(crate) stats: UnsafeCell<AwaiterStats>,
+    observer: UnsafeCell<Option<Arc<dyn AwaiterObserver>>>,
+    prioritized: Mutex<Vec<PrioritizedAwait>>,
+    /// The number of prioritized awaits registered so far, so the window
+    /// notices new ones without taking the lock.
+    prioritized_added: AtomicUsize,
     phantom: PhantomData<T>,
 }
 
@@ -34,10 +56,31 @@ impl<T: TrackId> AwaitersBroker<T> {
             stats: UnsafeCell::new(AwaiterStats {
                 total_registered: 0,
             }),
+            observer: UnsafeCell::new(None),
+            prioritized: Mutex::new(Vec::new()),
+            prioritized_added: AtomicUsize::new(0),
             phantom: PhantomData,
         }
     }
 
+    /// Rewinds the broker to the initial state. Must not be called while
+    /// there are active awaiters.
+    pub(crate) fn reset(&self) {
+        self.max_resolved.store(0, Ordering::Relaxed);
+        self.prioritized.lock().unwrap().clear();
+        self.prioritized_added.store(0, Ordering::Relaxed);
+    }
+
+    /// Safety: Must not be called while there are active awaiters.
+    pub(crate) unsafe fn set_observer(&self, observer: Option<Arc<dyn AwaiterObserver>>) {
+        *self.observer.u_deref_mut() = observer;
+    }
+
+    fn observer(&self) -> Option<&dyn AwaiterObserver> {
+        // Safety: The observer is replaced only when there are no awaiters.
+        unsafe { self.observer.u_deref() }.as_deref()
+    }
+
     pub(crate) fn notify(&self, resolved: T) {
         // TODO: Remove once the system is stable.
         let max_resolved = self.max_resolved.load(Ordering::Relaxed).to(T::from);
@@ -54,17 +97,70 @@ impl<T: TrackId> AwaitersBroker<T> {
     pub(crate) fn register<'a>(&'a self, comms: &'a ResolverComms, md: &Metadata<T>) -> Awaiter<T> {
         unsafe { self.stats.u_deref_mut().total_registered += 1 };
 
+        if let Some(observer) = self.observer() {
+            observer.on_register(md.tracker.into());
+        }
+
         Awaiter::new(self, comms, md.tracker)
     }
+
+    /// Same as `register`, but asks the resolution window to resolve `places`
+    /// ahead of the execution order, along with the unresolved values they
+    /// depend on. The awaiter returns once either the window or the order
+    /// gets to them.
+    pub(crate) fn register_prioritized<'a>(
+        &'a self,
+        comms: &'a ResolverComms,
+        md: &Metadata<T>,
+        places: &[Place],
+    ) -> Awaiter<T> {
+        let awaiter = self.register(comms, md);
+
+        if self.max_resolved.load(Ordering::Relaxed).to(T::from) >= md.tracker {
+            return awaiter;
+        }
+
+        let resolved = Arc::new(AtomicBool::new(false));
+
+        self.prioritized.lock().unwrap().push(PrioritizedAwait {
+            places: places.to_vec(),
+            resolved: resolved.clone(),
+        });
+        self.prioritized_added.fetch_add(1, Ordering::Relaxed);
+
+        Awaiter {
+            resolved: Some(resolved),
+            ..awaiter
+        }
+    }
+
+    pub(crate) fn prioritized_added(&self) -> usize {
+        self.prioritized_added.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn take_prioritized(&self) -> Vec<PrioritizedAwait> {
+        std::mem::take(&mut *self.prioritized.lock().unwrap())
+    }
+
+    /// Puts back the prioritized awaits the window couldn't resolve yet.
+    pub(crate) fn keep_prioritized(&self, awaits: Vec<PrioritizedAwait>) {
+        self.prioritized.lock().unwrap().extend(awaits);
+    }
 }
 
 /// The Awaiter attempts to resolve a (set of) variables in its own thread.  
 /// Waits based on the `track_id`. Once an id is resolved, all items with lower id are considered
 /// resolved.
+///
+/// Clones share the registration, so a single awaiter can be waited on from
+/// several threads. All of them return once the awaited id is resolved.
 pub struct Awaiter<'a, T> {
     pub(crate) broker: &'a AwaitersBroker<T>,
     comms: &'a ResolverComms,
     track_id: T,
+    /// Set when the values were resolved ahead of the execution order, see
+    /// `AwaitersBroker::register_prioritized`.
+    resolved: Option<Arc<AtomicBool>>,
 }
 
 impl<'a, T> Awaiter<'a, T> {
@@ -77,28 +173,62 @@ impl<'a, T> Awaiter<'a, T> {
             broker,
             comms,
             track_id,
+            resolved: None,
+        }
+    }
+
+    fn resolved_ahead(&self) -> bool {
+        self.resolved
+            .as_ref()
+            .map_or(false, |x| x.load(Ordering::Acquire))
+    }
+}
+
+impl<'a, T: Copy> Clone for Awaiter<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            broker: self.broker,
+            comms: self.comms,
+            track_id: self.track_id,
+            resolved: self.resolved.clone(),
         }
     }
 }
 
+// Safety: Waiting only loads the broker's atomics and calls the observer,
+// which is `Send + Sync` and isn't replaced while there are awaiters. The
+// broker stats are touched only by `register`, and the panic payload is
+// behind a mutex.
+unsafe impl<'a, T: TrackId> Send for Awaiter<'a, T> {}
+unsafe impl<'a, T: TrackId> Sync for Awaiter<'a, T> {}
+
 impl<'a, T: TrackId> crate::dag::Awaiter<'a> for Awaiter<'a, T> {
     fn wait(&self) {
         let iterations = 0;
 
         loop {
-            if self.broker.max_resolved.load(Ordering::Relaxed).to(T::from) >= self.track_id {
+            if self.broker.max_resolved.load(Ordering::Relaxed).to(T::from) >= self.track_id
+                || self.resolved_ahead()
+            {
                 break;
             }
 
             if self.comms.rw_panicked.load(Ordering::Relaxed) {
-                if let Some(e) = self.comms.rw_panic.take() {
+                // The payload is taken by whoever observes the panic first,
+                // the rest still mustn't treat the value as resolved.
+                let panic = self.comms.rw_panic.lock().unwrap().take();
+
+                if let Some(e) = panic {
                     resume_unwind(e);
                 } else {
-                    log!("Resolution window panicked, but no panic payload stored.");
-                    return;
+                    panic!("Resolution window panicked before the awaited value was resolved.");
                 }
             }
 
+            if self.comms.cancelled.load(Ordering::Relaxed) {
+                panic!("Resolution was cancelled before the awaited value was resolved.");
+            }
+
             // TODO: This threshold is arbitrary. It should be tuned.
             if iterations > 1000 {
                 yield_now();
@@ -110,6 +240,10 @@ impl<'a, T: TrackId> crate::dag::Awaiter<'a> for Awaiter<'a, T> {
         // After waiting the client code will want to access the value, which
         // is written in another thread.
         fence(Ordering::Acquire);
+
+        if let Some(observer) = self.broker.observer() {
+            observer.on_wake(self.track_id.into());
+        }
     }
 }
 
@@ -61,6 +61,80 @@ impl From<GuideLoc> for usize {
 
 impl TrackId for GuideLoc {}
 
+/// A track id that is derived from the location in the guide.
+pub trait GuideTrackId: TrackId + From<GuideLoc> + Into<GuideLoc> + Send {
+    /// Checks that all locations of a resolver created with `opts` can be
+    /// represented.
+    fn fits(opts: &crate::dag::CircuitResolverOpts) -> bool;
+}
+
+impl GuideTrackId for GuideLoc {
+    fn fits(_opts: &crate::dag::CircuitResolverOpts) -> bool {
+        true
+    }
+}
+
+/// A `GuideLoc` packed into 32 bits, which halves the tracker size in the
+/// values metadata. The span id takes the high bits and the position the low
+/// ones, so the ordering is preserved. Suitable only for resolvers that pass
+/// `GuideTrackId::fits`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default, PartialOrd, Ord)]
+pub struct CompactGuideLoc(u32);
+
+impl CompactGuideLoc {
+    const POS_BITS: u32 = 12;
+    const POS_MASK: u32 = (1 << Self::POS_BITS) - 1;
+    const MAX_SPANS: u32 = 1 << (32 - Self::POS_BITS);
+}
+
+impl GuideTrackId for CompactGuideLoc {
+    // Each registration creates at most two spans and outputs at least one
+    // variable. A growing resolver has no upper bound on the variables.
+    fn fits(opts: &crate::dag::CircuitResolverOpts) -> bool {
+        opts.on_capacity_exceeded != crate::dag::CapacityPolicy::Grow
+            && opts.desired_parallelism < Self::POS_MASK
+            && (opts.max_variables as u64 * 2 + GUIDE_SIZE as u64) < Self::MAX_SPANS as u64
+    }
+}
+
+impl From<GuideLoc> for CompactGuideLoc {
+    fn from(value: GuideLoc) -> Self {
+        debug_assert!(value.pos <= Self::POS_MASK && value.id.0 < Self::MAX_SPANS);
+        Self(value.id.0 << Self::POS_BITS | value.pos)
+    }
+}
+
+impl From<CompactGuideLoc> for GuideLoc {
+    fn from(value: CompactGuideLoc) -> Self {
+        Self {
+            id: SpanId(value.0 >> CompactGuideLoc::POS_BITS),
+            pos: value.0 & CompactGuideLoc::POS_MASK,
+        }
+    }
+}
+
+impl From<u64> for CompactGuideLoc {
+    fn from(value: u64) -> Self {
+        // This trait will not fail under normal circumstances.
+        debug_assert!(value <= u32::MAX as u64);
+        Self(value as u32)
+    }
+}
+
+impl From<CompactGuideLoc> for u64 {
+    fn from(value: CompactGuideLoc) -> Self {
+        value.0 as u64
+    }
+}
+
+impl From<CompactGuideLoc> for usize {
+    fn from(value: CompactGuideLoc) -> Self {
+        value.0 as usize
+    }
+}
+
+impl TrackId for CompactGuideLoc {}
+
 // region: guide trait
 
 pub(crate) trait Guide<T, F: SmallField, Cfg: CSResolverConfig> {
@@ -350,7 +424,13 @@ where
     T: 'a,
 {
     fn size(&self) -> usize;
-    fn write(&self, target: &mut [OrderInfo<T>]) -> Range<OrderIx>;
+
+    fn write(&self, target: &mut [OrderInfo<T>]) -> Range<OrderIx> {
+        self.write_from(target, 0)
+    }
+
+    /// Same as `write`, but `target` starts at the order index `base`.
+    fn write_from(&self, target: &mut [OrderInfo<T>], base: usize) -> Range<OrderIx>;
 }
 
 pub(crate) struct BufferGuideOrder<'a, T: Debug, F: SmallField, Cfg: CSResolverConfig> {
@@ -370,16 +450,16 @@ impl<'a, T: Copy + Debug, F: SmallField, Cfg: CSResolverConfig> GuideOrder<'a, T
             .sum::<usize>()
     }
 
-    fn write(&self, target: &mut [OrderInfo<T>]) -> Range<OrderIx> {
+    fn write_from(&self, target: &mut [OrderInfo<T>], base: usize) -> Range<OrderIx> {
         if self.released_spans == 0 {
             return self.guide.next_target.index..self.guide.next_target.index;
         }
 
         let start = self.guide.next_target.index;
-        let mut pos = self.guide.next_target.index.into();
+        let mut pos: usize = self.guide.next_target.index.into();
 
         for span in &self.guide.spans[0..self.released_spans as usize] {
-            target[pos..pos + span.buffer.len()].copy_from_slice(&span.buffer);
+            target[pos - base..pos - base + span.buffer.len()].copy_from_slice(&span.buffer);
 
             pos += span.buffer.len();
         }
@@ -425,14 +505,14 @@ impl<'a, T: Copy + Debug, F: SmallField, Cfg: CSResolverConfig> GuideOrder<'a, T
             .sum::<usize>()
     }
 
-    fn write(&self, target: &mut [OrderInfo<T>]) -> Range<OrderIx> {
+    fn write_from(&self, target: &mut [OrderInfo<T>], base: usize) -> Range<OrderIx> {
         // Not using the pointer cause after this no modifications are
         // allowed anyway, so we don't care about preserving invariants.
         let start = self.guide.next_target.index;
-        let mut pos = start.into();
+        let mut pos: usize = start.into();
 
         for span in &self.guide.spans {
-            target[pos..pos + span.buffer.len()].copy_from_slice(&span.buffer);
+            target[pos - base..pos - base + span.buffer.len()].copy_from_slice(&span.buffer);
 
             pos += span.buffer.len();
         }
@@ -907,7 +987,7 @@ impl std::ops::Add<u32> for &Pointer {
 mod general_tests {
     use crate::dag::guide::SpanId;
 
-    use super::GuideLoc;
+    use super::{CompactGuideLoc, GuideLoc};
 
     #[test]
     fn guide_loc_ordering() {
@@ -940,6 +1020,23 @@ mod general_tests {
             })
         );
     }
+
+    #[test]
+    fn compact_guide_loc_preserves_ordering() {
+        let locs =
+            [(0, 0), (0, 1), (0, 4095), (1, 0), (1, 2), (1 << 19, 7)].map(|(id, pos)| GuideLoc {
+                id: SpanId(id),
+                pos,
+            });
+
+        for loc in locs {
+            assert_eq!(loc, GuideLoc::from(CompactGuideLoc::from(loc)));
+        }
+
+        for pair in locs.windows(2) {
+            assert!(CompactGuideLoc::from(pair[0]) < CompactGuideLoc::from(pair[1]));
+        }
+    }
 }
 
 #[cfg(test)]
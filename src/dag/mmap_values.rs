@@ -0,0 +1,98 @@
+use std::cell::UnsafeCell;
+use std::fs::OpenOptions;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::path::Path;
+
+use memmap2::MmapMut;
+
+use crate::field::SmallField;
+
+use super::guide::{CompactGuideLoc, GuideLoc};
+use super::primitives::{Metadata, ValueStorage, ValueStore};
+
+/// Track ids whose default metadata is all zero bytes, so the cells of a
+/// zero-filled file hold the default metadata.
+///
+/// # Safety
+///
+/// The all-zero byte pattern must be a valid `Metadata<Self>` equal to
+/// `Metadata::default()`.
+pub unsafe trait ZeroedTrackId: Default + Copy {}
+
+// Safety: Both are plain integers with the zero default, as is the metadata
+// data.
+unsafe impl ZeroedTrackId for GuideLoc {}
+unsafe impl ZeroedTrackId for CompactGuideLoc {}
+
+/// Keeps the resolver values in a memory-mapped file, so the OS can page
+/// them out for witnesses that don't fit into memory. The metadata of a value
+/// is stored next to it, as the resolver accesses both through the same cell.
+///
+/// The store lives in the file only as long as the resolver keeps it. Both
+/// growing it under `CapacityPolicy::Grow` and `into_value_storage` copy the
+/// whole store into memory.
+pub struct MmapValueStore<V, T> {
+    mmap: MmapMut,
+    len: usize,
+    phantom: PhantomData<(V, T)>,
+}
+
+impl<V: SmallField, T: ZeroedTrackId> MmapValueStore<V, T> {
+    /// Creates the file at `path`, truncating an existing one, and maps `len`
+    /// values from it. The file is zero-filled, which is a valid field
+    /// element and the default metadata.
+    pub fn create(path: impl AsRef<Path>, len: usize) -> std::io::Result<Self> {
+        let size = len
+            .checked_mul(size_of::<(V, Metadata<T>)>())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} values don't fit into the address space.", len),
+                )
+            })?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        file.set_len(size as u64)?;
+
+        // Safety: The file was just truncated, and is accessed only through
+        // the mapping while the store is alive.
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        Ok(Self {
+            mmap,
+            len,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Writes the modified pages back to the file.
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.mmap.flush()
+    }
+}
+
+// Safety: The mapping isn't moved or remapped while the store is alive, and
+// a zero-filled cell holds a valid value with the default metadata, see
+// `ZeroedTrackId`.
+unsafe impl<V: SmallField, T: ZeroedTrackId> ValueStore<V, T> for MmapValueStore<V, T> {
+    fn cells(&mut self) -> &mut [UnsafeCell<(V, Metadata<T>)>] {
+        // Safety: The mapping is page aligned and holds `len` cells.
+        unsafe { std::slice::from_raw_parts_mut(self.mmap.as_mut_ptr() as *mut _, self.len) }
+    }
+
+    fn into_storage(mut self: Box<Self>) -> ValueStorage<V, T> {
+        self.cells()
+            .iter()
+            // Safety: The values and metadata are plain data, and the mapping
+            // is dropped without dropping the cells.
+            .map(|x| UnsafeCell::new(unsafe { std::ptr::read(x.get()) }))
+            .collect()
+    }
+}
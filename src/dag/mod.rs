@@ -1,3 +1,8 @@
+pub use self::awaiters::AwaiterObserver;
+pub use self::guide::{CompactGuideLoc, GuideMetadata, GuideTrackId, OrderInfo};
+#[cfg(feature = "mmap-values")]
+pub use self::mmap_values::{MmapValueStore, ZeroedTrackId};
+pub use self::primitives::{Metadata, ResolverIx, ValueStorage, ValueStore};
 use self::resolvers::mt::sorters::sorter_live::LiveResolverSorter;
 use std::fmt::Debug;
 use std::hint::spin_loop;
@@ -7,10 +12,12 @@ use std::sync::Arc;
 use crate::config::CSResolverConfig;
 use crate::cs::traits::cs::{CSWitnessSource, DstBuffer};
 use crate::cs::Place;
-use crate::field::SmallField;
+use crate::field::{ExtensionField, FieldExtension, SmallField};
 
 mod awaiters;
 mod guide;
+#[cfg(feature = "mmap-values")]
+mod mmap_values;
 mod primitives;
 mod resolver_box;
 pub mod resolvers;
@@ -22,6 +29,67 @@ pub trait TrivialWitnessCastable<F: SmallField, const N: usize>:
     fn cast_into_field_elements(self) -> [F; N];
 }
 
+impl<F: SmallField, E: FieldExtension<2, BaseField = F> + 'static + Send + Sync>
+    TrivialWitnessCastable<F, 2> for ExtensionField<F, 2, E>
+{
+    fn cast_from_field_elements(parts: [F; 2]) -> Self {
+        Self::from_coeff_in_base(parts)
+    }
+
+    fn cast_into_field_elements(self) -> [F; 2] {
+        self.into_coeffs_in_base()
+    }
+}
+
+/// The places of a value of type `T` stored across `N` places, see
+/// `MtCircuitResolver::register_castable_output`. Reads the value back without
+/// tracking which places hold which limbs.
+pub struct ResolvedHandle<T, const N: usize> {
+    places: [Place; N],
+    phantom: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T, const N: usize> ResolvedHandle<T, N> {
+    pub fn new(places: [Place; N]) -> Self {
+        Self {
+            places,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    pub fn places(&self) -> [Place; N] {
+        self.places
+    }
+
+    /// The value, or `None` if any of its places isn't resolved yet.
+    pub fn get<F: SmallField, S: WitnessSource<F>>(&self, source: &S) -> Option<T>
+    where
+        T: TrivialWitnessCastable<F, N>,
+    {
+        let mut parts = [F::ZERO; N];
+
+        for (part, place) in parts.iter_mut().zip(self.places) {
+            *part = source.try_get_value(place)?;
+        }
+
+        Some(T::cast_from_field_elements(parts))
+    }
+}
+
+impl<T, const N: usize> Clone for ResolvedHandle<T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const N: usize> Copy for ResolvedHandle<T, N> {}
+
+impl<T, const N: usize> Debug for ResolvedHandle<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ResolvedHandle").field(&self.places).finish()
+    }
+}
+
 pub enum CSWitnessValues<F: SmallField, const N: usize, S: WitnessSource<F>> {
     Placeholder,
     Ready([F; N]),
@@ -100,31 +168,522 @@ pub trait Awaiter<'a> {
 pub struct CircuitResolverOpts {
     pub max_variables: usize,
     pub desired_parallelism: u32,
+    /// Spreads the first touch of the values storage across all available
+    /// cores, so on multi-socket machines the pages are distributed between
+    /// the NUMA nodes instead of landing on the node of the allocating thread.
+    /// With `worker_affinity` set, the values are touched from the CPUs of
+    /// the set instead, so the pages land on the nodes of the workers. Has
+    /// effect only with the `numa` feature, which enables `worker_affinity`
+    /// for the pinning.
+    pub numa_first_touch: bool,
+    /// CPUs to pin the resolution window workers to. The workers are pinned
+    /// to the CPUs of the set in ascending order of their ids, one each, and
+    /// the workers beyond the set size are left unpinned. Has effect only
+    /// with the `worker_affinity` feature.
+    pub worker_affinity: Option<CpuSet>,
+    pub on_capacity_exceeded: CapacityPolicy,
+    pub duplicate_set_policy: DuplicateSetPolicy,
+    /// The number of resolvers the sorter accumulates before publishing them
+    /// to the resolution window, which takes the execution order lock. Larger
+    /// batches reduce the lock traffic at the cost of latency. Flushes, e.g.
+    /// on awaiter requests, publish regardless.
+    pub internalize_batch_size: usize,
+    /// Counts, for each place, the resolutions that take it as an input, see
+    /// `MtCircuitResolver::fanout`. Costs a `u32` per place of
+    /// `max_variables` and an extra write per input on registration.
+    pub track_fanout: bool,
+    /// Collects the edges from the inputs to the outputs of each resolution,
+    /// see `MtCircuitResolver::dependency_graph`. Costs two adjacency list
+    /// entries per edge, so it's meant for analysis rather than production
+    /// runs.
+    pub track_dependencies: bool,
+    /// Stores the resolved values in the record, so a playback of the record
+    /// with the same inputs checks that it resolves the same values. Catches
+    /// resolutions that aren't deterministic. Costs a `u64` per value in the
+    /// record.
+    pub verify_playback: bool,
+    /// Blocks `add_resolution` while more than this many resolvers in the
+    /// execution order wait to be resolved, until half of them are. Keeps the
+    /// registration from running arbitrarily far ahead of the resolution, at
+    /// the cost of registration throughput. The resolvers themselves are kept
+    /// until `release_resolvers` regardless.
+    pub max_pending_resolvers: Option<usize>,
+    /// Seeds the order in which registrations, delayed until their inputs
+    /// are tracked, are internalized once several of them become ready at
+    /// once. That order is the only scheduling choice that isn't determined
+    /// by the registrations, so two recordings of the same circuit with the
+    /// same seed produce identical records. Without a seed, each resolver
+    /// picks a random one. The resolution window executes the recorded order
+    /// and doesn't make choices of its own.
+    pub scheduling_seed: Option<u64>,
+    pub on_resolver_panic: PanicPolicy,
+    /// How the resolution window waits while it has no resolvers to execute,
+    /// see `IdlePolicy`.
+    pub window_idle_policy: IdlePolicy,
+    /// Makes the resolution window resolve awaited values ahead of the
+    /// execution order. Between two batches it invokes the unresolved
+    /// resolvers the awaited places depend on, so an awaiter doesn't wait
+    /// for the unrelated resolvers ordered before them. Costs a search of the
+    /// window buffer for each awaiter.
+    pub prioritize_awaited: bool,
+    /// Suppresses all the output of the resolver, including the stats logged
+    /// by `wait_till_resolved` and the output of test and debug builds.
+    pub silent: bool,
+}
+
+/// What `set_value` does for a place that already has a value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateSetPolicy {
+    #[default]
+    Panic,
+    /// Ignores setting the same value again, but panics on a different one.
+    IgnoreIfEqual,
+    /// Replaces the value. Resolutions that already consumed the previous
+    /// value are not invoked again. Waits for the resolvers already in the
+    /// execution order to finish first.
+    Overwrite,
+}
+
+/// What the resolution window does when a resolution panics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Stops the resolution, the panic is resumed on the thread waiting for
+    /// it.
+    #[default]
+    Abort,
+    /// Marks the outputs of the resolution as failed and carries on. The
+    /// resolutions taking a failed value as input fail without being invoked.
+    /// The failed registrations are listed by
+    /// `MtCircuitResolver::failed_registrations`. The awaiters of failed
+    /// values return, the values themselves stay unresolved.
+    SkipAndRecord,
+}
+
+/// How the resolution window waits for new resolvers while it has none to
+/// execute. Trades the latency of picking up new resolvers against the CPU
+/// used by an idle window, which matters for workloads registering slowly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdlePolicy {
+    /// Busy-waits, picking new resolvers up the soonest, but occupies a core
+    /// while idle.
+    Spin,
+    /// Yields to the scheduler between checks. Still occupies a core that
+    /// no other thread wants, but gives way to the ones that do.
+    #[default]
+    Yield,
+    /// Sleeps for the duration between checks. Uses next to no CPU while
+    /// idle, but picks new resolvers up as late as the duration.
+    Sleep(std::time::Duration),
+}
+
+/// A set of CPU ids below `CpuSet::MAX_CPUS`, see
+/// `CircuitResolverOpts::worker_affinity`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CpuSet {
+    bits: [u64; CpuSet::MAX_CPUS / 64],
 }
 
+impl CpuSet {
+    pub const MAX_CPUS: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, id: usize) {
+        assert!(
+            id < Self::MAX_CPUS,
+            "CPU id {} exceeds the supported {}.",
+            id,
+            Self::MAX_CPUS
+        );
+
+        self.bits[id / 64] |= 1 << (id % 64);
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        id < Self::MAX_CPUS && self.bits[id / 64] & (1 << (id % 64)) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|x| *x == 0)
+    }
+
+    /// The ids in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..Self::MAX_CPUS).filter(|x| self.contains(*x))
+    }
+}
+
+impl FromIterator<usize> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        iter.into_iter().for_each(|x| set.insert(x));
+        set
+    }
+}
+
+impl Debug for CpuSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// The scheduling priority of a resolution, see
+/// `MtCircuitResolver::add_resolution_prioritized`. Ordered from the highest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+/// What the resolver does when a place beyond `max_variables` is registered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CapacityPolicy {
+    #[default]
+    Panic,
+    /// Grows the values storage, at least doubling it. Waits for the
+    /// resolvers already in the execution order to finish first.
+    Grow,
+    /// Makes the fallible registration functions return `CapacityExceeded`.
+    /// The infallible ones panic.
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityExceeded {
+    pub place: Place,
+    pub capacity: usize,
+}
+
+impl std::fmt::Display for CapacityExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Place {:?} exceeds the resolver capacity of {} values.",
+            self.place, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for CapacityExceeded {}
+
 impl CircuitResolverOpts {
     pub fn new(max_variables: usize) -> Self {
+        Self::builder(max_variables).build()
+    }
+
+    pub fn builder(max_variables: usize) -> CircuitResolverOptsBuilder {
+        CircuitResolverOptsBuilder {
+            opts: Self {
+                max_variables,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Builds `CircuitResolverOpts`, checking the options on `build`. Options that
+/// aren't set keep their default values.
+#[derive(Clone, Debug)]
+pub struct CircuitResolverOptsBuilder {
+    opts: CircuitResolverOpts,
+}
+
+impl CircuitResolverOptsBuilder {
+    pub fn desired_parallelism(mut self, value: u32) -> Self {
+        self.opts.desired_parallelism = value;
+        self
+    }
+
+    pub fn numa_first_touch(mut self, value: bool) -> Self {
+        self.opts.numa_first_touch = value;
+        self
+    }
+
+    pub fn worker_affinity(mut self, value: impl IntoIterator<Item = usize>) -> Self {
+        self.opts.worker_affinity = Some(value.into_iter().collect());
+        self
+    }
+
+    pub fn on_capacity_exceeded(mut self, value: CapacityPolicy) -> Self {
+        self.opts.on_capacity_exceeded = value;
+        self
+    }
+
+    pub fn duplicate_set_policy(mut self, value: DuplicateSetPolicy) -> Self {
+        self.opts.duplicate_set_policy = value;
+        self
+    }
+
+    pub fn internalize_batch_size(mut self, value: usize) -> Self {
+        self.opts.internalize_batch_size = value;
+        self
+    }
+
+    pub fn track_fanout(mut self, value: bool) -> Self {
+        self.opts.track_fanout = value;
+        self
+    }
+
+    pub fn track_dependencies(mut self, value: bool) -> Self {
+        self.opts.track_dependencies = value;
+        self
+    }
+
+    pub fn verify_playback(mut self, value: bool) -> Self {
+        self.opts.verify_playback = value;
+        self
+    }
+
+    pub fn max_pending_resolvers(mut self, value: Option<usize>) -> Self {
+        self.opts.max_pending_resolvers = value;
+        self
+    }
+
+    pub fn scheduling_seed(mut self, value: Option<u64>) -> Self {
+        self.opts.scheduling_seed = value;
+        self
+    }
+
+    pub fn on_resolver_panic(mut self, value: PanicPolicy) -> Self {
+        self.opts.on_resolver_panic = value;
+        self
+    }
+
+    pub fn window_idle_policy(mut self, value: IdlePolicy) -> Self {
+        self.opts.window_idle_policy = value;
+        self
+    }
+
+    pub fn prioritize_awaited(mut self, value: bool) -> Self {
+        self.opts.prioritize_awaited = value;
+        self
+    }
+
+    pub fn silent(mut self, value: bool) -> Self {
+        self.opts.silent = value;
+        self
+    }
+
+    pub fn build(self) -> CircuitResolverOpts {
+        assert!(
+            self.opts.max_variables > 0,
+            "`max_variables` must be positive."
+        );
+        assert!(
+            self.opts.desired_parallelism > 0,
+            "`desired_parallelism` must be positive."
+        );
+        assert!(
+            self.opts.internalize_batch_size > 0,
+            "`internalize_batch_size` must be positive."
+        );
+        assert!(
+            self.opts.max_pending_resolvers != Some(0),
+            "`max_pending_resolvers` must be positive."
+        );
+
+        self.opts
+    }
+}
+
+impl Default for CircuitResolverOpts {
+    fn default() -> Self {
         Self {
-            max_variables,
+            max_variables: 0,
             desired_parallelism: 1 << 12,
+            numa_first_touch: false,
+            worker_affinity: None,
+            on_capacity_exceeded: CapacityPolicy::Panic,
+            duplicate_set_policy: DuplicateSetPolicy::Panic,
+            internalize_batch_size: 1,
+            track_fanout: false,
+            track_dependencies: false,
+            verify_playback: false,
+            max_pending_resolvers: None,
+            scheduling_seed: None,
+            on_resolver_panic: PanicPolicy::Abort,
+            window_idle_policy: IdlePolicy::Yield,
+            prioritize_awaited: false,
+            silent: false,
         }
     }
 }
 
 impl From<usize> for CircuitResolverOpts {
     fn from(value: usize) -> Self {
-        Self {
-            max_variables: value,
-            desired_parallelism: 1 << 12,
+        Self::new(value)
+    }
+}
+
+/// Reasons a value can't be read from a resolver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GetValueError {
+    /// The place is beyond `max_variables`.
+    OutOfRange,
+    /// The value was neither set nor is an output of a registered resolution.
+    NotTracked,
+    /// The value will be produced by a resolution that hasn't run yet.
+    NotResolved,
+    /// The value was declared as a hole, so it's never computed.
+    Hole,
+}
+
+impl std::fmt::Display for GetValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfRange => write!(f, "place is out of the resolver range"),
+            Self::NotTracked => write!(f, "value is not tracked by the resolver"),
+            Self::NotResolved => write!(f, "value is not resolved yet"),
+            Self::Hole => write!(f, "value is declared as a hole"),
         }
     }
 }
 
+impl std::error::Error for GetValueError {}
+
+/// The failures reported by the fallible resolver functions. Their infallible
+/// counterparts panic with the same message.
+pub enum CircuitResolverError {
+    /// The requested place is beyond the contiguous range of tracked values,
+    /// so it can't be computed with the current registrations. `max_tracked`
+    /// is `None` when nothing is tracked yet.
+    Hole {
+        requested: Place,
+        max_tracked: Option<Place>,
+    },
+    /// Registrations that wait on each other's outputs, so none of them can
+    /// be resolved. `places` are the awaited places along the cycle.
+    Cycle {
+        places: Vec<Place>,
+    },
+    CapacityExceeded(CapacityExceeded),
+    /// The value at `place` is already set or resolved, and the
+    /// `DuplicateSetPolicy` doesn't allow setting it again.
+    DuplicateValue {
+        place: Place,
+    },
+    /// Values and resolutions can't be added after `wait_till_resolved` or
+    /// `cancel`.
+    RegistrationClosed,
+    /// The place was declared with `declare_tracked_prefix`, but isn't set
+    /// yet, so it can't be used as an input or awaited.
+    NotSet {
+        place: Place,
+    },
+    /// The place was declared with `declare_hole`, so it's never computed and
+    /// can't be used as an input or awaited.
+    DeclaredHole {
+        place: Place,
+    },
+    /// The resolution window panicked, with the panic payload.
+    WindowPanicked(Box<dyn std::any::Any + Send + 'static>),
+}
+
+impl std::fmt::Debug for CircuitResolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CircuitResolverError({})", self)
+    }
+}
+
+impl std::fmt::Display for CircuitResolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hole {
+                requested,
+                max_tracked,
+            } => write!(
+                f,
+                "The awaiter will never resolve since the awaited variable can't be computed \
+                 based on currently available registrations. You have holes!!! \
+                 Requested {:?}, max tracked {:?}.",
+                requested, max_tracked
+            ),
+            Self::Cycle { places } => write!(
+                f,
+                "Registrations wait on each other's outputs in a cycle through {:?}.",
+                places
+            ),
+            Self::CapacityExceeded(e) => write!(f, "{}", e),
+            Self::DuplicateValue { place } => write!(f, "Value at {:?} is already set.", place),
+            Self::RegistrationClosed => write!(
+                f,
+                "Registration is closed, values and resolutions can't be added after \
+                 `wait_till_resolved` or `cancel`."
+            ),
+            Self::NotSet { place } => write!(
+                f,
+                "Place {:?} is declared as tracked, but isn't set yet. Set it before using it.",
+                place
+            ),
+            Self::DeclaredHole { place } => write!(
+                f,
+                "Place {:?} is declared as a hole, it can't be used as an input or awaited.",
+                place
+            ),
+            Self::WindowPanicked(payload) => {
+                match payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(|x| x.as_str()))
+                {
+                    Some(msg) => write!(f, "Resolution window panicked: {}", msg),
+                    None => write!(f, "Resolution window panicked."),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for CircuitResolverError {}
+
+impl From<CapacityExceeded> for CircuitResolverError {
+    fn from(value: CapacityExceeded) -> Self {
+        Self::CapacityExceeded(value)
+    }
+}
+
+/// Whether a value at a place is known to the resolver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaceStatus {
+    /// The value was neither set nor is an output of a registered resolution.
+    Untracked,
+    /// The value will be produced by a registered resolution that hasn't run
+    /// yet.
+    Tracked,
+    Resolved,
+    /// The resolution producing the value panicked, or one of its inputs
+    /// failed, see `PanicPolicy::SkipAndRecord`.
+    Failed,
+    /// The value was declared as never computed, see `declare_hole`.
+    Hole,
+}
+
 pub trait TrackId:
     From<u64> + Into<u64> + Into<usize> + Eq + Ord + Debug + Default + Clone + Copy
 {
 }
 
+/// Resolves the values of a single field `F`.
+///
+/// Values of other types, like extension field elements, are stored as
+/// several places holding their base field coefficients. The resolution
+/// functions combine and split them, and the resolved value is read back with
+/// `TrivialWitnessCastable`. A dedicated value type, e.g. an enum over the
+/// base and extension fields, is not supported:
+/// - The values storage is a flat array of `F` with the metadata inlined, so a
+///   wider type would enlarge every cell, including the base field ones.
+/// - `DstBuffer` writes directly into that storage, and the resolution
+///   functions are monomorphized over `F`, so both would need the sum type.
+/// - `SmallField` requires a prime field of a small characteristic, which
+///   neither an extension nor a sum type is.
 pub trait CircuitResolver<F: SmallField, Cfg: CSResolverConfig>:
     WitnessSource<F> + WitnessSourceAwaitable<F> + CSWitnessSource<F> + Send + Sync
 {
@@ -132,6 +691,8 @@ pub trait CircuitResolver<F: SmallField, Cfg: CSResolverConfig>:
 
     fn new(args: Self::Arg) -> Self;
     fn set_value(&mut self, key: Place, value: F);
+    /// `inputs` may be empty, in which case the resolution doesn't wait for
+    /// any values and is scheduled right away.
     fn add_resolution<Fn>(&mut self, inputs: &[Place], outputs: &[Place], f: Fn)
     where
         Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync;
@@ -141,8 +702,16 @@ pub trait CircuitResolver<F: SmallField, Cfg: CSResolverConfig>:
 
 pub type NullCircuitResolver<F, CFG> = resolvers::NullCircuitResolver<F, CFG>;
 
+/// Records the structure of the registrations without computing values, see
+/// `ResolutionStructure`.
+pub type AnalysisCircuitResolver<F, CFG> = resolvers::AnalysisCircuitResolver<F, CFG>;
+
 pub type StCircuitResolver<F, CFG> = resolvers::StCircuitResolver<F, CFG>;
 pub type MtCircuitResolver<F, CFG> =
     resolvers::MtCircuitResolver<F, LiveResolverSorter<F, CFG>, CFG>;
 
+/// Uses a 32 bit tracker per value, see `CompactGuideLoc`.
+pub type CompactMtCircuitResolver<F, CFG> =
+    resolvers::MtCircuitResolver<F, LiveResolverSorter<F, CFG, CompactGuideLoc>, CFG>;
+
 pub type DefaultCircuitResolver<F, CFG> = MtCircuitResolver<F, CFG>;
@@ -1,5 +1,5 @@
 use std::cell::UnsafeCell;
-use std::ops::{Add, AddAssign, Sub};
+use std::ops::{Add, AddAssign, Deref, DerefMut, Sub};
 
 use crate::cs::{Place, Variable};
 use crate::utils::PipeOp as _;
@@ -8,10 +8,90 @@ use super::guide::OrderInfo;
 use super::TrackId;
 
 pub struct Values<V, T: Default> {
-    pub(crate) variables: Box<[UnsafeCell<(V, Metadata<T>)>]>,
+    pub(crate) variables: ValueCells<V, T>,
     pub(crate) max_tracked: i64, // Be sure to not overflow.
 }
 
+/// Memory the resolver values are kept in, see
+/// `MtCircuitResolver::with_value_store`.
+///
+/// # Safety
+///
+/// `cells` must return the same cells on every call while the store is
+/// alive, as the resolver keeps a pointer to them and accesses them
+/// unsynchronized from its threads. The cells must initially hold the default
+/// metadata.
+pub unsafe trait ValueStore<V, T: Default> {
+    fn cells(&mut self) -> &mut [UnsafeCell<(V, Metadata<T>)>];
+
+    /// Moves the cells into an own allocation. Used when the resolver grows
+    /// the storage, and by `MtCircuitResolver::into_value_storage`.
+    fn into_storage(self: Box<Self>) -> ValueStorage<V, T>;
+}
+
+// Safety: The boxed slice isn't reallocated while it's held.
+unsafe impl<V, T: Default> ValueStore<V, T> for ValueStorage<V, T> {
+    fn cells(&mut self) -> &mut [UnsafeCell<(V, Metadata<T>)>] {
+        self
+    }
+
+    fn into_storage(self: Box<Self>) -> ValueStorage<V, T> {
+        *self
+    }
+}
+
+/// The cells of a `ValueStore`. Derefs to them directly rather than through
+/// the store, as they're accessed on every read and write of a value.
+pub(crate) struct ValueCells<V, T: Default> {
+    ptr: *mut UnsafeCell<(V, Metadata<T>)>,
+    len: usize,
+    store: Box<dyn ValueStore<V, T>>,
+}
+
+impl<V, T: Default> ValueCells<V, T> {
+    pub(crate) fn new(mut store: Box<dyn ValueStore<V, T>>) -> Self {
+        let cells = store.cells();
+
+        Self {
+            ptr: cells.as_mut_ptr(),
+            len: cells.len(),
+            store,
+        }
+    }
+
+    pub(crate) fn into_storage(self) -> ValueStorage<V, T> {
+        self.store.into_storage()
+    }
+}
+
+impl<V: 'static, T: Default + 'static> From<ValueStorage<V, T>> for ValueCells<V, T> {
+    fn from(storage: ValueStorage<V, T>) -> Self {
+        Self::new(Box::new(storage))
+    }
+}
+
+impl<V: 'static, T: Default + 'static> Default for ValueCells<V, T> {
+    fn default() -> Self {
+        Self::from(ValueStorage::default())
+    }
+}
+
+impl<V, T: Default> Deref for ValueCells<V, T> {
+    type Target = [UnsafeCell<(V, Metadata<T>)>];
+
+    fn deref(&self) -> &Self::Target {
+        // Safety: The store keeps the cells at the same place while it's held.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<V, T: Default> DerefMut for ValueCells<V, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Safety: Same as in `deref`.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
 impl<V, T: Default + Copy> Values<V, T> {
     pub(crate) fn resolve_type(&self, _key: Place) -> &[UnsafeCell<(V, Metadata<T>)>] {
         &self.variables
@@ -54,7 +134,14 @@ impl<V, T: Default + Copy> Values<V, T> {
         // If the item was already set, we panic in the next line.
         let (v, md) = unsafe { self.get_item_ref_mut(key) };
 
-        if md.is_tracked() {
+        if md.is_hole() {
+            panic!(
+                "Value with index {} is declared as a hole",
+                key.as_any_index()
+            )
+        }
+
+        if md.is_tracked() && md.is_declared() == false {
             panic!("Value with index {} is already set", key.as_any_index())
         }
 
@@ -63,6 +150,70 @@ impl<V, T: Default + Copy> Values<V, T> {
         self.advance_track();
     }
 
+    /// Marks the untracked values among the first `len` as tracked ahead of
+    /// being set, and moves `max_tracked` past them at once.
+    pub(crate) fn declare_prefix(&mut self, len: usize) {
+        assert!(
+            len <= self.variables.len(),
+            "Declared prefix of {} values exceeds the capacity of {}.",
+            len,
+            self.variables.len()
+        );
+
+        for cell in self.variables[..len].iter_mut() {
+            let md = &mut cell.get_mut().1;
+
+            if md.is_tracked() == false {
+                *md = Metadata::new_declared();
+            }
+        }
+
+        self.max_tracked = self.max_tracked.max(len as i64 - 1);
+
+        self.advance_track();
+    }
+
+    /// Marks an untracked value as never computed, so `max_tracked` can move
+    /// past it.
+    pub(crate) fn declare_hole(&mut self, key: Place) {
+        // Safety: The value is untracked, so it isn't used.
+        let (_, md) = unsafe { self.get_item_ref_mut(key) };
+
+        if md.is_tracked() {
+            panic!("Value with index {} is already tracked", key.as_any_index())
+        }
+
+        *md = Metadata::new_hole();
+
+        self.advance_track();
+    }
+
+    /// Extends the storage to `len` untracked values. The values are moved
+    /// into an own allocation, whatever store they were kept in.
+    pub(crate) fn grow(&mut self, len: usize, default: impl Fn() -> V)
+    where
+        V: 'static,
+        T: 'static,
+    {
+        let mut variables = std::mem::take(&mut self.variables)
+            .into_storage()
+            .into_vec();
+
+        variables.resize_with(len, || UnsafeCell::new((default(), Metadata::default())));
+
+        self.variables = variables.into_boxed_slice().into();
+    }
+
+    /// Drops the metadata of all values, making them untracked. The values
+    /// themselves are left as is, they are overwritten on the next set.
+    pub(crate) fn reset(&mut self) {
+        for cell in self.variables.iter_mut() {
+            cell.get_mut().1 = Metadata::default();
+        }
+
+        self.max_tracked = -1;
+    }
+
     pub(crate) fn advance_track(&mut self) {
         for i in (self.max_tracked + 1)..self.variables.len() as i64 {
             if i.to(std::convert::TryInto::<u64>::try_into)
@@ -83,11 +234,14 @@ impl<V, T: Default + Copy> Values<V, T> {
 
 type Mdd = u16;
 
+/// Storage of the resolver values, see `MtCircuitResolver::with_value_storage`.
+pub type ValueStorage<V, T> = Box<[UnsafeCell<(V, Metadata<T>)>]>;
+
 #[derive(Default)]
 // Used by the resolver for internal tracking purposes.
-pub(crate) struct Metadata<T: Default> {
+pub struct Metadata<T: Default> {
     data: Mdd,
-    pub tracker: T,
+    pub(crate) tracker: T,
 }
 
 impl<T: Default> Metadata<T> {
@@ -95,6 +249,12 @@ impl<T: Default> Metadata<T> {
     const TRACKED_MASK: Mdd = 0b1000_0000_0000_0000;
     // Means this element was resolved and it's value is set.
     const RESOLVED_MASK: Mdd = 0b0100_0000_0000_0000;
+    // Means the resolution of this element panicked and it won't be resolved.
+    const FAILED_MASK: Mdd = 0b0010_0000_0000_0000;
+    // Means this element was declared as tracked ahead of being set.
+    const DECLARED_MASK: Mdd = 0b0001_0000_0000_0000;
+    // Means this element is intentionally never computed.
+    const HOLE_MASK: Mdd = 0b0000_1000_0000_0000;
 
     pub(crate) fn new(tracker: T) -> Self {
         Self {
@@ -103,6 +263,20 @@ impl<T: Default> Metadata<T> {
         }
     }
 
+    pub(crate) fn new_declared() -> Self {
+        Self {
+            data: Self::TRACKED_MASK | Self::DECLARED_MASK,
+            tracker: T::default(),
+        }
+    }
+
+    pub(crate) fn new_hole() -> Self {
+        Self {
+            data: Self::TRACKED_MASK | Self::HOLE_MASK,
+            tracker: T::default(),
+        }
+    }
+
     pub(crate) fn new_resolved() -> Self {
         Self {
             data: Self::TRACKED_MASK | Self::RESOLVED_MASK,
@@ -124,6 +298,24 @@ impl<T: Default> Metadata<T> {
         // TODO: separate the resolver implementations.
         self.data |= Self::RESOLVED_MASK | Self::TRACKED_MASK;
     }
+
+    /// Whether this element was declared as tracked and isn't set yet.
+    pub fn is_declared(&self) -> bool {
+        self.data & Self::DECLARED_MASK != 0
+    }
+
+    /// Whether this element was declared as never computed.
+    pub fn is_hole(&self) -> bool {
+        self.data & Self::HOLE_MASK != 0
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.data & Self::FAILED_MASK != 0
+    }
+
+    pub(crate) fn mark_failed(&mut self) {
+        self.data |= Self::FAILED_MASK;
+    }
 }
 
 #[derive(Debug)]
@@ -167,19 +359,44 @@ impl From<u32> for OrderIx {
     }
 }
 
+/// The value doesn't fit into an `OrderIx`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderIxOverflow(pub u64);
+
+impl std::fmt::Display for OrderIxOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "order index {} exceeds {}", self.0, u32::MAX - 1)
+    }
+}
+
+impl std::error::Error for OrderIxOverflow {}
+
+impl OrderIx {
+    pub fn try_new(value: u64) -> Result<Self, OrderIxOverflow> {
+        match value < u32::MAX as u64 {
+            true => Ok(Self(value as u32)),
+            false => Err(OrderIxOverflow(value)),
+        }
+    }
+
+    /// Panics on overflow in all build profiles, as a wrapped index would
+    /// address an unrelated resolver.
+    fn checked_add(self, rhs: u64) -> Self {
+        Self::try_new(self.0 as u64 + rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+// The conversions panic instead of truncating, as the index is used to address
+// the execution order.
 impl From<u64> for OrderIx {
     fn from(value: u64) -> Self {
-        // This trait will not fail under normal circumstances.
-        debug_assert!(value < u32::MAX as u64);
-        Self(value as u32)
+        Self::try_new(value).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl From<usize> for OrderIx {
     fn from(value: usize) -> Self {
-        // This trait will not fail under normal circumstances.
-        debug_assert!(value < u32::MAX as usize);
-        Self(value as u32)
+        Self::try_new(value as u64).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -213,8 +430,8 @@ impl Add<i32> for OrderIx {
     type Output = Self;
 
     fn add(self, rhs: i32) -> Self::Output {
-        debug_assert!(rhs >= 0);
-        OrderIx(self.0 + rhs as u32)
+        assert!(rhs >= 0, "Adding a negative {} to an order index.", rhs);
+        self.checked_add(rhs as u64)
     }
 }
 
@@ -228,7 +445,7 @@ impl Add<u32> for OrderIx {
     type Output = Self;
 
     fn add(self, rhs: u32) -> Self::Output {
-        OrderIx(self.0 + rhs)
+        self.checked_add(rhs as u64)
     }
 }
 
@@ -242,8 +459,7 @@ impl Add<usize> for OrderIx {
     type Output = Self;
 
     fn add(self, rhs: usize) -> Self::Output {
-        debug_assert!(rhs < u32::MAX as usize);
-        OrderIx(self.0 + rhs as u32)
+        self.checked_add(rhs as u64)
     }
 }
 
@@ -317,3 +533,44 @@ impl AddAssign<u32> for ResolverIx {
         self.0 = rhs as usize;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn order_ix_rejects_overflow() {
+        assert_eq!(
+            Ok(OrderIx(u32::MAX - 1)),
+            OrderIx::try_new(u32::MAX as u64 - 1)
+        );
+        assert_eq!(
+            Err(OrderIxOverflow(u32::MAX as u64 + 1)),
+            OrderIx::try_new(u32::MAX as u64 + 1)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds")]
+    fn order_ix_conversion_panics_on_overflow() {
+        let _ = OrderIx::from(u32::MAX as u64 + 1);
+    }
+
+    #[test]
+    fn order_ix_adds_up_to_the_boundary() {
+        assert_eq!(OrderIx(u32::MAX - 1), OrderIx(u32::MAX - 2) + 1u32);
+        assert_eq!(OrderIx(u32::MAX - 1), OrderIx(0) + (u32::MAX as usize - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds")]
+    fn order_ix_addition_panics_on_overflow() {
+        let _ = OrderIx(u32::MAX - 1) + 1u32;
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds")]
+    fn order_ix_usize_addition_panics_on_overflow() {
+        let _ = OrderIx(1) + u32::MAX as usize;
+    }
+}
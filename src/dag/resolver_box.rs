@@ -26,6 +26,7 @@ pub struct ResolverBox<V> {
     // needs to be tested.
     container: Container,
     allocations: usize,
+    closure_bytes: usize,
     phantom: PhantomData<V>,
 }
 
@@ -40,6 +41,7 @@ impl<V> ResolverBox<V> {
         ResolverBox {
             container: Container::new(size_power),
             allocations: 0,
+            closure_bytes: 0,
             phantom: PhantomData,
         }
     }
@@ -67,10 +69,12 @@ impl<V> ResolverBox<V> {
             bind_fn_ref,
         };
         let (loc, ptr) = self.container.reserve(ctor.size());
+        let closure_size = ctor.closure_size();
 
         unsafe { ctor.write(ptr as *mut _) };
 
         self.allocations += 1;
+        self.closure_bytes += closure_size;
 
         debug_assert!(
             loc < u32::MAX as usize,
@@ -80,6 +84,19 @@ impl<V> ResolverBox<V> {
         ResolverIx::new_resolver(loc)
     }
 
+    /// Frees the memory of all the resolvers. The box mustn't be used
+    /// afterwards.
+    pub fn release(&mut self) {
+        self.container.pages = Vec::new();
+        self.allocations = 0;
+        self.closure_bytes = 0;
+    }
+
+    /// The bytes taken by the resolver closures, including their captures.
+    pub fn closure_memory(&self) -> usize {
+        self.closure_bytes
+    }
+
     /// Retrives the resolution from the box.
     ///
     /// Safety: The index must be one of the provided values from `push` calls.
@@ -219,6 +236,19 @@ impl<F, V> ResolverDstCtor<'_, F, V>
 where
     F: ResolutionFn<V>,
 {
+    fn closure_size(&self) -> usize {
+        // Otherwise the size will be `x % 8 != 0` and the next allocation will
+        // be aligned to 4 bytes.
+        // This is a fix for a closure that was found in nature. I haven't been
+        // able to reproduce it, but it was a 4 byte closure that was aligned to
+        // 4 bytes.
+        if size_of::<F>() == 4 {
+            8
+        } else {
+            size_of::<F>()
+        }
+    }
+
     fn size(&self) -> usize {
         assert!(
             size_of::<F>() % 4 == 0,
@@ -228,16 +258,7 @@ where
 
         debug_assert!(self.inputs.len() <= u16::MAX as usize);
         debug_assert!(self.outputs.len() <= u16::MAX as usize);
-        // Otherwise the size will be `x % 8 != 0` and the next allocation will
-        // be aligned to 4 bytes.
-        // This is a fix for a closure that was found in nature. I haven't been
-        // able to reproduce it, but it was a 4 byte closure that was aligned to
-        // 4 bytes.
-        let closure_size = if size_of::<F>() == 4 {
-            8
-        } else {
-            size_of::<F>()
-        };
+        let closure_size = self.closure_size();
 
         let r = ((self.inputs.len() + self.outputs.len()) * size_of::<Place>())
             + size_of::<ResolverHeader>()
@@ -315,11 +336,7 @@ where
         let f_ptr = plc_ptr.add(self.outputs.len()) as *mut F;
         debug_assert_eq!(0, f_ptr as usize % std::mem::align_of_val(&f_ptr));
 
-        let closure_size = if size_of::<F>() == 4 {
-            8
-        } else {
-            size_of::<F>()
-        };
+        let closure_size = self.closure_size();
 
         debug_assert_eq!(
             self.size(),
@@ -455,7 +472,23 @@ pub(crate) fn invocation_binder<Fn, F: SmallField>(
             );
         }
 
-        bound(ins, &mut DstBuffer::MutSliceIndirect(out, debug_track, 0));
+        let mut dst = DstBuffer::MutSliceIndirect(&mut *out, debug_track, 0);
+
+        bound(ins, &mut dst);
+
+        if cfg!(debug_assertions) {
+            let DstBuffer::MutSliceIndirect(_, _, written) = dst else {
+                unreachable!()
+            };
+
+            assert!(
+                written == out.len(),
+                "Resolution {} produced {} outputs, but {} were declared.",
+                resolver.added_at(),
+                written,
+                out.len()
+            );
+        }
 
         if (cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA) && debug_track && true {
             log!(
@@ -760,6 +793,37 @@ mod test {
         run_invariant_asserts(&ins, &out, 1 << 7, &resolution_fn, binder, value);
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Resolution 7 produced 1 outputs, but 2 were declared.")]
+    fn invocation_checks_output_count() {
+        let mut rbox = ResolverBox::new();
+
+        let resolution_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
+            outs.push(ins[0]);
+        };
+
+        let ins = [Place::from_variable(Variable::from_variable_index(0))];
+
+        let out = [
+            Place::from_variable(Variable::from_variable_index(1)),
+            Place::from_variable(Variable::from_variable_index(2)),
+        ];
+
+        let binder = get_binder(&resolution_fn);
+
+        let ix = rbox.push(&ins, &out, 7, resolution_fn, binder);
+
+        let (mut a, mut b) = (F::ZERO, F::ZERO);
+
+        binder(
+            unsafe { rbox.get(ix) },
+            &[F::ONE],
+            &mut [&mut a, &mut b],
+            false,
+        );
+    }
+
     #[test]
     fn store_doesnt_drop_fn() {
         struct DroppedContext<'a> {
@@ -0,0 +1,157 @@
+use std::marker::PhantomData;
+
+use crate::{
+    config::CSResolverConfig,
+    cs::{
+        traits::cs::{CSWitnessSource, DstBuffer},
+        Place,
+    },
+    dag::{awaiters::ImmediateAwaiter, CircuitResolver, WitnessSource, WitnessSourceAwaitable},
+    field::SmallField,
+};
+
+/// The structure of the registered resolutions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResolutionStructure {
+    pub values_set: usize,
+    pub registrations: usize,
+    /// The total number of inputs over all registrations, i.e. the number of
+    /// dependency edges.
+    pub edges: usize,
+    pub max_fan_in: usize,
+    pub max_fan_out: usize,
+    /// The highest place that was set or is an output of a registration.
+    pub max_place: Option<Place>,
+}
+
+/// A resolver for registration-only passes. It records the structure of the
+/// resolutions without allocating the values or running any resolution
+/// function, so it doesn't produce values.
+pub struct AnalysisCircuitResolver<F, CFG> {
+    structure: ResolutionStructure,
+    phantom: PhantomData<(F, CFG)>,
+}
+
+impl<F, CFG> AnalysisCircuitResolver<F, CFG> {
+    pub fn structure(&self) -> ResolutionStructure {
+        self.structure
+    }
+
+    fn track(&mut self, place: Place) {
+        if self.structure.max_place.map_or(true, |x| x.0 < place.0) {
+            self.structure.max_place = Some(place);
+        }
+    }
+}
+
+impl<F: SmallField, CFG: CSResolverConfig> WitnessSource<F> for AnalysisCircuitResolver<F, CFG> {
+    const PRODUCES_VALUES: bool = false;
+
+    fn try_get_value(&self, _variable: Place) -> Option<F> {
+        None
+    }
+
+    fn get_value_unchecked(&self, _variable: Place) -> F {
+        panic!("Analysis resolver doesn't produce values.");
+    }
+}
+
+impl<F: SmallField, CFG: CSResolverConfig> WitnessSourceAwaitable<F>
+    for AnalysisCircuitResolver<F, CFG>
+{
+    type Awaiter<'a> = ImmediateAwaiter;
+
+    fn get_awaiter<const N: usize>(&mut self, _vars: [Place; N]) -> Self::Awaiter<'_> {
+        ImmediateAwaiter {}
+    }
+}
+
+impl<F: SmallField, CFG: CSResolverConfig> CSWitnessSource<F> for AnalysisCircuitResolver<F, CFG> {}
+
+impl<F: SmallField, CFG: CSResolverConfig> CircuitResolver<F, CFG>
+    for AnalysisCircuitResolver<F, CFG>
+{
+    type Arg = ();
+
+    fn new(_args: Self::Arg) -> Self {
+        Self {
+            structure: ResolutionStructure::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    fn set_value(&mut self, key: Place, _value: F) {
+        self.structure.values_set += 1;
+        self.track(key);
+    }
+
+    fn add_resolution<Fn>(&mut self, inputs: &[Place], outputs: &[Place], _f: Fn)
+    where
+        Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync,
+    {
+        let s = &mut self.structure;
+
+        s.registrations += 1;
+        s.edges += inputs.len();
+        s.max_fan_in = std::cmp::max(s.max_fan_in, inputs.len());
+        s.max_fan_out = std::cmp::max(s.max_fan_out, outputs.len());
+
+        outputs.iter().for_each(|x| self.track(*x));
+    }
+
+    fn wait_till_resolved(&mut self) {}
+
+    fn clear(&mut self) {
+        self.structure = ResolutionStructure::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        config::{CSConfig, DevCSConfig},
+        field::{goldilocks::GoldilocksField, U64Representable},
+    };
+
+    use super::*;
+
+    type F = GoldilocksField;
+    type Cfg = <DevCSConfig as CSConfig>::ResolverConfig;
+
+    #[test]
+    fn records_structure() {
+        let mut resolver = AnalysisCircuitResolver::<F, Cfg>::new(());
+
+        let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
+            outs.push(ins[0]);
+        };
+
+        resolver.set_value(Place::from_variable_index(0), F::from_u64_unchecked(1));
+        resolver.set_value(Place::from_variable_index(1), F::from_u64_unchecked(2));
+        resolver.add_resolution(
+            &[Place::from_variable_index(0), Place::from_variable_index(1)],
+            &[Place::from_variable_index(2)],
+            res_fn,
+        );
+        resolver.add_resolution(
+            &[Place::from_variable_index(2)],
+            &[Place::from_variable_index(3), Place::from_variable_index(4)],
+            res_fn,
+        );
+
+        resolver.wait_till_resolved();
+
+        assert_eq!(
+            ResolutionStructure {
+                values_set: 2,
+                registrations: 2,
+                edges: 3,
+                max_fan_in: 2,
+                max_fan_out: 2,
+                max_place: Some(Place::from_variable_index(4)),
+            },
+            resolver.structure()
+        );
+        assert_eq!(None, resolver.try_get_value(Place::from_variable_index(2)));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{marker::PhantomData, ops::Range};
+
+use crate::{cs::Place, dag::WitnessSource, field::SmallField};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlappingRanges {
+    pub first: Range<u64>,
+    pub second: Range<u64>,
+}
+
+impl std::fmt::Display for OverlappingRanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Place ranges {:?} and {:?} overlap.",
+            self.first, self.second
+        )
+    }
+}
+
+impl std::error::Error for OverlappingRanges {}
+
+/// Reads the witness of a circuit that is resolved by several resolvers,
+/// each owning a disjoint range of place indices. A place is read from the
+/// resolver owning it at the index relative to the range start, as the
+/// resolvers track their values from index 0.
+pub struct CombinedWitnessSource<F, S> {
+    /// Sorted by the range start.
+    parts: Vec<(Range<u64>, S)>,
+    phantom: PhantomData<F>,
+}
+
+impl<F: SmallField, S: WitnessSource<F>> CombinedWitnessSource<F, S> {
+    pub fn new(mut parts: Vec<(Range<u64>, S)>) -> Result<Self, OverlappingRanges> {
+        parts.sort_by_key(|(range, _)| range.start);
+
+        for pair in parts.windows(2) {
+            if pair[0].0.end > pair[1].0.start {
+                return Err(OverlappingRanges {
+                    first: pair[0].0.clone(),
+                    second: pair[1].0.clone(),
+                });
+            }
+        }
+
+        Ok(Self {
+            parts,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The resolver owning `place`, if any, and the place within it.
+    pub fn part(&self, place: Place) -> Option<(&S, Place)> {
+        let ix = place.as_any_index();
+
+        let i = self.parts.partition_point(|(range, _)| range.start <= ix);
+
+        match i {
+            0 => None,
+            i => {
+                let (range, part) = &self.parts[i - 1];
+
+                // Keeps the variable type bit of the place.
+                range
+                    .contains(&ix)
+                    .then_some((part, Place(place.0 - range.start)))
+            }
+        }
+    }
+
+    pub fn into_parts(self) -> Vec<(Range<u64>, S)> {
+        self.parts
+    }
+}
+
+impl<F: SmallField, S: WitnessSource<F>> WitnessSource<F> for CombinedWitnessSource<F, S> {
+    const PRODUCES_VALUES: bool = S::PRODUCES_VALUES;
+
+    fn try_get_value(&self, variable: Place) -> Option<F> {
+        let (part, place) = self.part(variable)?;
+
+        part.try_get_value(place)
+    }
+
+    fn get_value_unchecked(&self, variable: Place) -> F {
+        match self.part(variable) {
+            Some((part, place)) => part.get_value_unchecked(place),
+            None => panic!("Place {:?} isn't owned by any resolver.", variable),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{DoPerformRuntimeAsserts, Resolver},
+        dag::{
+            resolvers::{mt::sorters::sorter_live::LiveResolverSorter, MtCircuitResolver},
+            CircuitResolverOpts,
+        },
+        field::{goldilocks::GoldilocksField, Field},
+    };
+
+    type F = GoldilocksField;
+    type Cfg = Resolver<DoPerformRuntimeAsserts>;
+    type Mt = MtCircuitResolver<F, LiveResolverSorter<F, Cfg>, Cfg>;
+
+    /// Resolves `range.start + i` into the place `i`.
+    fn resolve_part(range: Range<u64>) -> Mt {
+        let len = range.end - range.start;
+
+        let mut storage = Mt::new(CircuitResolverOpts {
+            max_variables: len as usize,
+            desired_parallelism: 16,
+            ..Default::default()
+        });
+
+        storage.set_value(
+            Place::from_variable_index(0),
+            F::from_u64_with_reduction(range.start),
+        );
+
+        for i in 1..len {
+            storage.add_resolution(
+                &[Place::from_variable_index(i - 1)],
+                &[Place::from_variable_index(i)],
+                |ins, outs| {
+                    let mut x = ins[0];
+                    outs.push(*x.add_assign(&F::ONE));
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        storage
+    }
+
+    #[test]
+    fn dispatches_by_range() {
+        let combined = CombinedWitnessSource::new(vec![
+            (10..20, resolve_part(10..20)),
+            (0..10, resolve_part(0..10)),
+        ])
+        .unwrap();
+
+        for i in 0..20 {
+            let place = Place::from_variable_index(i);
+
+            assert_eq!(
+                Some(F::from_u64_with_reduction(i)),
+                combined.try_get_value(place)
+            );
+            assert_eq!(
+                F::from_u64_with_reduction(i),
+                combined.get_value_unchecked(place)
+            );
+        }
+
+        assert_eq!(None, combined.try_get_value(Place::from_variable_index(20)));
+    }
+
+    #[test]
+    fn rejects_overlapping_ranges() {
+        let r = CombinedWitnessSource::new(vec![
+            (0..10, resolve_part(0..10)),
+            (5..20, resolve_part(5..20)),
+        ]);
+
+        assert_eq!(
+            Some(OverlappingRanges {
+                first: 0..10,
+                second: 5..20
+            }),
+            r.err()
+        );
+    }
+}
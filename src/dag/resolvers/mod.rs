@@ -1,7 +1,12 @@
+mod analysis;
+mod combined;
 pub mod mt;
 mod null;
 mod st;
 
+pub(crate) use analysis::AnalysisCircuitResolver;
+pub use analysis::ResolutionStructure;
+pub use combined::{CombinedWitnessSource, OverlappingRanges};
 pub(crate) use mt::MtCircuitResolver;
 pub(crate) use null::NullCircuitResolver;
 pub(crate) use st::StCircuitResolver;
@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+/// The resolved state of a range of variables, one bit per variable, see
+/// `MtCircuitResolver::resolved_bitset`. Bit `i` of the words stands for the
+/// variable `range.start + i`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedBitSet {
+    range: Range<u64>,
+    words: Vec<u64>,
+}
+
+impl ResolvedBitSet {
+    pub(crate) fn new(range: Range<u64>) -> Self {
+        let len = (range.end - range.start) as usize;
+
+        Self {
+            range,
+            words: vec![0; (len + 63) / 64],
+        }
+    }
+
+    pub(crate) fn insert(&mut self, variable: u64) {
+        let i = (variable - self.range.start) as usize;
+
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn range(&self) -> &Range<u64> {
+        &self.range
+    }
+
+    pub fn len(&self) -> usize {
+        (self.range.end - self.range.start) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_slice(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Whether the variable is resolved. Panics if it's out of the range.
+    pub fn contains(&self, variable: u64) -> bool {
+        assert!(
+            self.range.contains(&variable),
+            "Variable {} is out of the bitset range {:?}.",
+            variable,
+            self.range
+        );
+
+        let i = (variable - self.range.start) as usize;
+
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    /// Whether all the variables set in `required` are resolved. `required`
+    /// is laid out the same as `as_slice`, and may be shorter.
+    pub fn contains_all(&self, required: &[u64]) -> bool {
+        assert!(
+            required.len() <= self.words.len(),
+            "Required set of {} words is longer than the bitset of {} words.",
+            required.len(),
+            self.words.len()
+        );
+
+        self.words
+            .iter()
+            .zip(required)
+            .all(|(have, need)| have & need == *need)
+    }
+}
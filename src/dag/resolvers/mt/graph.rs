@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::cs::Place;
+
+/// The dependencies between the places, with an edge from each input of a
+/// registered resolution to each of its outputs. See
+/// `MtCircuitResolver::dependency_graph`.
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    predecessors: HashMap<Place, Vec<Place>>,
+    successors: HashMap<Place, Vec<Place>>,
+    edges: usize,
+}
+
+impl DependencyGraph {
+    pub(crate) fn add_resolution(&mut self, inputs: &[Place], outputs: &[Place]) {
+        for output in outputs {
+            self.predecessors
+                .entry(*output)
+                .or_default()
+                .extend_from_slice(inputs);
+        }
+
+        for input in inputs {
+            self.successors
+                .entry(*input)
+                .or_default()
+                .extend_from_slice(outputs);
+        }
+
+        self.edges += inputs.len() * outputs.len();
+    }
+
+    /// The inputs of the resolution producing `place`.
+    pub fn predecessors(&self, place: Place) -> &[Place] {
+        self.predecessors.get(&place).map_or(&[], |x| x)
+    }
+
+    /// The outputs of the resolutions taking `place` as an input, in
+    /// registration order.
+    pub fn successors(&self, place: Place) -> &[Place] {
+        self.successors.get(&place).map_or(&[], |x| x)
+    }
+
+    /// The places that are an input or an output of a resolution.
+    pub fn places(&self) -> impl Iterator<Item = Place> + '_ {
+        self.successors.keys().copied().chain(
+            self.predecessors
+                .keys()
+                .copied()
+                .filter(|x| self.successors.contains_key(x) == false),
+        )
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges
+    }
+
+    /// The number of resolutions on the longest dependency chain, which
+    /// bounds the resolution latency regardless of the parallelism. Places
+    /// on a cycle aren't counted.
+    pub fn critical_path_length(&self) -> usize {
+        // Kahn's algorithm, the depth of a place is the length of the longest
+        // chain producing it.
+        let mut pending = self
+            .predecessors
+            .iter()
+            .map(|(place, x)| (*place, x.len()))
+            .collect::<HashMap<_, _>>();
+        let mut depths = HashMap::new();
+
+        let mut ready = self
+            .successors
+            .keys()
+            .filter(|x| self.predecessors.contains_key(x) == false)
+            .map(|x| (*x, 0))
+            .collect::<Vec<_>>();
+
+        let mut longest = 0;
+
+        while let Some((place, depth)) = ready.pop() {
+            longest = longest.max(depth);
+
+            for output in self.successors(place) {
+                let output_depth = depths.entry(*output).or_insert(0);
+                *output_depth = (*output_depth).max(depth + 1);
+
+                let count = pending.get_mut(output).unwrap();
+                *count -= 1;
+
+                if *count == 0 {
+                    ready.push((*output, *output_depth));
+                }
+            }
+        }
+
+        longest
+    }
+}
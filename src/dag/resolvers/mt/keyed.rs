@@ -0,0 +1,54 @@
+use smallvec::SmallVec;
+
+use crate::{cs::traits::cs::DstBuffer, field::SmallField};
+
+/// Output buffer for resolutions added with
+/// `MtCircuitResolver::add_resolution_keyed`. The outputs are set by their
+/// index in the declared `outputs`, in any order. Each output must be set
+/// exactly once.
+pub struct KeyedDstBuffer<V> {
+    values: SmallVec<[Option<V>; 4]>,
+}
+
+impl<V: SmallField> KeyedDstBuffer<V> {
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            values: SmallVec::from_elem(None, len),
+        }
+    }
+
+    /// The number of declared outputs.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn set(&mut self, output_ix: usize, value: V) {
+        assert!(
+            output_ix < self.values.len(),
+            "Output index {} is out of the {} declared outputs.",
+            output_ix,
+            self.values.len()
+        );
+
+        let slot = &mut self.values[output_ix];
+
+        assert!(slot.is_none(), "Output {} is set twice.", output_ix);
+
+        *slot = Some(value);
+    }
+
+    /// Writes the outputs into `dst` in the declared order, panicking on
+    /// the first output that wasn't set.
+    pub(crate) fn write_to(self, dst: &mut DstBuffer<'_, '_, V>) {
+        for (i, x) in self.values.into_iter().enumerate() {
+            match x {
+                Some(x) => dst.push(x),
+                None => panic!("Output {} wasn't set by the resolution.", i),
+            }
+        }
+    }
+}
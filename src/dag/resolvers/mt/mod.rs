@@ -1,16 +1,28 @@
+mod bitset;
+mod graph;
+mod keyed;
+mod plan;
+#[cfg(feature = "profile-resolvers")]
+pub mod profiling;
 mod registrar;
 mod resolution_window;
+mod scratch;
 pub mod sorters;
+mod vectorized;
 
 use std::{
     any::Any,
-    cell::{Cell, UnsafeCell},
+    cell::UnsafeCell,
+    future::Future,
     marker::PhantomData,
     panic::resume_unwind,
+    pin::Pin,
     sync::{
-        atomic::{fence, AtomicBool, AtomicIsize},
-        Arc, Mutex,
+        atomic::{fence, AtomicBool, AtomicIsize, AtomicU64, AtomicUsize},
+        mpsc::Sender,
+        Arc, Condvar, Mutex,
     },
+    task::{Context, Poll, Waker},
     thread::JoinHandle,
 };
 
@@ -18,13 +30,16 @@ use crate::{
     config::CSResolverConfig,
     cs::{
         traits::cs::{CSWitnessSource, DstBuffer},
-        Place,
+        Place, Variable,
     },
     dag::{
         awaiters::{self, AwaitersBroker},
-        primitives::{ExecOrder, Values},
+        primitives::{ExecOrder, ResolverIx, ValueStorage, ValueStore, Values},
         resolver_box::ResolverBox,
-        CircuitResolver, WitnessSource, WitnessSourceAwaitable,
+        Awaiter as _, AwaiterObserver, CapacityExceeded, CapacityPolicy, CircuitResolver,
+        CircuitResolverError, CircuitResolverOpts, DuplicateSetPolicy, GetValueError, GuideTrackId,
+        OrderInfo, PanicPolicy, PlaceStatus, Priority, ResolvedHandle, TrackId,
+        TrivialWitnessCastable, WitnessSource, WitnessSourceAwaitable,
     },
     field::SmallField,
     log,
@@ -32,10 +47,22 @@ use crate::{
 };
 
 use self::{
-    resolution_window::ResolutionWindow,
-    sorters::{ResolutionRecord, ResolverSortingMode},
+    resolution_window::{RWConfig, RWConfigRecord, ResolutionWindow},
+    sorters::{
+        sorter_live::LiveResolverSorter, sorter_playback::PlaybackResolverSorter, FlushReport,
+        ParallelismReport, PendingInfo, RecordSummary, ResolutionRecord, ResolutionRecordSource,
+        ResolverSortingMode,
+    },
 };
 
+pub use self::bitset::ResolvedBitSet;
+pub use self::graph::DependencyGraph;
+pub use self::keyed::KeyedDstBuffer;
+pub use self::plan::{ExecutionPlan, ParsePlanError, PlanStep};
+pub use self::resolution_window::{Spawner, ThreadSpawner};
+pub use self::scratch::ScratchSpace;
+pub use self::vectorized::{ElementwiseOp, VECTORIZED_CHUNK_LEN};
+
 pub(crate) const PARANOIA: bool = false;
 
 /// Used to send notifications and data between the resolver, resolution window
@@ -44,7 +71,18 @@ pub struct ResolverComms {
     pub exec_order_buffer_hint: AtomicIsize,
     pub registration_complete: AtomicBool,
     pub rw_panicked: AtomicBool,
-    pub rw_panic: Cell<Option<Box<dyn Any + Send + 'static>>>,
+    /// The payload of the resolution window panic. Taken by whoever observes
+    /// the panic first, which may be one of several threads waiting on an
+    /// awaiter.
+    pub rw_panic: Mutex<Option<Box<dyn Any + Send + 'static>>>,
+    /// Requests the resolution window to stop before the execution order is
+    /// exhausted.
+    pub cancelled: AtomicBool,
+    /// Exclusive bound on the execution order indices the resolution window
+    /// resolves, see `MtCircuitResolver::resolve_until`.
+    pub resolve_limit: AtomicUsize,
+    /// The execution order prefix the resolution window has fully executed.
+    pub executed: AtomicUsize,
 }
 
 #[derive(Debug)]
@@ -70,6 +108,244 @@ impl Stats {
     }
 }
 
+/// Wall-clock split of a resolution, measured from the resolver creation.
+#[derive(Clone, Copy, Debug)]
+pub struct ResolutionTiming {
+    /// Time until `wait_till_resolved` was called.
+    pub registration: std::time::Duration,
+    /// Time from then on until all values were resolved.
+    pub resolution: std::time::Duration,
+    pub total: std::time::Duration,
+}
+
+/// How `wait_till_resolved_deadline` ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolutionOutcome {
+    Completed,
+    /// The resolution was stopped at the deadline, with `pending` resolvers
+    /// of the execution order not invoked.
+    TimedOut {
+        resolved: u64,
+        pending: u64,
+    },
+}
+
+/// The number of registered resolutions per input and output count.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArityHistogram {
+    pub inputs: std::collections::BTreeMap<usize, u64>,
+    pub outputs: std::collections::BTreeMap<usize, u64>,
+}
+
+/// The registered values by origin, see `MtCircuitResolver::value_breakdown`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValueBreakdown {
+    /// Values set directly with `set_value`.
+    pub seeded: u64,
+    /// Outputs of the registered resolutions.
+    pub derived: u64,
+}
+
+/// How far the execution order is executed, see
+/// `MtCircuitResolver::execution_position`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionPosition {
+    /// The length of the execution order prefix that was executed.
+    pub executed: usize,
+    /// The length of the execution order prefix that is safe to execute.
+    pub available: usize,
+    /// The length of the execution order. In playback it's known upfront,
+    /// otherwise it grows with the registrations.
+    pub total: usize,
+}
+
+/// The resolution window panicked. Holds the panic payload, unless it was
+/// already taken by an awaiter.
+pub struct ResolutionPanicked(pub Option<Box<dyn Any + Send + 'static>>);
+
+impl std::fmt::Debug for ResolutionPanicked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ResolutionPanicked({})", self)
+    }
+}
+
+impl std::fmt::Display for ResolutionPanicked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let payload = self.0.as_ref();
+
+        match payload
+            .and_then(|x| x.downcast_ref::<&str>().copied())
+            .or_else(|| payload.and_then(|x| x.downcast_ref::<String>().map(|x| x.as_str())))
+        {
+            Some(msg) => write!(f, "Resolution window panicked: {}", msg),
+            None => write!(f, "Resolution window panicked."),
+        }
+    }
+}
+
+impl std::error::Error for ResolutionPanicked {}
+
+impl From<ResolutionPanicked> for CircuitResolverError {
+    fn from(value: ResolutionPanicked) -> Self {
+        Self::WindowPanicked(
+            value
+                .0
+                .unwrap_or_else(|| Box::new("The panic payload was taken by an awaiter.")),
+        )
+    }
+}
+
+#[derive(Default)]
+struct CompletionSlot {
+    finished: bool,
+    /// Payload of a panic that wasn't caught by the resolution window.
+    join_panic: Option<Box<dyn Any + Send + 'static>>,
+    waker: Option<Waker>,
+}
+
+/// Set by the thread that joins the resolution window.
+#[derive(Default)]
+struct CompletionSignal {
+    slot: Mutex<CompletionSlot>,
+    finished: Condvar,
+}
+
+/// Resolves once all the values are resolved, see
+/// `MtCircuitResolver::completion_future`.
+///
+/// Dropping the future before it's ready blocks until the resolution ends,
+/// as the resolvers may still reference the resolver data.
+pub struct CompletionFuture<'a, V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig> {
+    resolver: &'a mut MtCircuitResolver<V, RS, CFG>,
+    signal: Option<Arc<CompletionSignal>>,
+    /// Whether the resolution is to be completed by this future.
+    owned: bool,
+}
+
+impl<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig> Future
+    for CompletionFuture<'_, V, RS, CFG>
+{
+    type Output = Result<(), CircuitResolverError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(signal) = &this.signal {
+            let mut slot = signal.slot.lock().unwrap();
+
+            if slot.finished == false {
+                slot.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+
+            let join_panic = slot.join_panic.take();
+
+            drop(slot);
+            this.signal = None;
+
+            if let Some(e) = join_panic {
+                this.owned = false;
+                return Poll::Ready(Err(CircuitResolverError::WindowPanicked(e)));
+            }
+        }
+
+        match std::mem::take(&mut this.owned) {
+            true => Poll::Ready(this.resolver.complete_resolution(true).map_err(Into::into)),
+            false => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig> Drop
+    for CompletionFuture<'_, V, RS, CFG>
+{
+    fn drop(&mut self) {
+        let mut panicked = false;
+
+        if let Some(signal) = self.signal.take() {
+            let mut slot = signal
+                .finished
+                .wait_while(signal.slot.lock().unwrap(), |x| x.finished == false)
+                .unwrap();
+
+            panicked = slot.join_panic.take().is_some();
+        }
+
+        // Dropped before it was polled to completion, so the resolution is
+        // finished here, or a recording would never be written.
+        if std::mem::take(&mut self.owned) && panicked == false {
+            panicked = self.resolver.complete_resolution(false).is_err();
+        }
+
+        if panicked && self.resolver.silent == false {
+            log!("Resolution window panicked, but the completion future was dropped.");
+        }
+    }
+}
+
+/// Notifications requested with `MtCircuitResolver::notify_on_resolve`.
+pub(crate) struct ResolveSubscriptions {
+    /// Set once there was a subscription, so the resolution window doesn't
+    /// take the lock otherwise.
+    active: AtomicBool,
+    senders: Mutex<std::collections::HashMap<Place, Vec<Sender<()>>>>,
+}
+
+impl ResolveSubscriptions {
+    pub(crate) fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            senders: Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// Fires right away if `is_resolved` says the place is already resolved.
+    /// It's checked under the lock, as the window marks the values resolved
+    /// before taking it in `notify`.
+    fn subscribe(&self, place: Place, tx: Sender<()>, is_resolved: impl FnOnce() -> bool) {
+        let mut senders = self.senders.lock().unwrap();
+
+        // Pairs with the fence in `is_active`: either the window sees the
+        // subscription, or this thread sees the value resolved.
+        self.active
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        fence(std::sync::atomic::Ordering::SeqCst);
+
+        match is_resolved() {
+            // The receiver may be gone, which is fine for a notification.
+            true => tx.send(()).unwrap_or_default(),
+            false => senders.entry(place).or_default().push(tx),
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        fence(std::sync::atomic::Ordering::SeqCst);
+        self.active.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Drops the subscriptions left over from a previous resolution.
+    pub(crate) fn clear(&self) {
+        self.senders.lock().unwrap().clear();
+        self.active
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Fires the subscriptions of the resolved `places`.
+    pub(crate) fn notify(&self, places: impl Iterator<Item = Place>) {
+        if self.is_active() == false {
+            return;
+        }
+
+        let mut senders = self.senders.lock().unwrap();
+
+        for place in places {
+            if let Some(txs) = senders.remove(&place) {
+                txs.into_iter().for_each(|x| x.send(()).unwrap_or_default());
+            }
+        }
+    }
+}
+
 /// Shared between the resolver, awaiters and the resolution window.
 pub struct ResolverCommonData<V, T: Default> {
     // The following two are meant to have an asynchronized access. The access
@@ -85,6 +361,51 @@ pub struct ResolverCommonData<V, T: Default> {
     /// amount of resolvers.
     pub exec_order: Mutex<ExecOrder>,
     pub awaiters_broker: AwaitersBroker<T>,
+    /// The number of resolutions invoked so far.
+    pub resolved_count: AtomicU64,
+    pub(crate) subscriptions: ResolveSubscriptions,
+    /// The registrations whose resolutions failed, see
+    /// `PanicPolicy::SkipAndRecord`.
+    pub failed: Mutex<Vec<crate::dag::guide::RegistrationNum>>,
+
+    /// Durations of all resolver invocations, appended by each worker once
+    /// it finishes.
+    #[cfg(feature = "profile-resolvers")]
+    pub timings: Mutex<Vec<(crate::dag::guide::RegistrationNum, std::time::Duration)>>,
+}
+
+impl<V: SmallField, T: TrackId + 'static> ResolverCommonData<V, T> {
+    /// Rewinds the data for a new resolution, keeping the values allocation.
+    /// The sorter rewinds the execution order items, which it lays out.
+    ///
+    /// # Safety
+    ///
+    /// The resolution window must not be running.
+    pub(crate) unsafe fn reset(&self) {
+        self.values.u_deref_mut().reset();
+        *self.resolvers.u_deref_mut() = ResolverBox::new();
+
+        self.exec_order.lock().unwrap().size = 0;
+        self.awaiters_broker.reset();
+        self.resolved_count
+            .store(0, std::sync::atomic::Ordering::Relaxed);
+        self.subscriptions.clear();
+        self.failed.lock().unwrap().clear();
+        #[cfg(feature = "profile-resolvers")]
+        self.timings.lock().unwrap().clear();
+    }
+}
+
+/// The registrations a checkpoint recorded as invoked, for a resolver created
+/// by `resume`. Registrations are numbered across the runs, counting the ones
+/// skipped because they were invoked before.
+#[derive(Default)]
+struct ResumedOrder {
+    invoked: std::collections::HashSet<crate::dag::guide::RegistrationNum>,
+    /// The number across the runs of each registration passed to the sorter.
+    registered: Vec<crate::dag::guide::RegistrationNum>,
+    /// The number of registrations made, including the skipped ones.
+    next: crate::dag::guide::RegistrationNum,
 }
 
 /// The data is tracked in the following manner:
@@ -103,13 +424,59 @@ pub struct MtCircuitResolver<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSR
     pub(crate) common: Arc<ResolverCommonData<V, RS::TrackId>>,
     comms: Arc<ResolverComms>,
     resolution_window_handle: Option<JoinHandle<()>>,
+    /// Spawns the resolution window threads.
+    spawner: Arc<dyn Spawner>,
+    /// Set for resolvers created with `new_lazy`, the window is then spawned
+    /// on the first request that needs it.
+    lazy: bool,
+    /// The worker threads of the resolution window, zero when resolving
+    /// inline.
+    threads: u32,
+    /// The worker threads of a lazy window that wasn't spawned yet.
+    lazy_window: Option<u32>,
 
     stats: Stats,
     call_count: u32,
     debug_track: Vec<Place>,
+    /// Set when the resolver was restored from a checkpoint. Values that are
+    /// already resolved and resolutions that were invoked are skipped on
+    /// registration.
+    resumed: Option<ResumedOrder>,
+    /// Set when the resolver runs without worker threads. Holds the position
+    /// in the execution order up to which the resolvers were invoked inline.
+    inline_cursor: Option<usize>,
+    /// Contexts shared by resolutions added with `add_resolution_with_ctx`.
+    /// Kept alive until the resolver is dropped, after the resolution window
+    /// is joined.
+    contexts: Vec<Arc<dyn Any + Send + Sync>>,
+    /// Set by `release_resolvers`.
+    resolvers_released: bool,
+    /// Prints the drop progress, which helps to spot a hanging drop.
+    verbose_drop: bool,
+    /// Suppresses all the output, see `CircuitResolverOpts::silent`.
+    silent: bool,
+    arity: ArityHistogram,
+    /// The number of registered resolutions taking each place as an input,
+    /// indexed like the values. Allocated when fanout tracking is enabled.
+    fanout: Option<Vec<u32>>,
+    /// Allocated when dependency tracking is enabled.
+    dependencies: Option<DependencyGraph>,
+    /// The sorter progress at the last `flush`.
+    flushed: FlushReport,
+    /// Called when `max_tracked` advances, see `on_track_advance`.
+    track_advance: Option<Box<dyn FnMut(Place) + Send>>,
+    /// The `max_tracked` last passed to `track_advance`.
+    tracked_reported: i64,
     phantom: PhantomData<CFG>,
 }
 
+/// A reference to a context held in `MtCircuitResolver::contexts`.
+struct SharedCtx<C>(*const C);
+
+// Safety: The pointee is `Sync` and outlives every resolution that holds it.
+unsafe impl<C: Sync> Send for SharedCtx<C> {}
+unsafe impl<C: Sync> Sync for SharedCtx<C> {}
+
 unsafe impl<V, RS, CFG> Send for MtCircuitResolver<V, RS, CFG>
 where
     V: SmallField,
@@ -161,12 +528,63 @@ where
 impl<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig>
     MtCircuitResolver<V, RS, CFG>
 {
-    pub fn new(opts: RS::Arg) -> Self {
-        let threads = std::env::var("BOOJUM_CR_THREADS")
+    fn worker_threads() -> u32 {
+        std::env::var("BOOJUM_CR_THREADS")
             .map_err(|_| "")
             .and_then(|x| x.parse().map_err(|_| ""))
-            .unwrap_or(3);
+            .unwrap_or(3)
+    }
 
+    pub fn new(opts: RS::Arg) -> Self {
+        Self::new_with_threads(opts, Self::worker_threads())
+    }
+
+    /// Creates a resolver with the given number of worker threads. With zero
+    /// threads no resolution window is spawned, and the resolvers are invoked
+    /// on the calling thread in the execution order, whenever an awaiter is
+    /// requested and on `wait_till_resolved`. This makes the resolution
+    /// deterministic, which is useful for debugging. Setting
+    /// `BOOJUM_CR_THREADS=0` has the same effect for `new`.
+    pub fn new_with_threads(opts: RS::Arg, threads: u32) -> Self {
+        Self::new_with_spawner(opts, threads, Arc::new(ThreadSpawner))
+    }
+
+    /// Creates a resolver whose resolution window threads are spawned by
+    /// `spawner`, for hosts where spawning OS threads directly isn't allowed.
+    pub fn new_with_spawner(opts: RS::Arg, threads: u32, spawner: Arc<dyn Spawner>) -> Self {
+        Self::from_sorter(threads, spawner, false, |comms, debug_track, spawner| {
+            RS::new(opts, comms, debug_track, spawner)
+        })
+    }
+
+    /// Same as `new`, but the resolution window isn't spawned until it's
+    /// needed: on the first awaiter, `barrier`, `resolve_until` or wait for
+    /// the resolution, or when the backpressure has to drain it. Values set or
+    /// resolved inline can still be read without a window, e.g. with
+    /// `try_get_value`, which doesn't spawn it. A resolver that is dropped
+    /// without the window spawned is cancelled: its resolvers are never
+    /// invoked.
+    pub fn new_lazy(opts: RS::Arg) -> Self {
+        Self::new_lazy_with_spawner(opts, Self::worker_threads(), Arc::new(ThreadSpawner))
+    }
+
+    /// The lazy counterpart of `new_with_spawner`.
+    pub fn new_lazy_with_spawner(opts: RS::Arg, threads: u32, spawner: Arc<dyn Spawner>) -> Self {
+        Self::from_sorter(threads, spawner, true, |comms, debug_track, spawner| {
+            RS::new(opts, comms, debug_track, spawner)
+        })
+    }
+
+    fn from_sorter(
+        threads: u32,
+        spawner: Arc<dyn Spawner>,
+        lazy: bool,
+        new_sorter: impl FnOnce(
+            Arc<ResolverComms>,
+            &[Place],
+            &dyn Spawner,
+        ) -> (RS, Arc<ResolverCommonData<V, RS::TrackId>>),
+    ) -> Self {
         let debug_track = vec![];
 
         if cfg!(cr_paranoia_mode) || PARANOIA {
@@ -177,296 +595,5973 @@ impl<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig>
             exec_order_buffer_hint: AtomicIsize::new(0),
             registration_complete: AtomicBool::new(false),
             rw_panicked: AtomicBool::new(false),
-            rw_panic: Cell::new(None),
+            rw_panic: Mutex::new(None),
+            cancelled: AtomicBool::new(false),
+            resolve_limit: AtomicUsize::new(usize::MAX),
+            executed: AtomicUsize::new(0),
         }
         .to(Arc::new);
 
-        let (sorter, common) = RS::new(opts, comms.clone(), &debug_track);
+        let (sorter, common) = new_sorter(comms.clone(), &debug_track, spawner.as_ref());
+        let silent = sorter.silent();
 
         Self {
             call_count: 0,
-            sorter,
             comms: comms.clone(),
 
-            resolution_window_handle: ResolutionWindow::<V, RS::TrackId, RS::Config>::run(
-                comms,
-                common.clone(),
-                &debug_track,
-                threads,
-            )
-            .to(Some),
+            resolution_window_handle: match threads {
+                0 => None,
+                _ if lazy => None,
+                _ => ResolutionWindow::<V, RS::TrackId, RS::Config>::run(
+                    comms,
+                    common.clone(),
+                    &debug_track,
+                    threads,
+                    sorter.worker_affinity(),
+                    sorter.panic_policy(),
+                    sorter.idle_policy(),
+                    silent,
+                    spawner.as_ref(),
+                )
+                .to(Some),
+            },
+            spawner,
+            threads,
+            lazy,
+            lazy_window: match threads {
+                0 => None,
+                _ if lazy => Some(threads),
+                _ => None,
+            },
+            fanout: match sorter.track_fanout() {
+                // Safety: Dereferencing as & in &self context.
+                true => vec![0; unsafe { common.values.u_deref() }.variables.len()].to(Some),
+                false => None,
+            },
+            dependencies: match sorter.track_dependencies() {
+                true => Some(DependencyGraph::default()),
+                false => None,
+            },
+            inline_cursor: match threads {
+                0 => Some(0),
+                _ => None,
+            },
 
+            sorter,
             common,
             stats: Stats::new(),
             debug_track,
+            resumed: None,
+            contexts: Vec::new(),
+            resolvers_released: false,
+            verbose_drop: false,
+            silent,
+            arity: ArityHistogram::default(),
+            flushed: FlushReport::default(),
+            track_advance: None,
+            tracked_reported: -1,
             phantom: PhantomData,
         }
     }
 
     pub fn set_value(&mut self, key: Place, value: V) {
-        self.sorter.set_value(key, value)
+        if let Err(e) = self.try_set_value(key, value) {
+            panic!("{}", e);
+        }
     }
 
-    pub fn add_resolution<F>(&mut self, inputs: &[Place], outputs: &[Place], f: F)
-    where
-        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
-    {
-        self.sorter.add_resolution(inputs, outputs, f)
-    }
+    /// Same as `set_value`, but returns an error instead of panicking when
+    /// the registration is closed, the value is already set, or `key` is
+    /// beyond the capacity and the policy is `CapacityPolicy::Error`.
+    pub fn try_set_value(&mut self, key: Place, value: V) -> Result<(), CircuitResolverError> {
+        if self.is_registration_open() == false {
+            return Err(CircuitResolverError::RegistrationClosed);
+        }
 
-    pub fn wait_till_resolved(&mut self) {
-        self.wait_till_resolved_impl(true);
-    }
+        self.ensure_capacity(&[key])?;
 
-    pub fn wait_till_resolved_impl(&mut self, report: bool) {
-        if self
-            .comms
-            .registration_complete
-            .load(std::sync::atomic::Ordering::Relaxed)
-        {
-            return;
+        if self.place_status(key) == PlaceStatus::Hole {
+            return Err(CircuitResolverError::DeclaredHole { place: key });
         }
 
-        self.sorter.final_flush();
+        if self.resumed.is_some() && self.is_restored(key) {
+            return Ok(());
+        }
 
-        self.stats.registration_time = self.stats.started_at.elapsed();
+        if self.apply_duplicate_set_policy(key, value)? {
+            return Ok(());
+        }
 
-        self.comms
-            .registration_complete
-            .store(true, std::sync::atomic::Ordering::Relaxed);
+        self.sorter.set_value(key, value);
 
-        self.resolution_window_handle
-            .take()
-            .expect("Attempting to join resolution window handler for second time.")
-            .join()
-            .unwrap(); // Just propagate panics. Those are unhandled, unlike the ones from `rw_panic`.
+        self.report_track_advance();
 
-        self.stats.total_resolution_time = self.stats.started_at.elapsed();
+        self.common.subscriptions.notify(std::iter::once(key));
 
-        // Propage panic from the resolution window handler.
-        if self
-            .comms
-            .rw_panicked
-            .load(std::sync::atomic::Ordering::Relaxed)
-        {
-            if let Some(e) = self.comms.rw_panic.take() {
-                resume_unwind(e);
-            } else {
-                log!("Resolution window panicked, but no panic payload stored.");
-                return;
-            }
+        self.stats.values_added += 1;
+        if let crate::cs::VariableType::Witness = key.get_type() {
+            self.stats.witnesses_added += 1;
         }
 
-        match report {
-            true => {
-                log!("CR stats {:#?}", self.stats);
-            }
-            false if cfg!(test) || cfg!(debug_assertions) => {
-                print!(" resolution time {:?}...", self.stats.total_resolution_time);
-            }
-            _ => {}
-        }
+        Ok(())
+    }
 
-        self.sorter.write_sequence();
+    /// Declares the places from 0 up to and including `up_to` as inputs that
+    /// will be set, so bulk loading them doesn't advance the tracked prefix
+    /// value by value. The places that are tracked already are left as is.
+    ///
+    /// The declared places must be set before resolutions take them as
+    /// inputs or they are awaited, which fail with `NotSet` otherwise.
+    /// Resolutions registered earlier mustn't wait on the declared places.
+    pub fn declare_tracked_prefix(&mut self, up_to: Place) {
+        if self.is_registration_open() == false {
+            panic!("{}", CircuitResolverError::RegistrationClosed);
+        }
 
-        if cfg!(cr_paranoia_mode) || PARANOIA {
-            log!("CR {:?}", unsafe {
-                self.common.awaiters_broker.stats.u_deref()
-            });
+        if let Err(e) = self.ensure_capacity(&[up_to]) {
+            panic!("{}", e);
         }
+
+        self.sorter.declare_tracked_prefix(up_to.raw_ix() + 1);
+
+        self.report_track_advance();
     }
 
-    pub fn retrieve_sequence(&mut self) -> &ResolutionRecord {
-        assert!(self
-            .comms
-            .registration_complete
-            .load(std::sync::atomic::Ordering::Relaxed));
-        self.sorter.retrieve_sequence()
+    /// Declares that the value at `place` is never computed, so the tracked
+    /// prefix can move past it and awaiters on the later places don't report
+    /// it as a hole. The place can't be set, produced, used as an input or
+    /// awaited afterwards, and reads of it report `Hole`.
+    ///
+    /// Resolutions registered earlier mustn't take the place as an input.
+    pub fn declare_hole(&mut self, place: Place) {
+        if self.is_registration_open() == false {
+            panic!("{}", CircuitResolverError::RegistrationClosed);
+        }
+
+        if let Err(e) = self.ensure_capacity(&[place]) {
+            panic!("{}", e);
+        }
+
+        self.sorter.declare_hole(place);
+
+        self.report_track_advance();
     }
 
-    pub fn clear(&mut self) {
-        // TODO: implement
+    /// Calls `cb` with the new highest place up to which all places are
+    /// tracked, whenever it advances on setting values, adding resolutions or
+    /// flushing. Only the advances after the call are reported, each once,
+    /// with the places increasing. Replaces the previous callback.
+    pub fn on_track_advance(&mut self, cb: Box<dyn FnMut(Place) + Send>) {
+        // Safety: Reading a single value that is only written by this thread.
+        self.tracked_reported = unsafe { self.common.values.u_deref().max_tracked };
+        self.track_advance = Some(cb);
     }
-}
 
-impl<V: SmallField, RS: ResolverSortingMode<V> + 'static, CFG: CSResolverConfig> WitnessSource<V>
-    for MtCircuitResolver<V, RS, CFG>
-{
-    const PRODUCES_VALUES: bool = true;
+    fn report_track_advance(&mut self) {
+        let Some(cb) = &mut self.track_advance else {
+            return;
+        };
 
-    fn try_get_value(&self, variable: Place) -> Option<V> {
-        let (v, md) = unsafe { self.common.values.u_deref().get_item_ref(variable) };
+        // Safety: Reading a single value that is only written by this thread.
+        let max_tracked = unsafe { self.common.values.u_deref().max_tracked };
 
-        match md.is_resolved() {
-            true => {
-                fence(std::sync::atomic::Ordering::Acquire);
-                Some(*v)
-            }
-            false => None,
+        if max_tracked > self.tracked_reported {
+            self.tracked_reported = max_tracked;
+
+            cb(Place::from_variable_index(max_tracked as u64));
         }
     }
 
-    fn get_value_unchecked(&self, variable: Place) -> V {
-        // TODO: Should this fn be marked as unsafe?
-
+    /// Fails with `NotSet` for a declared place that isn't set yet, and with
+    /// `DeclaredHole` for a hole.
+    fn ensure_set(&self, places: &[Place]) -> Result<(), CircuitResolverError> {
         // Safety: Dereferencing as & in &self context.
-        let (r, md) = unsafe { self.common.values.u_deref().get_item_ref(variable) };
-        // log!("gvu: {:0>8} -> {}", variable.0, r);
+        let values = unsafe { self.common.values.u_deref() };
 
-        debug_assert!(
-            md.is_resolved(),
-            "Attempted to get value of unresolved variable."
-        );
+        for place in places {
+            let md = &values.get_item_ref(*place).1;
 
-        *r
-    }
-}
+            if md.is_declared() {
+                return Err(CircuitResolverError::NotSet { place: *place });
+            }
 
-impl<V: SmallField, RS: ResolverSortingMode<V> + 'static, CFG: CSResolverConfig> CSWitnessSource<V>
-    for MtCircuitResolver<V, RS, CFG>
-{
-}
+            if md.is_hole() {
+                return Err(CircuitResolverError::DeclaredHole { place: *place });
+            }
+        }
 
-impl<V: SmallField, RS: ResolverSortingMode<V> + 'static, CFG: CSResolverConfig>
-    WitnessSourceAwaitable<V> for MtCircuitResolver<V, RS, CFG>
-{
-    type Awaiter<'a> = awaiters::Awaiter<'a, RS::TrackId>;
+        Ok(())
+    }
 
-    fn get_awaiter<const N: usize>(&mut self, vars: [Place; N]) -> awaiters::Awaiter<RS::TrackId> {
-        // Safety: We're only getting the metadata address for an item, which is
-        // immutable and the max_tracked value, which isn't but read only once
-        // for the duration of the reference.
-        let values = unsafe { self.common.values.u_deref() };
+    /// Returns `true` if setting `key` was handled by the duplicate set
+    /// policy, or an error if the policy rejects it. Otherwise the sorter
+    /// sets it.
+    fn apply_duplicate_set_policy(
+        &mut self,
+        key: Place,
+        value: V,
+    ) -> Result<bool, CircuitResolverError> {
+        // Safety: Dereferencing as & in &self context.
+        let (stored, md) = unsafe { self.common.values.u_deref() }.get_item_ref(key);
 
-        if values.max_tracked < vars.iter().map(|x| x.as_any_index()).max().unwrap() as i64 {
-            panic!("The awaiter will never resolve since the awaited variable can't be computed based on currently available registrations. You have holes!!!");
+        if md.is_resolved() == false {
+            return Ok(false);
         }
 
-        // We're picking the item that will be resolved last among other inputs.
-        let md = vars
-            .into_iter()
-            .map(|x| &values.get_item_ref(x).1)
-            .max_by_key(|x| x.tracker)
-            .unwrap();
+        let duplicate = Err(CircuitResolverError::DuplicateValue { place: key });
 
-        let r = awaiters::AwaitersBroker::register(&self.common.awaiters_broker, &self.comms, md);
+        match self.sorter.duplicate_set_policy() {
+            DuplicateSetPolicy::Panic => duplicate,
+            DuplicateSetPolicy::IgnoreIfEqual if *stored == value => Ok(true),
+            DuplicateSetPolicy::IgnoreIfEqual => duplicate,
+            DuplicateSetPolicy::Overwrite => {
+                self.wait_till_exec_order_resolved();
 
-        self.sorter.flush();
+                // Safety: All the resolvers in the execution order are
+                // resolved, so no other thread is accessing the value.
+                unsafe { self.common.values.u_deref().get_item_ref_mut(key).0 = value };
 
-        r
+                Ok(true)
+            }
+        }
     }
-}
-
-// impl Drop for CircuitResolver
 
-impl<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig> Drop
-    for MtCircuitResolver<V, RS, CFG>
-{
-    fn drop(&mut self) {
-        if cfg!(test) || cfg!(debug_assertions) {
-            print!("Starting drop of CircuitResolver (If this hangs, it's bad)...");
+    pub fn add_resolution<F>(&mut self, inputs: &[Place], outputs: &[Place], f: F)
+    where
+        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+    {
+        if let Err(e) = self.try_add_resolution(inputs, outputs, f) {
+            panic!("{}", e);
         }
-        self.wait_till_resolved_impl(false);
+    }
 
-        if cfg!(test) || cfg!(debug_assertions) {
-            log!("ok");
+    /// Same as `add_resolution`, but returns an error instead of panicking
+    /// when the registration is closed, or a place is beyond the capacity
+    /// and the policy is `CapacityPolicy::Error`.
+    pub fn try_add_resolution<F>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        f: F,
+    ) -> Result<(), CircuitResolverError>
+    where
+        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+    {
+        self.try_add_resolution_impl(inputs, outputs, Priority::Normal, f)
+    }
+
+    /// Same as `add_resolution`, but among the registrations that become
+    /// ready at the same time, e.g. when the value they wait on is set, those
+    /// with a higher priority are placed first in the execution order. Lets
+    /// latency-critical outputs resolve ahead of the bulk of the witness.
+    /// The priority doesn't reorder registrations that are ready when
+    /// they're added, and is ignored in playback, which follows the record.
+    pub fn add_resolution_prioritized<F>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        priority: Priority,
+        f: F,
+    ) where
+        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+    {
+        if let Err(e) = self.try_add_resolution_impl(inputs, outputs, priority, f) {
+            panic!("{}", e);
         }
     }
-}
 
-#[cfg(test)]
+    fn try_add_resolution_impl<F>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        priority: Priority,
+        f: F,
+    ) -> Result<(), CircuitResolverError>
+    where
+        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+    {
+        assert!(
+            self.resolvers_released == false,
+            "Attempting to add a resolution after the resolvers were released."
+        );
+
+        if self.is_registration_open() == false {
+            return Err(CircuitResolverError::RegistrationClosed);
+        }
+        self.ensure_capacity(inputs)?;
+        self.ensure_capacity(outputs)?;
+        self.ensure_set(inputs)?;
+
+        if let Some(resumed) = &mut self.resumed {
+            let registration = resumed.next;
+            resumed.next += 1;
+
+            if resumed.invoked.contains(&registration) {
+                return Ok(());
+            }
+
+            resumed.registered.push(registration);
+        }
+
+        *self.arity.inputs.entry(inputs.len()).or_default() += 1;
+        *self.arity.outputs.entry(outputs.len()).or_default() += 1;
+
+        if let Some(fanout) = &mut self.fanout {
+            inputs.iter().for_each(|x| fanout[x.raw_ix()] += 1);
+        }
+
+        if let Some(dependencies) = &mut self.dependencies {
+            dependencies.add_resolution(inputs, outputs);
+        }
+
+        self.sorter
+            .add_resolution_prioritized(inputs, outputs, priority, f);
+
+        self.report_track_advance();
+
+        self.stats.registrations_added += 1;
+
+        self.apply_backpressure();
+
+        Ok(())
+    }
+
+    /// Applies the capacity policy to the places that don't fit into the
+    /// values storage.
+    fn ensure_capacity(&mut self, places: &[Place]) -> Result<(), CapacityExceeded> {
+        // Safety: The storage is replaced only on this thread.
+        let capacity = unsafe { self.common.values.u_deref() }.variables.len();
+
+        let place = match places.iter().max_by_key(|x| x.raw_ix()) {
+            Some(x) if x.raw_ix() >= capacity => *x,
+            _ => return Ok(()),
+        };
+
+        let e = CapacityExceeded { place, capacity };
+
+        match self.sorter.capacity_policy() {
+            CapacityPolicy::Panic => panic!("{}", e),
+            CapacityPolicy::Error => Err(e),
+            CapacityPolicy::Grow => {
+                self.grow_values(std::cmp::max(place.raw_ix() + 1, capacity * 2));
+                Ok(())
+            }
+        }
+    }
+
+    /// Replaces the values storage with a larger one. The workers access the
+    /// values while invoking the resolvers, so this waits until everything
+    /// already in the execution order is resolved.
+    fn grow_values(&mut self, len: usize) {
+        self.wait_till_exec_order_resolved();
+
+        if cfg!(cr_paranoia_mode) || PARANOIA {
+            log!("CR: growing values storage to {}", len);
+        }
+
+        // Safety: All the resolvers in the execution order are resolved and
+        // the rest are invoked only after being added to it by this thread, so
+        // no other thread is accessing the values.
+        unsafe {
+            self.common
+                .values
+                .u_deref_mut()
+                .grow(len, || V::from_u64_unchecked(0));
+        }
+
+        if let Some(fanout) = &mut self.fanout {
+            fanout.resize(len, 0);
+        }
+    }
+
+    /// Waits until all the resolvers currently in the execution order are
+    /// resolved, after which the workers don't access the values until new
+    /// ones are added.
+    fn wait_till_exec_order_resolved(&self) {
+        // Without a window nothing accesses the values.
+        if self.inline_cursor.is_some() || self.lazy_window.is_some() {
+            return;
+        }
+
+        // The resolvers may lie past a `resolve_until` bound.
+        self.lift_resolve_limit();
+
+        let size = self.common.exec_order.lock().unwrap().size as u64;
+
+        while self
+            .common
+            .resolved_count
+            .load(std::sync::atomic::Ordering::Acquire)
+            < size
+        {
+            assert!(
+                self.comms
+                    .rw_panicked
+                    .load(std::sync::atomic::Ordering::Relaxed)
+                    == false,
+                "Resolution window panicked while waiting for the execution order."
+            );
+
+            std::thread::yield_now();
+        }
+    }
+
+    /// Lifts a `resolve_until` bound and wakes the resolution window, which
+    /// may be idling at the bound.
+    fn lift_resolve_limit(&self) {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        self.comms.resolve_limit.store(usize::MAX, Relaxed);
+        self.comms.exec_order_buffer_hint.store(1, Relaxed);
+    }
+
+    /// Blocks while more than `max_pending_resolvers` resolvers wait in the
+    /// execution order, until at most half of them are left. Only the ordered
+    /// resolvers are counted, as the ones waiting for inputs can't drain.
+    /// Those are waited for only once published, as the playback orders
+    /// resolvers past the published size, which the window can't run yet.
+    fn apply_backpressure(&mut self) {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        let Some(max) = self.sorter.max_pending_resolvers() else {
+            return;
+        };
+
+        if self
+            .sorter
+            .progress()
+            .ordered
+            .saturating_sub(self.resolved_count())
+            <= max as u64
+        {
+            return;
+        }
+
+        // Publishes the batched resolvers, so all the counted ones can drain.
+        self.sorter.flush();
+
+        if self.inline_cursor.is_some() {
+            Self::resolve_inline(
+                &self.common,
+                &self.debug_track,
+                &mut self.inline_cursor,
+                self.sorter.panic_policy(),
+            );
+            return;
+        }
+
+        self.spawn_window();
+
+        // The pending resolvers may lie past a `resolve_until` bound.
+        self.lift_resolve_limit();
+
+        let pending = |this: &Self| {
+            (this.common.exec_order.lock().unwrap().size as u64)
+                .saturating_sub(this.resolved_count())
+        };
+
+        while pending(self) > max as u64 / 2 {
+            assert!(
+                self.comms.rw_panicked.load(Relaxed) == false,
+                "Resolution window panicked while draining the pending resolvers."
+            );
+
+            std::thread::yield_now();
+        }
+    }
+
+    /// Whether values and resolutions can still be added, i.e. neither
+    /// `wait_till_resolved` nor `cancel` was called.
+    pub fn is_registration_open(&self) -> bool {
+        self.comms
+            .registration_complete
+            .load(std::sync::atomic::Ordering::Relaxed)
+            == false
+    }
+
+    fn assert_registration_open(&self) {
+        assert!(
+            self.is_registration_open(),
+            "Registration is closed, values and resolutions can't be added after \
+             `wait_till_resolved` or `cancel`."
+        );
+    }
+
+    /// Invokes the resolvers that were added to the execution order since the
+    /// last call. Does nothing if the resolver runs with worker threads.
+    fn resolve_inline(
+        common: &ResolverCommonData<V, RS::TrackId>,
+        debug_track: &[Place],
+        cursor: &mut Option<usize>,
+        panic_policy: PanicPolicy,
+    ) {
+        if let Some(start) = cursor {
+            let end = common.exec_order.lock().unwrap().size;
+
+            // Safety: The resolvers in `start..end` weren't invoked yet and
+            // there is no resolution window running.
+            unsafe {
+                resolution_window::resolve_inline::<V, RS::TrackId, RS::Config>(
+                    common,
+                    debug_track,
+                    *start..end,
+                    panic_policy,
+                );
+            }
+
+            *start = end;
+        }
+    }
+
+    /// Adds a resolution producing a single value of type `T`, stored across
+    /// `outputs`. The returned handle reads the value back once resolved.
+    pub fn register_castable_output<T, const N: usize, F>(
+        &mut self,
+        inputs: &[Place],
+        outputs: [Place; N],
+        f: F,
+    ) -> ResolvedHandle<T, N>
+    where
+        T: TrivialWitnessCastable<V, N>,
+        F: FnOnce(&[V]) -> T + Send + Sync,
+    {
+        self.add_resolution(inputs, &outputs, move |ins, outs| {
+            outs.extend(f(ins).cast_into_field_elements())
+        });
+
+        ResolvedHandle::new(outputs)
+    }
+
+    /// Adds a resolution that gets a reference to `ctx` on invocation. The
+    /// resolver holds a single clone of the context for all the resolutions
+    /// sharing it, instead of each closure capturing its own.
+    pub fn add_resolution_with_ctx<C, F>(
+        &mut self,
+        ctx: &Arc<C>,
+        inputs: &[Place],
+        outputs: &[Place],
+        f: F,
+    ) where
+        C: Send + Sync + 'static,
+        F: FnOnce(&C, &[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+    {
+        let ptr = Arc::as_ptr(ctx);
+
+        if self
+            .contexts
+            .iter()
+            .rev()
+            .any(|x| std::ptr::eq(Arc::as_ptr(x) as *const (), ptr as *const ()))
+            == false
+        {
+            self.contexts.push(ctx.clone());
+        }
+
+        let shared = SharedCtx(ptr);
+
+        self.add_resolution(inputs, outputs, move |ins, outs| {
+            let shared = shared;
+            // Safety: The context is kept alive by `self.contexts`.
+            f(unsafe { &*shared.0 }, ins, outs)
+        })
+    }
+
+    /// Adds a resolution that also reads `external_inputs` from `external`,
+    /// a witness source holding values of another circuit, e.g. a finished
+    /// resolver. Only `local_inputs` are dependencies in this resolver; the
+    /// external values are read on invocation and passed to `f` before the
+    /// local ones. The invocation panics if an external input isn't resolved.
+    ///
+    /// `WitnessSource` isn't object safe, so the source is a concrete type.
+    /// It's kept alive the same way as the context of
+    /// `add_resolution_with_ctx`.
+    pub fn add_resolution_cross<S, F>(
+        &mut self,
+        external: &Arc<S>,
+        external_inputs: &[Place],
+        local_inputs: &[Place],
+        outputs: &[Place],
+        f: F,
+    ) where
+        S: WitnessSource<V>,
+        F: FnOnce(&[V], &[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+    {
+        let external_inputs = external_inputs.to_vec();
+
+        self.add_resolution_with_ctx(
+            external,
+            local_inputs,
+            outputs,
+            move |external: &S, ins, outs| {
+                let external_values = external_inputs
+                    .iter()
+                    .map(|x| {
+                        external
+                            .try_get_value(*x)
+                            .unwrap_or_else(|| panic!("External input {:?} is not resolved.", x))
+                    })
+                    .collect::<Vec<_>>();
+
+                f(&external_values, ins, outs)
+            },
+        )
+    }
+
+    /// Adds a resolution that sets its outputs by index instead of pushing
+    /// them in order. Panics on invocation if an output is set twice or left
+    /// unset.
+    pub fn add_resolution_keyed<F>(&mut self, inputs: &[Place], outputs: &[Place], f: F)
+    where
+        F: FnOnce(&[V], &mut KeyedDstBuffer<V>) + Send + Sync,
+    {
+        let len = outputs.len();
+
+        self.add_resolution(inputs, outputs, move |ins, outs| {
+            let mut keyed = KeyedDstBuffer::new(len);
+
+            f(ins, &mut keyed);
+
+            keyed.write_to(outs);
+        })
+    }
+
+    /// Adds a resolution whose outputs are checked with `assert_fn` right
+    /// after it runs, panicking with the registration number if the check
+    /// fails. The check runs only in debug builds, in release builds this is
+    /// the same as `add_resolution`.
+    pub fn add_resolution_with_assert<F, A>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        f: F,
+        assert_fn: A,
+    ) where
+        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>) + Send + Sync,
+        A: Fn(&[V]) -> bool + Send + Sync,
+    {
+        if cfg!(debug_assertions) == false {
+            return self.add_resolution(inputs, outputs, f);
+        }
+
+        let registration = self.stats.registrations_added as crate::dag::guide::RegistrationNum;
+        let len = outputs.len();
+
+        self.add_resolution(inputs, outputs, move |ins, outs| {
+            let mut values = Vec::with_capacity(len);
+
+            f(ins, &mut DstBuffer::Vector(&mut values));
+
+            assert!(
+                assert_fn(&values),
+                "The outputs of registration {} failed the assertion.",
+                registration
+            );
+
+            outs.extend(values);
+        })
+    }
+
+    /// Adds a resolution that gets the scratch space of the invoking thread,
+    /// which is cleared after each invocation.
+    pub fn add_resolution_scratch<F>(&mut self, inputs: &[Place], outputs: &[Place], f: F)
+    where
+        F: FnOnce(&[V], &mut DstBuffer<'_, '_, V>, &mut ScratchSpace<V>) + Send + Sync,
+    {
+        self.add_resolution(inputs, outputs, move |ins, outs| {
+            scratch::with_scratch(|scratch| f(ins, outs, scratch))
+        })
+    }
+
+    /// Adds the resolution of `outputs[i] = lhs[i] op rhs[i]` over runs of
+    /// places. The runs are split into chunks of `VECTORIZED_CHUNK_LEN`, each
+    /// registered as a single resolver that computes its chunk in one loop,
+    /// instead of a resolver invocation per element.
+    ///
+    /// The resolver is generic over the field, so the kernel is a plain loop
+    /// left to the compiler to vectorize, rather than the field specific SIMD
+    /// implementations.
+    pub fn add_vectorized_resolution(
+        &mut self,
+        op: ElementwiseOp,
+        lhs: &[Place],
+        rhs: &[Place],
+        outputs: &[Place],
+    ) {
+        assert!(
+            lhs.len() == rhs.len() && lhs.len() == outputs.len(),
+            "Vectorized resolution runs differ in length: {}, {} and {} outputs.",
+            lhs.len(),
+            rhs.len(),
+            outputs.len()
+        );
+
+        let mut inputs = Vec::with_capacity(VECTORIZED_CHUNK_LEN * 2);
+
+        for ((lhs, rhs), outputs) in lhs
+            .chunks(VECTORIZED_CHUNK_LEN)
+            .zip(rhs.chunks(VECTORIZED_CHUNK_LEN))
+            .zip(outputs.chunks(VECTORIZED_CHUNK_LEN))
+        {
+            inputs.clear();
+            inputs.extend_from_slice(lhs);
+            inputs.extend_from_slice(rhs);
+
+            self.add_resolution_scratch(&inputs, outputs, move |ins, outs, scratch| {
+                let (lhs, rhs) = ins.split_at(ins.len() / 2);
+
+                op.apply(lhs, rhs, &mut scratch.values);
+
+                outs.extend(scratch.values.iter().copied());
+            });
+        }
+    }
+
+    /// Same as `get_awaiter`, but reports an awaiter that would never resolve
+    /// instead of panicking.
+    pub fn try_get_awaiter<const N: usize>(
+        &mut self,
+        vars: [Place; N],
+    ) -> Result<awaiters::Awaiter<RS::TrackId>, CircuitResolverError> {
+        // Safety: We're only getting the metadata address for an item, which is
+        // immutable and the max_tracked value, which isn't but read only once
+        // for the duration of the reference.
+        let values = unsafe { self.common.values.u_deref() };
+
+        let requested = vars.iter().max_by_key(|x| x.as_any_index()).unwrap();
+
+        if values.max_tracked < requested.as_any_index() as i64 {
+            return Err(CircuitResolverError::Hole {
+                requested: *requested,
+                max_tracked: match values.max_tracked {
+                    -1 => None,
+                    x => Some(Place::from_variable_index(x as u64)),
+                },
+            });
+        }
+
+        self.ensure_set(&vars)?;
+
+        self.spawn_window();
+
+        // Safety: Same as above.
+        let values = unsafe { self.common.values.u_deref() };
+
+        // We're picking the item that will be resolved last among other inputs.
+        let md = vars
+            .into_iter()
+            .map(|x| &values.get_item_ref(x).1)
+            .max_by_key(|x| x.tracker)
+            .unwrap();
+
+        // The awaited values may lie past a `resolve_until` bound.
+        self.lift_resolve_limit();
+
+        // Flushed first, so a prioritized awaiter finds its resolvers in the
+        // execution order.
+        self.sorter.flush();
+
+        let broker = &self.common.awaiters_broker;
+
+        let r = match self.sorter.prioritize_awaited() && self.inline_cursor.is_none() {
+            true => awaiters::AwaitersBroker::register_prioritized(broker, &self.comms, md, &vars),
+            false => awaiters::AwaitersBroker::register(broker, &self.comms, md),
+        };
+
+        Self::resolve_inline(
+            &self.common,
+            &self.debug_track,
+            &mut self.inline_cursor,
+            self.sorter.panic_policy(),
+        );
+
+        Ok(r)
+    }
+
+    /// Checks `places` the way `get_awaiter` does, returning those it would
+    /// fail on: places past the tracked prefix, declared holes and declared
+    /// inputs that weren't set. Past the tracked prefix are both the places
+    /// nothing produces and those behind a gap in it. A place may still
+    /// become reachable through later registrations, so the result is final
+    /// once the registration is complete.
+    pub fn check_reachable(&self, places: &[Place]) -> Result<(), Vec<Place>> {
+        // Safety: Dereferencing as & in &self context. `max_tracked` is
+        // written only by this thread, the declared and hole flags are set
+        // only by it too.
+        let values = unsafe { self.common.values.u_deref() };
+
+        let unreachable = places
+            .iter()
+            .copied()
+            .filter(|x| {
+                x.as_any_index() as i64 > values.max_tracked || {
+                    let md = &values.get_item_ref(*x).1;
+
+                    md.is_declared() || md.is_hole()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        match unreachable.is_empty() {
+            true => Ok(()),
+            false => Err(unreachable),
+        }
+    }
+
+    fn is_restored(&self, key: Place) -> bool {
+        // Safety: Restored values are never written to again, and this thread
+        // is the only one to write new ones.
+        unsafe {
+            self.common
+                .values
+                .u_deref()
+                .get_item_ref(key)
+                .1
+                .is_resolved()
+        }
+    }
+
+    pub fn wait_till_resolved(&mut self) {
+        self.wait_till_resolved_impl(true);
+    }
+
+    /// Same as `wait_till_resolved`, but returns the time spent in registration
+    /// and resolution separately.
+    pub fn wait_till_resolved_timed(&mut self) -> ResolutionTiming {
+        self.wait_till_resolved_impl(true);
+
+        ResolutionTiming {
+            registration: self.stats.registration_time,
+            resolution: self
+                .stats
+                .total_resolution_time
+                .saturating_sub(self.stats.registration_time),
+            total: self.stats.total_resolution_time,
+        }
+    }
+
+    /// Moves the registrations that can be resolved into the execution order,
+    /// and reports the progress since the previous call. Allows pacing the
+    /// registration against the resolution.
+    pub fn flush(&mut self) -> FlushReport {
+        self.assert_registration_open();
+
+        self.sorter.flush();
+
+        self.report_track_advance();
+
+        Self::resolve_inline(
+            &self.common,
+            &self.debug_track,
+            &mut self.inline_cursor,
+            self.sorter.panic_policy(),
+        );
+
+        let progress = self.sorter.progress();
+
+        let report = FlushReport {
+            internalized: progress.internalized - self.flushed.internalized,
+            ordered: progress.ordered - self.flushed.ordered,
+        };
+
+        self.flushed = progress;
+
+        report
+    }
+
+    /// Flushes and blocks until every resolver in the execution order is
+    /// resolved. Unlike `wait_till_resolved`, registration stays open, so
+    /// more resolutions can be added and the barrier reached again. The
+    /// registrations still waiting for their inputs are not waited for.
+    pub fn barrier(&mut self) {
+        self.assert_registration_open();
+
+        self.sorter.flush();
+
+        if self.inline_cursor.is_some() {
+            Self::resolve_inline(
+                &self.common,
+                &self.debug_track,
+                &mut self.inline_cursor,
+                self.sorter.panic_policy(),
+            );
+            return;
+        }
+
+        self.spawn_window();
+
+        // The resolvers may lie past a `resolve_until` bound.
+        self.lift_resolve_limit();
+
+        self.wait_till_exec_order_resolved();
+    }
+
+    /// The registrations that are blocked on an untracked input, in the order
+    /// they were added. Registration can't complete while any are left, so
+    /// this is meant for finding the missing values before
+    /// `wait_till_resolved`, which panics on them.
+    pub fn pending_registrations(&self) -> Vec<PendingInfo> {
+        self.sorter.pending_registrations()
+    }
+
+    /// Same as `wait_till_resolved`, but returns an error instead of
+    /// panicking when registrations are blocked by a hole or a cycle, or the
+    /// resolution window panicked. On a blocked registration the registration
+    /// stays open, so the missing values can still be set, or the resolution
+    /// cancelled.
+    pub fn try_wait_till_resolved(&mut self) -> Result<(), CircuitResolverError> {
+        if self.is_registration_open() {
+            if let Some(e) = self.blocked_registrations_error() {
+                return Err(e);
+            }
+        }
+
+        if self.close_registration() == false {
+            return Ok(());
+        }
+
+        self.spawn_window();
+
+        match self.inline_cursor {
+            Some(_) => Self::resolve_inline(
+                &self.common,
+                &self.debug_track,
+                &mut self.inline_cursor,
+                self.sorter.panic_policy(),
+            ),
+            None => self
+                .resolution_window_handle
+                .take()
+                .expect("Attempting to join resolution window handler for second time.")
+                .join()
+                .map_err(CircuitResolverError::WindowPanicked)?,
+        }
+
+        self.complete_resolution(true).map_err(Into::into)
+    }
+
+    /// Follows the first blocked registration through the registrations
+    /// producing the place it waits on, until a place no registration
+    /// produces, which is a hole, or a registration already visited, which
+    /// closes a cycle.
+    fn blocked_registrations_error(&self) -> Option<CircuitResolverError> {
+        let pending = self.sorter.pending_registrations();
+
+        let producer = |place: Place| pending.iter().position(|x| x.outputs.contains(&place));
+
+        let mut path = vec![0];
+
+        loop {
+            let waiting_on = pending.get(*path.last().unwrap())?.waiting_on;
+
+            match producer(waiting_on) {
+                None => {
+                    break Some(CircuitResolverError::Hole {
+                        requested: waiting_on,
+                        max_tracked: self.max_tracked_place(),
+                    })
+                }
+                Some(next) => match path.iter().position(|x| *x == next) {
+                    Some(start) => {
+                        break Some(CircuitResolverError::Cycle {
+                            places: path[start..]
+                                .iter()
+                                .map(|x| pending[*x].waiting_on)
+                                .collect(),
+                        })
+                    }
+                    None => path.push(next),
+                },
+            }
+        }
+    }
+
+    /// Closes the registration and returns a future that resolves once all
+    /// the values are resolved, for use in async code instead of
+    /// `wait_till_resolved`. A panic in the resolution window is returned as
+    /// an error. The future is ready immediately in inline mode, or when the
+    /// resolution was already waited for.
+    pub fn completion_future(&mut self) -> CompletionFuture<'_, V, RS, CFG> {
+        if self.close_registration() == false {
+            return CompletionFuture {
+                resolver: self,
+                signal: None,
+                owned: false,
+            };
+        }
+
+        self.spawn_window();
+
+        let signal = match self.inline_cursor {
+            Some(_) => {
+                Self::resolve_inline(
+                    &self.common,
+                    &self.debug_track,
+                    &mut self.inline_cursor,
+                    self.sorter.panic_policy(),
+                );
+                None
+            }
+            None => {
+                let handle = self
+                    .resolution_window_handle
+                    .take()
+                    .expect("Attempting to join resolution window handler for second time.");
+
+                let signal = Arc::new(CompletionSignal::default());
+                let s = signal.clone();
+
+                self.spawner.spawn(Box::new(move || {
+                    let result = handle.join();
+
+                    let mut slot = s.slot.lock().unwrap();
+                    slot.finished = true;
+                    slot.join_panic = result.err();
+                    let waker = slot.waker.take();
+                    drop(slot);
+
+                    s.finished.notify_all();
+
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }));
+
+                Some(signal)
+            }
+        };
+
+        CompletionFuture {
+            resolver: self,
+            signal,
+            owned: true,
+        }
+    }
+
+    /// Returns `false` if the registration was already closed.
+    fn close_registration(&mut self) -> bool {
+        if self
+            .comms
+            .registration_complete
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return false;
+        }
+
+        self.sorter.final_flush();
+
+        self.stats.registration_time = self.stats.started_at.elapsed();
+
+        self.lift_resolve_limit();
+        self.comms
+            .registration_complete
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+
+        true
+    }
+
+    /// Resolves the values until `target` is resolved and returns, leaving the
+    /// resolvers that come after its one in the execution order pending. Those
+    /// are resolved by a later `resolve_until` with a later target, or by
+    /// `wait_till_resolved`. `barrier`, `get_awaiter` and the calls that wait
+    /// for the execution order lift the bound.
+    ///
+    /// The rest stays pending only without threads or with a window created
+    /// by `new_lazy`, which `resolve_until` spawns already bounded. A window
+    /// that is already running stops taking resolvers at the bound, but the
+    /// ones it took before are still resolved.
+    ///
+    /// Finding the resolver of `target` is linear in the execution order size.
+    pub fn resolve_until(&mut self, target: Place) {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        if self.comms.registration_complete.load(Relaxed) {
+            return;
+        }
+
+        match self.place_status(target) {
+            PlaceStatus::Resolved | PlaceStatus::Failed => return,
+            PlaceStatus::Hole => panic!(
+                "Attempting to resolve until {:?}, which is declared as a hole.",
+                target
+            ),
+            PlaceStatus::Untracked => panic!(
+                "Attempting to resolve until {:?}, which is not tracked.",
+                target
+            ),
+            PlaceStatus::Tracked => {}
+        }
+
+        self.sorter.flush();
+
+        let end = {
+            let exec_order = self.common.exec_order.lock().unwrap();
+
+            // Safety: Dereferencing as & in &self context. The resolvers in
+            // the execution order are never modified.
+            let resolvers = unsafe { self.common.resolvers.u_deref() };
+
+            exec_order.items[..exec_order.size]
+                .iter()
+                .position(|x| unsafe { resolvers.get(x.value) }.outputs().contains(&target))
+                .expect("Attempting to resolve until a value whose resolution is still waiting for its inputs.")
+                + 1
+        };
+
+        if let Some(start) = &mut self.inline_cursor {
+            if end > *start {
+                // Safety: The resolvers in `start..end` weren't invoked yet and
+                // there is no resolution window running.
+                unsafe {
+                    resolution_window::resolve_inline::<V, RS::TrackId, RS::Config>(
+                        &self.common,
+                        &self.debug_track,
+                        *start..end,
+                        self.sorter.panic_policy(),
+                    );
+                }
+
+                *start = end;
+            }
+
+            return;
+        }
+
+        let limit = match self.comms.resolve_limit.load(Relaxed) {
+            usize::MAX => end,
+            x => std::cmp::max(x, end),
+        };
+
+        self.comms.resolve_limit.store(limit, Relaxed);
+        self.comms.exec_order_buffer_hint.store(1, Relaxed);
+
+        // Spawned once the limit is set, so it doesn't run past it.
+        self.spawn_window();
+
+        // Safety: Dereferencing as & in &self context.
+        let values = unsafe { self.common.values.u_deref() };
+
+        awaiters::AwaitersBroker::register(
+            &self.common.awaiters_broker,
+            &self.comms,
+            &values.get_item_ref(target).1,
+        )
+        .wait();
+    }
+
+    pub fn wait_till_resolved_impl(&mut self, report: bool) {
+        if self.close_registration() == false {
+            return;
+        }
+
+        self.spawn_window();
+
+        match self.inline_cursor {
+            Some(_) => Self::resolve_inline(
+                &self.common,
+                &self.debug_track,
+                &mut self.inline_cursor,
+                self.sorter.panic_policy(),
+            ),
+            None => self
+                .resolution_window_handle
+                .take()
+                .expect("Attempting to join resolution window handler for second time.")
+                .join()
+                .unwrap(), // Just propagate panics. Those are unhandled, unlike the ones from `rw_panic`.
+        }
+
+        self.finish_resolution(report);
+    }
+
+    /// Same as `wait_till_resolved`, but stops the resolution once `deadline`
+    /// passes, as `cancel` does. The values resolved by then stay readable.
+    pub fn wait_till_resolved_deadline(
+        &mut self,
+        deadline: std::time::Instant,
+    ) -> ResolutionOutcome {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        if self.close_registration() == false {
+            return ResolutionOutcome::Completed;
+        }
+
+        self.spawn_window();
+
+        let total = self.common.exec_order.lock().unwrap().size;
+
+        match &mut self.inline_cursor {
+            Some(cursor) => {
+                while *cursor < total && std::time::Instant::now() < deadline {
+                    // Safety: The resolver at `cursor` wasn't invoked yet and
+                    // there is no resolution window running.
+                    unsafe {
+                        resolution_window::resolve_inline::<V, RS::TrackId, RS::Config>(
+                            &self.common,
+                            &self.debug_track,
+                            *cursor..*cursor + 1,
+                            self.sorter.panic_policy(),
+                        );
+                    }
+
+                    *cursor += 1;
+                }
+            }
+            None => {
+                let handle = self
+                    .resolution_window_handle
+                    .take()
+                    .expect("Attempting to join resolution window handler for second time.");
+
+                while handle.is_finished() == false {
+                    let now = std::time::Instant::now();
+
+                    if now >= deadline {
+                        self.comms.cancelled.store(true, Relaxed);
+                        break;
+                    }
+
+                    std::thread::sleep(std::cmp::min(
+                        deadline - now,
+                        std::time::Duration::from_millis(1),
+                    ));
+                }
+
+                handle.join().unwrap();
+            }
+        }
+
+        let resolved = self.resolved_count();
+
+        if resolved < total as u64 && self.comms.rw_panicked.load(Relaxed) == false {
+            self.stats.total_resolution_time = self.stats.started_at.elapsed();
+
+            return ResolutionOutcome::TimedOut {
+                resolved,
+                pending: total as u64 - resolved,
+            };
+        }
+
+        self.finish_resolution(true);
+
+        ResolutionOutcome::Completed
+    }
+
+    /// Completes the resolution, propagating a panic of the resolution
+    /// window.
+    fn finish_resolution(&mut self, report: bool) {
+        match self.complete_resolution(report) {
+            Err(ResolutionPanicked(Some(e))) => resume_unwind(e),
+            Err(ResolutionPanicked(None)) => {
+                if self.silent == false {
+                    log!("Resolution window panicked, but no panic payload stored.");
+                }
+            }
+            Ok(()) => {}
+        }
+    }
+
+    /// Finishes the resolution once the resolution window has ended.
+    fn complete_resolution(&mut self, report: bool) -> Result<(), ResolutionPanicked> {
+        self.stats.total_resolution_time = self.stats.started_at.elapsed();
+
+        if self
+            .comms
+            .rw_panicked
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return Err(ResolutionPanicked(
+                self.comms.rw_panic.lock().unwrap().take(),
+            ));
+        }
+
+        match report {
+            _ if self.silent => {}
+            true => {
+                log!("CR stats {:#?}", self.stats);
+
+                #[cfg(feature = "profile-resolvers")]
+                if let Some(slowest) = self.slowest_resolver() {
+                    log!("CR slowest resolver {:?}", slowest);
+                }
+            }
+            false if self.verbose_drop => {
+                print!(" resolution time {:?}...", self.stats.total_resolution_time);
+            }
+            _ => {}
+        }
+
+        self.sorter.write_sequence();
+
+        if cfg!(cr_paranoia_mode) || PARANOIA {
+            log!("CR {:?}", unsafe {
+                self.common.awaiters_broker.stats.u_deref()
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn retrieve_sequence(&self) -> &ResolutionRecord {
+        assert!(self
+            .comms
+            .registration_complete
+            .load(std::sync::atomic::Ordering::Relaxed));
+        self.sorter.retrieve_sequence()
+    }
+
+    pub fn record_summary(&self) -> RecordSummary {
+        self.retrieve_sequence().summary()
+    }
+
+    /// Moves the resolution record out of the resolver, so it can be shared
+    /// with a playback resolver without copying.
+    pub fn into_record_arc(mut self) -> Arc<ResolutionRecord> {
+        assert!(self
+            .comms
+            .registration_complete
+            .load(std::sync::atomic::Ordering::Relaxed));
+
+        self.sorter.take_sequence().to(Arc::new)
+    }
+
+    /// Waits for the resolution to finish and moves the values out of the
+    /// resolver. The value of an item is valid if its metadata is resolved.
+    /// Values kept in a `ValueStore` other than a `ValueStorage` are copied
+    /// into memory.
+    pub fn into_value_storage(mut self) -> ValueStorage<V, RS::TrackId> {
+        self.wait_till_resolved_impl(false);
+
+        // Safety: The resolution is done, so this thread is the only one
+        // accessing the values.
+        unsafe { std::mem::take(&mut self.common.values.u_deref_mut().variables) }.into_storage()
+    }
+
+    /// Frees the resolvers and the shared contexts once the resolution is
+    /// done, keeping only the values. No resolutions can be added afterwards.
+    pub fn release_resolvers(&mut self) {
+        assert!(
+            self.comms
+                .registration_complete
+                .load(std::sync::atomic::Ordering::Relaxed),
+            "Attempting to release the resolvers while the resolution is running."
+        );
+
+        // Safety: The resolution window is joined, so this thread is the only
+        // one accessing the resolvers.
+        unsafe { self.common.resolvers.u_deref_mut().release() };
+
+        self.contexts.clear();
+        self.resolvers_released = true;
+    }
+
+    /// Lists the resolvers in the execution order with their registration
+    /// numbers and outputs. Only the resolvers that were already moved into
+    /// the execution order are listed, which are all of them once the
+    /// resolution is done.
+    pub fn export_execution_plan(&self) -> ExecutionPlan {
+        assert!(
+            self.resolvers_released == false,
+            "Attempting to export the execution plan after the resolvers were released."
+        );
+
+        let exec_order = self.common.exec_order.lock().unwrap();
+
+        // Safety: Dereferencing as & in &self context. The resolvers in the
+        // execution order are never modified.
+        let resolvers = unsafe { self.common.resolvers.u_deref() };
+
+        let steps = exec_order.items[..exec_order.size]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                // Safety: The index was taken from the execution order.
+                let r = unsafe { resolvers.get(x.value) };
+
+                PlanStep {
+                    order_ix: i as u64,
+                    registration: r.added_at(),
+                    outputs: r.outputs().to_vec(),
+                }
+            })
+            .collect();
+
+        ExecutionPlan { steps }
+    }
+
+    /// The registration whose resolution outputs `place`, or `None` if there
+    /// is none, e.g. for a value set directly. Searches the registrations
+    /// waiting on an untracked input and the resolvers in the execution
+    /// order, so a registration still buffered by the sorter is found only
+    /// after a `flush`. Scans all of them, so it's meant for debugging.
+    pub fn producer_of(&self, place: Place) -> Option<crate::dag::guide::RegistrationNum> {
+        assert!(
+            self.resolvers_released == false,
+            "Attempting to look up a producer after the resolvers were released."
+        );
+
+        if let Some(x) = self
+            .sorter
+            .pending_registrations()
+            .into_iter()
+            .find(|x| x.outputs.contains(&place))
+        {
+            return Some(x.registration);
+        }
+
+        let exec_order = self.common.exec_order.lock().unwrap();
+
+        // Safety: Dereferencing as & in &self context. The resolvers in the
+        // execution order are never modified.
+        let resolvers = unsafe { self.common.resolvers.u_deref() };
+
+        exec_order.items[..exec_order.size]
+            .iter()
+            // Safety: The index was taken from the execution order.
+            .map(|x| unsafe { resolvers.get(x.value) })
+            .find(|x| x.outputs().contains(&place))
+            .map(|x| x.added_at())
+    }
+
+    /// Enables printing the progress of the drop, off by default.
+    pub fn set_verbose_drop(&mut self, value: bool) {
+        self.verbose_drop = value;
+    }
+
+    /// Overrides `CircuitResolverOpts::silent`, e.g. for the playback, which
+    /// isn't configured with the options.
+    pub fn set_silent(&mut self, value: bool) {
+        self.silent = value;
+    }
+
+    /// Sets an observer that is notified whenever an awaiter is registered
+    /// and woken.
+    pub fn set_awaiter_observer(&mut self, observer: Option<Arc<dyn AwaiterObserver>>) {
+        // Safety: The awaiters borrow the resolver, so there are none while
+        // it's borrowed mutably.
+        unsafe { self.common.awaiters_broker.set_observer(observer) };
+    }
+
+    /// The registrations whose resolutions panicked or had a failed input,
+    /// under `PanicPolicy::SkipAndRecord`, in registration order.
+    pub fn failed_registrations(&self) -> Vec<crate::dag::guide::RegistrationNum> {
+        self.common
+            .failed
+            .lock()
+            .unwrap()
+            .clone()
+            .op(|x| x.sort_unstable())
+    }
+
+    /// The number of resolutions invoked so far. Can be polled from any
+    /// thread while the resolution is running.
+    pub fn resolved_count(&self) -> u64 {
+        self.common
+            .resolved_count
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// The number of resolver invocations the resolution performs, i.e. the
+    /// registrations added so far. Final once the registration is complete,
+    /// so together with `resolved_count` it gives the resolution progress.
+    pub fn estimated_total_work(&self) -> u64 {
+        self.stats.registrations_added
+    }
+
+    /// Splits the registered values into those set directly and those
+    /// produced by resolutions. The internal witness count is a subset of the
+    /// seeded values, the ones set on witness places, so it isn't reported
+    /// separately.
+    pub fn value_breakdown(&self) -> ValueBreakdown {
+        ValueBreakdown {
+            seeded: self.stats.values_added,
+            derived: self
+                .arity
+                .outputs
+                .iter()
+                .map(|(len, count)| *len as u64 * count)
+                .sum(),
+        }
+    }
+
+    /// The position of the resolution in the execution order. Can be polled
+    /// from any thread while the resolution is running. The gap between
+    /// `executed` and `available` is scheduled but not yet run, the one
+    /// between `available` and `total` waits for the registrations.
+    pub fn execution_position(&self) -> ExecutionPosition {
+        let executed = match self.inline_cursor {
+            Some(cursor) => cursor,
+            None => self
+                .comms
+                .executed
+                .load(std::sync::atomic::Ordering::Relaxed),
+        };
+
+        let exec_order = self.common.exec_order.lock().unwrap();
+
+        ExecutionPosition {
+            executed,
+            available: exec_order.size,
+            total: exec_order.items.len(),
+        }
+    }
+
+    /// Renders the resolver counters in the Prometheus text exposition
+    /// format. The resolution time is the one of the last completed
+    /// resolution, or zero while it's running.
+    #[cfg(feature = "prometheus")]
+    pub fn prometheus_metrics(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, value: &dyn std::fmt::Display| {
+            writeln!(out, "# HELP {} {}", name, help).unwrap();
+            writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+            writeln!(out, "{} {}", name, value).unwrap();
+        };
+
+        metric(
+            "cr_values_added",
+            "counter",
+            "Values set on the resolver.",
+            &self.stats.values_added,
+        );
+        metric(
+            "cr_registrations_added",
+            "counter",
+            "Resolutions added to the resolver.",
+            &self.stats.registrations_added,
+        );
+        metric(
+            "cr_resolution_seconds",
+            "gauge",
+            "Time from the resolver creation until all values were resolved.",
+            &self.stats.total_resolution_time.as_secs_f64(),
+        );
+        metric(
+            "cr_resolved_count",
+            "counter",
+            "Resolutions invoked so far.",
+            &self.resolved_count(),
+        );
+
+        out
+    }
+
+    pub fn arity_histogram(&self) -> &ArityHistogram {
+        &self.arity
+    }
+
+    /// The edges from the inputs to the outputs of the registered
+    /// resolutions. Requires `CircuitResolverOpts::track_dependencies`.
+    pub fn dependency_graph(&self) -> &DependencyGraph {
+        self.dependencies
+            .as_ref()
+            .expect("Dependencies are not tracked, enable `track_dependencies` in the options.")
+    }
+
+    /// The number of resolutions on the longest dependency chain, see
+    /// `DependencyGraph::critical_path_length`. Requires
+    /// `CircuitResolverOpts::track_dependencies`.
+    pub fn critical_path_length(&self) -> usize {
+        self.dependency_graph().critical_path_length()
+    }
+
+    /// The number of registered resolutions that take `place` as an input.
+    /// Requires `CircuitResolverOpts::track_fanout`.
+    pub fn fanout(&self, place: Place) -> u32 {
+        let fanout = self
+            .fanout
+            .as_ref()
+            .expect("Fanout is not tracked, enable `track_fanout` in the options.");
+
+        fanout[place.raw_ix()]
+    }
+
+    pub fn parallelism_report(&self) -> ParallelismReport {
+        self.retrieve_sequence().parallelism_report()
+    }
+
+    /// A copy of the execution order committed so far, with the parallelism
+    /// the guide assigned to each resolver. Complete only once the
+    /// registration is, e.g. after `wait_till_resolved`.
+    pub fn guide_order(&self) -> Vec<OrderInfo<ResolverIx>> {
+        let exec_order = self.common.exec_order.lock().unwrap();
+
+        exec_order.items[..exec_order.size].to_vec()
+    }
+
+    /// Drops the registered circuit and its values, so the resolver can be
+    /// reused for another circuit with the same options. The values storage
+    /// is kept, see `clear_and_resize` to reallocate it. Waits for the
+    /// current resolution to finish, if it is still running.
+    pub fn clear(&mut self) {
+        self.wait_till_resolved_impl(false);
+
+        self.common =
+            self.sorter
+                .clear(self.comms.clone(), &self.debug_track, self.spawner.as_ref());
+
+        self.restart();
+    }
+
+    /// Rewinds the resolver state and starts a new resolution window, once
+    /// the sorter was reset or replaced for a new resolution.
+    fn restart(&mut self) {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        self.resumed = None;
+
+        if let Some(fanout) = &mut self.fanout {
+            // Safety: Dereferencing as & in &self context.
+            *fanout = vec![0; unsafe { self.common.values.u_deref() }.variables.len()];
+        }
+
+        if let Some(dependencies) = &mut self.dependencies {
+            *dependencies = DependencyGraph::default();
+        }
+
+        self.contexts.clear();
+        self.resolvers_released = false;
+        self.arity = ArityHistogram::default();
+        self.flushed = FlushReport::default();
+        self.tracked_reported = -1;
+
+        self.comms.exec_order_buffer_hint.store(0, Relaxed);
+        self.comms.rw_panicked.store(false, Relaxed);
+        *self.comms.rw_panic.lock().unwrap() = None;
+        self.comms.cancelled.store(false, Relaxed);
+        self.comms.resolve_limit.store(usize::MAX, Relaxed);
+        self.comms.registration_complete.store(false, Relaxed);
+        self.comms.executed.store(0, Relaxed);
+
+        self.stats = Stats::new();
+
+        if let Some(cursor) = &mut self.inline_cursor {
+            *cursor = 0;
+            return;
+        }
+
+        if self.lazy {
+            self.lazy_window = Some(self.threads);
+            return;
+        }
+
+        self.resolution_window_handle = ResolutionWindow::<V, RS::TrackId, RS::Config>::run(
+            self.comms.clone(),
+            self.common.clone(),
+            &self.debug_track,
+            self.threads,
+            self.sorter.worker_affinity(),
+            self.sorter.panic_policy(),
+            self.sorter.idle_policy(),
+            self.silent,
+            self.spawner.as_ref(),
+        )
+        .to(Some);
+    }
+
+    /// Spawns the window of a lazy resolver, if it wasn't yet.
+    fn spawn_window(&mut self) {
+        if let Some(threads) = self.lazy_window.take() {
+            self.resolution_window_handle = ResolutionWindow::<V, RS::TrackId, RS::Config>::run(
+                self.comms.clone(),
+                self.common.clone(),
+                &self.debug_track,
+                threads,
+                self.sorter.worker_affinity(),
+                self.sorter.panic_policy(),
+                self.sorter.idle_policy(),
+                self.silent,
+                self.spawner.as_ref(),
+            )
+            .to(Some);
+        }
+    }
+
+    /// Stops the resolution window as soon as the currently executing batch
+    /// is done and joins it. Values resolved up to that point stay intact,
+    /// the rest are never resolved, so awaiting them panics.
+    pub fn cancel(&mut self) {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        if self.comms.registration_complete.load(Relaxed) {
+            return;
+        }
+
+        self.comms.cancelled.store(true, Relaxed);
+        self.comms.registration_complete.store(true, Relaxed);
+
+        if self.inline_cursor.is_none() && self.lazy_window.take().is_none() {
+            self.resolution_window_handle
+                .take()
+                .expect("Attempting to join resolution window handler for second time.")
+                .join()
+                .unwrap();
+        }
+
+        self.stats.total_resolution_time = self.stats.started_at.elapsed();
+
+        if (cfg!(test) || cfg!(debug_assertions)) && self.silent == false {
+            log!("CR cancelled after {:?}", self.stats.total_resolution_time);
+        }
+    }
+
+    /// Bounds-checked counterpart of `get_value_unchecked`.
+    pub fn get_value(&self, key: Place) -> Result<V, GetValueError> {
+        // Safety: Dereferencing as & in &self context.
+        let values = unsafe { self.common.values.u_deref() };
+
+        if key.raw_ix() >= values.variables.len() {
+            return Err(GetValueError::OutOfRange);
+        }
+
+        let (v, md) = values.get_item_ref(key);
+
+        if md.is_hole() {
+            return Err(GetValueError::Hole);
+        }
+
+        match (md.is_tracked(), md.is_resolved()) {
+            (_, true) => {
+                fence(std::sync::atomic::Ordering::Acquire);
+                Ok(*v)
+            }
+            (true, false) => Err(GetValueError::NotResolved),
+            (false, false) => Err(GetValueError::NotTracked),
+        }
+    }
+
+    /// Same as `get_value_unchecked`, but without the check that the value is
+    /// resolved in debug builds, for readback loops over values already known
+    /// to be resolved.
+    ///
+    /// # Safety
+    ///
+    /// The value must be resolved, and its resolution must happen-before this
+    /// call, e.g. through an awaiter or `wait_till_resolved`. Otherwise the
+    /// value may be read while a worker writes it.
+    pub unsafe fn get_value_assume_resolved(&self, key: Place) -> V {
+        self.common.values.u_deref().get_item_ref(key).0
+    }
+
+    /// A reference to the value at `key` if it's resolved, for reading it in
+    /// place instead of copying it.
+    ///
+    /// A resolved slot is written exactly once, before its resolved flag is
+    /// published, and never again: the workers and `set_value` only write
+    /// unresolved slots. The only ways to move or overwrite a resolved value
+    /// are growing the storage, restarting or resetting the resolver, all of
+    /// which take `&mut self`, so the borrow rules them out for as long as
+    /// the reference lives. The `Acquire` fence makes the value written by the
+    /// worker visible to this thread.
+    pub fn try_get_ref(&self, key: Place) -> Option<&V> {
+        // Safety: Dereferencing as & in &self context.
+        let (v, md) = unsafe { self.common.values.u_deref().get_item_ref(key) };
+
+        match md.is_resolved() {
+            true => {
+                fence(std::sync::atomic::Ordering::Acquire);
+                Some(v)
+            }
+            false => None,
+        }
+    }
+
+    /// Sends a message to `tx` once `place` is resolved, or right away if it
+    /// already is. A lighter alternative to an awaiter for a thread that
+    /// doesn't need to block. Each subscription fires once.
+    ///
+    /// Unlike with `get_awaiter`, the registrations aren't flushed, so the
+    /// place may resolve only once its resolver is moved to the execution
+    /// order by a later registration, `flush` or `wait_till_resolved`.
+    pub fn notify_on_resolve(&mut self, place: Place, tx: Sender<()>) {
+        self.common.subscriptions.subscribe(place, tx, || {
+            self.place_status(place) == PlaceStatus::Resolved
+        });
+    }
+
+    /// Tells apart values that are pending from those that no registration
+    /// will ever produce. Panics if `key` is beyond `max_variables`.
+    pub fn place_status(&self, key: Place) -> PlaceStatus {
+        // Safety: Dereferencing as & in &self context.
+        let (_, md) = unsafe { self.common.values.u_deref().get_item_ref(key) };
+
+        if md.is_hole() {
+            return PlaceStatus::Hole;
+        }
+
+        if md.is_failed() {
+            return PlaceStatus::Failed;
+        }
+
+        match (md.is_tracked(), md.is_resolved()) {
+            (_, true) => PlaceStatus::Resolved,
+            (true, false) => PlaceStatus::Tracked,
+            (false, false) => PlaceStatus::Untracked,
+        }
+    }
+
+    /// Reconstructs a value stored across `places`. Returns `None` if any of
+    /// them isn't resolved yet.
+    pub fn get_castable<T: TrivialWitnessCastable<V, N>, const N: usize>(
+        &self,
+        places: [Place; N],
+    ) -> Option<T> {
+        let mut parts = [V::ZERO; N];
+
+        for (part, place) in parts.iter_mut().zip(places) {
+            *part = self.get_value(place).ok()?;
+        }
+
+        Some(T::cast_from_field_elements(parts))
+    }
+
+    /// The resolved state of the variables in `range`, read in one pass. While
+    /// the resolution window is running this is a snapshot: a variable may get
+    /// resolved right after it was read as unresolved. The values of the
+    /// variables read as resolved are visible to this thread.
+    pub fn resolved_bitset(&self, range: std::ops::Range<u64>) -> ResolvedBitSet {
+        // Safety: Dereferencing as & in &self context. The metadata is read as
+        // a snapshot.
+        let values = unsafe { self.common.values.u_deref() };
+
+        assert!(
+            range.start <= range.end && range.end <= values.variables.len() as u64,
+            "Range {:?} is out of the {} variables.",
+            range,
+            values.variables.len()
+        );
+
+        let mut bitset = ResolvedBitSet::new(range.clone());
+
+        for i in range {
+            if values
+                .get_item_ref(Place::from_variable_index(i))
+                .1
+                .is_resolved()
+            {
+                bitset.insert(i);
+            }
+        }
+
+        fence(std::sync::atomic::Ordering::Acquire);
+
+        bitset
+    }
+
+    /// Copies the values of the variables in `range` into `dst`. All of them
+    /// must be resolved.
+    pub fn copy_resolved_into(&self, range: std::ops::Range<u64>, dst: &mut [V]) {
+        assert_eq!(
+            (range.end - range.start) as usize,
+            dst.len(),
+            "Destination length doesn't match the range."
+        );
+
+        // Safety: Dereferencing as & in &self context.
+        let values = unsafe { self.common.values.u_deref() };
+        let cells = &values.variables[range.start as usize..range.end as usize];
+
+        assert!(
+            cells.iter().all(|x| unsafe { x.u_deref() }.1.is_resolved()),
+            "Attempted to copy an unresolved value."
+        );
+
+        // Orders the value reads after the resolved flags were observed.
+        fence(std::sync::atomic::Ordering::Acquire);
+
+        for (cell, dst) in cells.iter().zip(dst.iter_mut()) {
+            // Safety: Only resolved values are read, and those are not
+            // written to anymore.
+            *dst = unsafe { cell.u_deref() }.0;
+        }
+    }
+
+    /// Same as `copy_resolved_into` for several equally long `columns`, but
+    /// writes the values row by row: the `r`th value of each column, in the
+    /// column order, then the `r + 1`th and so on.
+    pub fn copy_transposed(&self, columns: &[std::ops::Range<u64>], dst: &mut [V]) {
+        let rows = columns.first().map_or(0, |x| (x.end - x.start) as usize);
+
+        assert!(
+            columns.iter().all(|x| (x.end - x.start) as usize == rows),
+            "Columns differ in length."
+        );
+        assert_eq!(
+            rows * columns.len(),
+            dst.len(),
+            "Destination length doesn't match the columns."
+        );
+
+        // Safety: Dereferencing as & in &self context.
+        let values = unsafe { self.common.values.u_deref() };
+
+        assert!(
+            columns
+                .iter()
+                .flat_map(|x| &values.variables[x.start as usize..x.end as usize])
+                .all(|x| unsafe { x.u_deref() }.1.is_resolved()),
+            "Attempted to copy an unresolved value."
+        );
+
+        // Orders the value reads after the resolved flags were observed.
+        fence(std::sync::atomic::Ordering::Acquire);
+
+        for (row, dst) in dst.chunks_exact_mut(columns.len().max(1)).enumerate() {
+            for (column, dst) in columns.iter().zip(dst.iter_mut()) {
+                // Safety: Only resolved values are read, and those are not
+                // written to anymore.
+                *dst = unsafe { values.variables[column.start as usize + row].u_deref() }.0;
+            }
+        }
+    }
+
+    /// The canonical `u64` representation of a resolved value, or `None` if
+    /// it isn't resolved. Panics if `key` is beyond `max_variables`.
+    pub fn get_value_raw_u64(&self, key: Place) -> Option<u64> {
+        match self.get_value(key) {
+            Ok(v) => Some(v.as_u64_reduced()),
+            Err(GetValueError::OutOfRange) => {
+                panic!("Place {:?} is out of the variables range.", key)
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Same as `copy_resolved_into`, but writes the canonical `u64`
+    /// representations of the values.
+    pub fn copy_resolved_raw_into(&self, range: std::ops::Range<u64>, dst: &mut [u64]) {
+        assert_eq!(
+            (range.end - range.start) as usize,
+            dst.len(),
+            "Destination length doesn't match the range."
+        );
+
+        // Safety: Dereferencing as & in &self context.
+        let values = unsafe { self.common.values.u_deref() };
+        let cells = &values.variables[range.start as usize..range.end as usize];
+
+        assert!(
+            cells.iter().all(|x| unsafe { x.u_deref() }.1.is_resolved()),
+            "Attempted to copy an unresolved value."
+        );
+
+        // Orders the value reads after the resolved flags were observed.
+        fence(std::sync::atomic::Ordering::Acquire);
+
+        for (cell, dst) in cells.iter().zip(dst.iter_mut()) {
+            // Safety: Only resolved values are read, and those are not
+            // written to anymore.
+            *dst = unsafe { cell.u_deref() }.0.as_u64_reduced();
+        }
+    }
+
+    /// Lists the places that are tracked, but not yet resolved. Can be called
+    /// while the resolution is running, in which case the result is a best
+    /// effort snapshot.
+    pub fn unresolved_places(&self) -> impl Iterator<Item = Place> + '_ {
+        // Safety: Dereferencing as & in &self context. The metadata is only
+        // read, concurrent writes may or may not be observed.
+        let values = unsafe { self.common.values.u_deref() };
+
+        values
+            .variables
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| {
+                let md = unsafe { &cell.u_deref().1 };
+
+                md.is_tracked() && md.is_resolved() == false && md.is_hole() == false
+            })
+            .map(|(i, _)| Place::from_variable_index(i as u64))
+    }
+
+    /// Lists the resolved places along with their values. Can be called while
+    /// the resolution is running, in which case the result is a best effort
+    /// snapshot; it's complete after `wait_till_resolved`.
+    pub fn iter_resolved(&self) -> impl Iterator<Item = (Place, V)> + '_ {
+        // Safety: Dereferencing as & in &self context.
+        let values = unsafe { self.common.values.u_deref() };
+
+        values.variables.iter().enumerate().filter_map(|(i, cell)| {
+            // Safety: Only resolved values are read, and those are not
+            // written to anymore.
+            let (v, md) = unsafe { cell.u_deref() };
+
+            match md.is_tracked() && md.is_resolved() {
+                true => {
+                    // The value may have been resolved after the previous
+                    // one was yielded.
+                    fence(std::sync::atomic::Ordering::Acquire);
+                    Some((Place::from_variable_index(i as u64), *v))
+                }
+                false => None,
+            }
+        })
+    }
+
+    /// Writes all resolved values and the execution order, so that they can
+    /// be restored with `resume`. The resolution functions can't be
+    /// serialized, so the order is stored as registration numbers, each with
+    /// whether it was invoked. A resolver is taken as invoked once all of its
+    /// outputs are resolved, thus one without outputs is invoked again after
+    /// `resume`.
+    ///
+    /// A resolution that is still running is cancelled first, as with
+    /// `cancel`, so the resolver can't be used for registration afterwards.
+    pub fn checkpoint<W: std::io::Write>(
+        &mut self,
+        mut w: W,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        assert!(
+            self.resolvers_released == false,
+            "Attempting to checkpoint a resolver after the resolvers were released."
+        );
+
+        self.cancel();
+
+        // Safety: The resolution window is joined, so this thread is the only
+        // one accessing the values.
+        let values = unsafe { self.common.values.u_deref() };
+
+        w.write_all(&(values.variables.len() as u64).to_le_bytes())?;
+
+        for cell in values.variables.iter() {
+            // Safety: See above.
+            let (v, md) = unsafe { &*cell.get() };
+
+            match md.is_resolved() {
+                true => {
+                    w.write_all(&[1])?;
+                    w.write_all(&v.as_u64().to_le_bytes())?;
+                }
+                false => w.write_all(&[0])?,
+            }
+        }
+
+        let exec_order = self.common.exec_order.lock().unwrap();
+
+        // Safety: See above.
+        let resolvers = unsafe { self.common.resolvers.u_deref() };
+
+        let mut invoked = self
+            .resumed
+            .as_ref()
+            .map(|x| x.invoked.iter().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        invoked.sort_unstable();
+
+        let order = exec_order.items[..exec_order.size].iter().map(|x| {
+            // Safety: The index was taken from the execution order.
+            let r = unsafe { resolvers.get(x.value) };
+
+            let registration = match &self.resumed {
+                Some(resumed) => resumed.registered[r.added_at() as usize],
+                None => r.added_at(),
+            };
+
+            let is_invoked = r.outputs().is_empty() == false
+                && r.outputs()
+                    .iter()
+                    .all(|x| values.get_item_ref(*x).1.is_resolved());
+
+            (registration, is_invoked)
+        });
+
+        // The registrations invoked in the previous runs come first.
+        let order = invoked
+            .into_iter()
+            .map(|x| (x, true))
+            .chain(order)
+            .collect::<Vec<_>>();
+
+        w.write_all(&(order.len() as u64).to_le_bytes())?;
+
+        for (registration, is_invoked) in order {
+            w.write_all(&registration.to_le_bytes())?;
+            w.write_all(&[is_invoked as u8])?;
+        }
+
+        Ok(())
+    }
+
+    /// Aggregates the durations of the resolver invocations. Must be called
+    /// after the resolution has finished.
+    #[cfg(feature = "profile-resolvers")]
+    pub fn resolver_timing_histogram(&self) -> profiling::TimingHistogram {
+        assert!(
+            self.comms
+                .registration_complete
+                .load(std::sync::atomic::Ordering::Relaxed),
+            "Attempting to read resolver timings while the resolution is running."
+        );
+
+        profiling::TimingHistogram::new(&self.common.timings.lock().unwrap())
+    }
+
+    /// The slowest resolver invocation, or `None` if no resolver was invoked.
+    /// Must be called after the resolution has finished.
+    #[cfg(feature = "profile-resolvers")]
+    pub fn slowest_resolver(&self) -> Option<profiling::SlowestInfo> {
+        assert!(
+            self.comms
+                .registration_complete
+                .load(std::sync::atomic::Ordering::Relaxed),
+            "Attempting to read resolver timings while the resolution is running."
+        );
+
+        let (registration, duration) = self
+            .common
+            .timings
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+        let exec_order = self.common.exec_order.lock().unwrap();
+
+        // Safety: The resolution has finished, so the resolvers aren't
+        // accessed by other threads.
+        let resolvers = unsafe { self.common.resolvers.u_deref() };
+
+        let outputs = exec_order.items[..exec_order.size]
+            .iter()
+            // Safety: The resolvers in the execution order were pushed to the
+            // box.
+            .map(|x| unsafe { resolvers.get(x.value) })
+            .find(|x| x.added_at() == registration)
+            .map(|x| x.outputs().to_vec())
+            .unwrap_or_default();
+
+        Some(profiling::SlowestInfo {
+            registration,
+            outputs,
+            duration,
+        })
+    }
+
+    /// The bytes the resolver box holds for the resolver closures and their
+    /// captures. Zero once the resolvers are released.
+    pub fn closure_memory(&self) -> usize {
+        // Safety: Dereferencing as & in &self context. The box is only pushed
+        // to from `&mut self`.
+        unsafe { self.common.resolvers.u_deref().closure_memory() }
+    }
+
+    /// Pre-sizes the execution order and the sorter buffers for
+    /// `expected_registrations`, with some slack for the gaps in the order,
+    /// so they aren't reallocated during the registration. The playback sizes
+    /// them from the record already.
+    pub fn reserve(&mut self, expected_registrations: usize) {
+        self.sorter.reserve(expected_registrations)
+    }
+
+    /// Returns the highest variable up to which all variables are tracked,
+    /// or `None` if nothing is tracked yet.
+    pub fn max_tracked_place(&self) -> Option<Place> {
+        // Safety: Reading a single value that is only written by this thread.
+        let max_tracked = unsafe { self.common.values.u_deref().max_tracked };
+
+        match max_tracked {
+            x if x < 0 => None,
+            x => Some(Place::from_variable(Variable::from_variable_index(
+                x as u64,
+            ))),
+        }
+    }
+}
+
+impl<V, CFG, T> MtCircuitResolver<V, LiveResolverSorter<V, CFG, T>, CFG>
+where
+    V: SmallField,
+    CFG: CSResolverConfig,
+    T: GuideTrackId + 'static,
+    RWConfigRecord<T>: RWConfig<T>,
+{
+    /// Creates a resolver that writes the values into `storage` instead of an
+    /// own allocation, so they can be taken back with `into_value_storage`
+    /// without a copy.
+    ///
+    /// The storage must have `opts.max_variables` items, all with the default
+    /// metadata. The resolver accesses the cells unsynchronized from its
+    /// threads while it is alive, so the caller must not keep any references
+    /// into them. Under `CapacityPolicy::Grow` the storage may be replaced by
+    /// a larger own allocation, which `into_value_storage` then returns.
+    pub fn with_value_storage(opts: CircuitResolverOpts, storage: ValueStorage<V, T>) -> Self {
+        Self::with_value_store(opts, storage)
+    }
+
+    /// Same as `with_value_storage`, but keeps the values in any `store`,
+    /// e.g. a file-backed `MmapValueStore` for witnesses that don't fit into
+    /// memory.
+    pub fn with_value_store(
+        opts: CircuitResolverOpts,
+        store: impl ValueStore<V, T> + 'static,
+    ) -> Self {
+        Self::with_value_store_and_spawner(
+            opts,
+            store,
+            Self::worker_threads(),
+            Arc::new(ThreadSpawner),
+        )
+    }
+
+    /// Same as `with_value_store`, with the threads spawned by `spawner`, see
+    /// `new_with_spawner`.
+    pub fn with_value_store_and_spawner(
+        opts: CircuitResolverOpts,
+        store: impl ValueStore<V, T> + 'static,
+        threads: u32,
+        spawner: Arc<dyn Spawner>,
+    ) -> Self {
+        Self::from_sorter(threads, spawner, false, |comms, debug_track, spawner| {
+            LiveResolverSorter::new_with_storage(
+                opts,
+                comms,
+                debug_track,
+                spawner,
+                Some(Box::new(store)),
+            )
+        })
+    }
+
+    /// Discards the circuit and prepares the resolver for a new one of up to
+    /// `new_max_variables` values, keeping the other options. The values are
+    /// reallocated to the new size, so the memory held for a larger circuit
+    /// is released. Waits for the current resolution to finish, if it is
+    /// still running.
+    pub fn clear_and_resize(&mut self, new_max_variables: usize) {
+        self.wait_till_resolved_impl(false);
+
+        let opts = CircuitResolverOpts {
+            max_variables: new_max_variables,
+            ..*self.sorter.options()
+        };
+
+        // The resolution window is joined, so the sorter holds the only other
+        // reference to the common data and both are dropped here.
+        let (sorter, common) = LiveResolverSorter::new_with_storage(
+            opts,
+            self.comms.clone(),
+            &self.debug_track,
+            self.spawner.as_ref(),
+            None,
+        );
+
+        self.sorter = sorter;
+        self.common = common;
+
+        self.restart();
+    }
+}
+
+impl<V: SmallField, CFG: CSResolverConfig> MtCircuitResolver<V, LiveResolverSorter<V, CFG>, CFG> {
+    /// Creates a resolver with the values stored by `checkpoint` already
+    /// resolved. The circuit is then registered again from the start: values
+    /// that are already resolved and resolutions that were invoked before the
+    /// checkpoint are skipped, so only the remaining part of the circuit is
+    /// computed. The resolver can be checkpointed again.
+    ///
+    /// The resolution record produced by a resumed resolver doesn't contain
+    /// the skipped resolutions and can't be used for playback.
+    pub fn resume<R: std::io::Read>(
+        opts: CircuitResolverOpts,
+        mut r: R,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut buf = [0; 8];
+
+        r.read_exact(&mut buf)?;
+        let len = u64::from_le_bytes(buf) as usize;
+
+        if len > opts.max_variables {
+            return Err(format!(
+                "Checkpoint contains {} values, but the resolver is limited to {}.",
+                len, opts.max_variables
+            )
+            .into());
+        }
+
+        let mut this = Self::new(opts);
+
+        for ix in 0..len {
+            let mut flag = [0; 1];
+            r.read_exact(&mut flag)?;
+
+            match flag[0] {
+                0 => {}
+                1 => {
+                    r.read_exact(&mut buf)?;
+
+                    this.sorter.set_value(
+                        Place::from_variable_index(ix as u64),
+                        V::from_u64_unchecked(u64::from_le_bytes(buf)),
+                    );
+                }
+                x => return Err(format!("Invalid value flag {} at index {}.", x, ix).into()),
+            }
+        }
+
+        r.read_exact(&mut buf)?;
+        let order_len = u64::from_le_bytes(buf);
+
+        let mut resumed = ResumedOrder::default();
+
+        for ix in 0..order_len {
+            let mut registration = [0; 4];
+            let mut flag = [0; 1];
+            r.read_exact(&mut registration)?;
+            r.read_exact(&mut flag)?;
+
+            match flag[0] {
+                0 => {}
+                1 => {
+                    resumed
+                        .invoked
+                        .insert(crate::dag::guide::RegistrationNum::from_le_bytes(
+                            registration,
+                        ));
+                }
+                x => return Err(format!("Invalid invocation flag {} at order {}.", x, ix).into()),
+            }
+        }
+
+        this.resumed = Some(resumed);
+
+        Ok(this)
+    }
+}
+
+impl<V: SmallField, Rrs: ResolutionRecordSource + 'static, CFG: CSResolverConfig>
+    MtCircuitResolver<V, PlaybackResolverSorter<V, Rrs, CFG>, CFG>
+{
+    /// Prepares the resolver to play the same record back for a new set of
+    /// inputs. Waits for the current resolution to finish, if it is still
+    /// running.
+    pub fn reset(&mut self) {
+        self.clear();
+    }
+
+    /// Sets `CircuitResolverOpts::max_pending_resolvers` for the playback,
+    /// which isn't configured with the options.
+    pub fn set_max_pending_resolvers(&mut self, value: Option<usize>) {
+        self.sorter.set_max_pending_resolvers(value);
+    }
+}
+
+impl<V: SmallField, RS: ResolverSortingMode<V> + 'static, CFG: CSResolverConfig> WitnessSource<V>
+    for MtCircuitResolver<V, RS, CFG>
+{
+    const PRODUCES_VALUES: bool = true;
+
+    fn try_get_value(&self, variable: Place) -> Option<V> {
+        let (v, md) = unsafe { self.common.values.u_deref().get_item_ref(variable) };
+
+        match md.is_resolved() {
+            true => {
+                fence(std::sync::atomic::Ordering::Acquire);
+                Some(*v)
+            }
+            false => None,
+        }
+    }
+
+    fn get_value_unchecked(&self, variable: Place) -> V {
+        // TODO: Should this fn be marked as unsafe?
+
+        // Safety: Dereferencing as & in &self context.
+        let (r, md) = unsafe { self.common.values.u_deref().get_item_ref(variable) };
+        // log!("gvu: {:0>8} -> {}", variable.0, r);
+
+        debug_assert!(
+            md.is_resolved(),
+            "Attempted to get value of unresolved variable."
+        );
+
+        *r
+    }
+}
+
+impl<V: SmallField, RS: ResolverSortingMode<V> + 'static, CFG: CSResolverConfig> CSWitnessSource<V>
+    for MtCircuitResolver<V, RS, CFG>
+{
+}
+
+impl<V: SmallField, RS: ResolverSortingMode<V> + 'static, CFG: CSResolverConfig>
+    WitnessSourceAwaitable<V> for MtCircuitResolver<V, RS, CFG>
+{
+    type Awaiter<'a> = awaiters::Awaiter<'a, RS::TrackId>;
+
+    fn get_awaiter<const N: usize>(&mut self, vars: [Place; N]) -> awaiters::Awaiter<RS::TrackId> {
+        self.try_get_awaiter(vars)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+// impl Drop for CircuitResolver
+
+impl<V: SmallField, RS: ResolverSortingMode<V>, CFG: CSResolverConfig> Drop
+    for MtCircuitResolver<V, RS, CFG>
+{
+    fn drop(&mut self) {
+        let verbose = self.verbose_drop && self.silent == false;
+
+        if verbose {
+            print!("Starting drop of CircuitResolver (If this hangs, it's bad)...");
+        }
+
+        // Nothing awaited the values, so there is no point in spawning a
+        // window just to resolve them.
+        if self.lazy_window.is_some() {
+            self.cancel();
+        }
+
+        self.wait_till_resolved_impl(false);
+
+        if verbose {
+            log!("ok");
+        }
+    }
+}
+
+#[cfg(test)]
 mod test {
     use std::collections::VecDeque;
     use std::hint::spin_loop;
     use std::rc::Rc;
     use std::sync::Mutex;
 
-    use itertools::Itertools as _;
+    use itertools::Itertools as _;
+
+    use crate::config::Resolver;
+    use crate::cs::traits::cs::DstBuffer;
+    use crate::cs::Place;
+    use crate::dag::resolvers::mt::sorters::sorter_live::*;
+    use crate::dag::resolvers::mt::sorters::sorter_playback::PlaybackResolverSorter;
+    use crate::dag::resolvers::mt::sorters::ResolverSortingMode;
+    use crate::dag::resolvers::MtCircuitResolver;
+    use crate::dag::{
+        Awaiter, CircuitResolverOpts, WitnessSource as _, WitnessSourceAwaitable as _,
+    };
+
+    use crate::field::SmallField;
+    use crate::log;
+    use crate::utils::PipeOp as _;
+    use crate::{
+        config::DoPerformRuntimeAsserts,
+        cs::Variable,
+        field::{goldilocks::GoldilocksField, Field},
+    };
+
+    use super::sorters::{ResolutionRecordSource, ResolutionRecordWriter};
+    use super::*;
+
+    type F = GoldilocksField;
+    type Cfg = Resolver<DoPerformRuntimeAsserts>;
+
+    pub struct TestRecordStorage {
+        record: std::rc::Rc<ResolutionRecord>,
+    }
+
+    impl ResolutionRecordWriter for TestRecordStorage {
+        fn store(&mut self, _record: &ResolutionRecord) {}
+    }
+
+    impl ResolutionRecordSource for TestRecordStorage {
+        fn get(&self) -> &ResolutionRecord {
+            &self.record
+        }
+    }
+
+    struct CountingSpawner(AtomicUsize);
+
+    impl Spawner for CountingSpawner {
+        fn spawn(&self, f: Box<dyn FnOnce() + Send>) -> JoinHandle<()> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            std::thread::spawn(f)
+        }
+    }
+
+    #[test]
+    fn playground() {
+        let mut v = VecDeque::with_capacity(4);
+
+        v.push_front(1);
+        v.push_front(2);
+        v.push_front(3);
+        v.push_front(4);
+
+        log!("{:#?}", v.iter().take(5).collect_vec());
+
+        assert_eq!(4, v.len());
+    }
+
+    fn tracks_values_populate<F: SmallField, RS: ResolverSortingMode<F>>(
+        resolver: &mut MtCircuitResolver<F, RS, Cfg>,
+        limit: u64,
+    ) {
+        for i in 0..limit {
+            let a = Place::from_variable(Variable::from_variable_index(i));
+
+            resolver.set_value(a, F::from_u64_with_reduction(i));
+        }
+    }
+
+    #[test]
+    fn tracks_values_record_mode() {
+        let limit = 10;
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        log!("Storage is ready");
+
+        tracks_values_populate(&mut storage, limit);
+
+        for i in 0..limit {
+            let a = Place::from_variable(Variable::from_variable_index(i));
+            let v = storage.get_value_unchecked(a);
+
+            assert_eq!(F::from_u64_with_reduction(i), v);
+        }
+    }
+
+    #[test]
+    fn max_tracked_place_follows_contiguous_prefix() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        assert_eq!(None, storage.max_tracked_place());
+
+        let var = |i| Place::from_variable(Variable::from_variable_index(i));
+
+        storage.set_value(var(1), F::from_u64_with_reduction(1));
+
+        assert_eq!(None, storage.max_tracked_place());
+
+        storage.set_value(var(0), F::from_u64_with_reduction(0));
+
+        assert_eq!(Some(var(1)), storage.max_tracked_place());
+    }
+
+    #[test]
+    fn reports_closure_memory() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::ONE);
+
+        storage.add_resolution(&[var(0)], &[var(1)], |ins, outs| outs.push(ins[0]));
+
+        assert_eq!(0, storage.closure_memory());
+
+        let captured = [F::ONE; 8];
+
+        storage.add_resolution(&[var(1)], &[var(2)], move |ins, outs| {
+            let mut x = ins[0];
+            outs.push(*x.add_assign(&captured[7]));
+        });
+
+        assert_eq!(64, storage.closure_memory());
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(2),
+            storage.get_value_unchecked(var(2))
+        );
+    }
+
+    #[test]
+    fn resolves_to_barrier() {
+        for threads in [0, 4] {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+                    CircuitResolverOpts::builder(100)
+                        .desired_parallelism(16)
+                        .build(),
+                    threads,
+                );
+
+            let var = Place::from_variable_index;
+
+            storage.set_value(var(0), F::from_u64_with_reduction(1));
+
+            let double = |ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut x = ins[0];
+                outs.push(*x.double());
+            };
+
+            for i in 0..10 {
+                storage.add_resolution(&[var(i)], &[var(i + 1)], double);
+            }
+
+            storage.barrier();
+
+            assert_eq!(
+                Ok(F::from_u64_with_reduction(1 << 10)),
+                storage.get_value(var(10))
+            );
+            assert!(storage.is_registration_open());
+
+            for i in 10..20 {
+                storage.add_resolution(&[var(i)], &[var(i + 1)], double);
+            }
+
+            storage.barrier();
+
+            assert_eq!(
+                Ok(F::from_u64_with_reduction(1 << 20)),
+                storage.get_value(var(20))
+            );
+
+            storage.wait_till_resolved();
+        }
+    }
+
+    #[test]
+    fn resolves_past_declared_hole() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        let double = |ins: &[F], outs: &mut DstBuffer<F>| {
+            let mut x = ins[0];
+            outs.push(*x.double());
+        };
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+
+        for i in 0..4 {
+            storage.add_resolution(&[var(i)], &[var(i + 1)], double);
+        }
+
+        // The branch producing 5 is never taken.
+        storage.add_resolution(&[var(4)], &[var(6)], double);
+        storage.add_resolution(&[var(6)], &[var(7)], double);
+
+        assert!(matches!(
+            storage.try_get_awaiter([var(7)]),
+            Err(CircuitResolverError::Hole { .. })
+        ));
+
+        storage.declare_hole(var(5));
+
+        assert_eq!(PlaceStatus::Hole, storage.place_status(var(5)));
+        assert_eq!(Err(GetValueError::Hole), storage.get_value(var(5)));
+        assert!(matches!(
+            storage.try_add_resolution(&[var(5)], &[var(8)], double),
+            Err(CircuitResolverError::DeclaredHole { .. })
+        ));
+        assert!(matches!(
+            storage.try_set_value(var(5), F::ONE),
+            Err(CircuitResolverError::DeclaredHole { .. })
+        ));
+
+        storage.get_awaiter([var(7)]).wait();
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            Ok(F::from_u64_with_reduction(1 << 6)),
+            storage.get_value(var(7))
+        );
+        assert_eq!(0, storage.unresolved_places().count());
+    }
+
+    #[test]
+    fn reserves_exec_order() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.reserve(1000);
+
+        assert!(storage.common.exec_order.lock().unwrap().items.capacity() >= 1125);
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(Place::from_variable_index(3))
+        );
+    }
+
+    #[test]
+    fn silences_output() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(100)
+                .desired_parallelism(16)
+                .silent(true)
+                .build(),
+        );
+
+        assert!(storage.silent);
+
+        storage.set_verbose_drop(true);
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(Place::from_variable_index(3))
+        );
+    }
+
+    #[test]
+    fn reports_track_advance() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        let advances = Arc::new(Mutex::new(Vec::new()));
+
+        storage.on_track_advance({
+            let advances = advances.clone();
+            Box::new(move |x| advances.lock().unwrap().push(x))
+        });
+
+        let copy = |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]);
+
+        storage.set_value(var(2), F::ONE);
+        storage.set_value(var(0), F::ONE);
+        storage.set_value(var(1), F::ONE);
+        storage.add_resolution(&[var(2)], &[var(4)], copy);
+        storage.add_resolution(&[var(2)], &[var(3)], copy);
+        storage.flush();
+
+        assert_eq!(vec![var(0), var(2), var(4)], *advances.lock().unwrap());
+
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn declares_tracked_prefix() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 20,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        // Waits past the prefix.
+        storage.add_resolution(&[var(12)], &[var(13)], |ins, outs| outs.push(ins[0]));
+
+        storage.declare_tracked_prefix(var(9));
+
+        assert_eq!(Some(var(9)), storage.max_tracked_place());
+        assert!(matches!(
+            storage.try_add_resolution(&[var(3)], &[var(10)], |ins, outs| outs.push(ins[0])),
+            Err(CircuitResolverError::NotSet { place }) if place == var(3)
+        ));
+        assert!(matches!(
+            storage.try_get_awaiter([var(3)]),
+            Err(CircuitResolverError::NotSet { place }) if place == var(3)
+        ));
+
+        for i in (0..10).rev() {
+            storage.set_value(var(i), F::from_u64_with_reduction(i));
+        }
+
+        let ins = (0..10).map(var).collect::<Vec<_>>();
+
+        storage.add_resolution(&ins, &[var(10)], |ins, outs| {
+            outs.push(ins.iter().fold(F::ZERO, |mut acc, x| *acc.add_assign(x)))
+        });
+        storage.add_resolution(&[var(10)], &[var(11)], |ins, outs| outs.push(ins[0]));
+        storage.set_value(var(12), F::from_u64_with_reduction(1));
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(45),
+            storage.get_value_unchecked(var(11))
+        );
+        assert_eq!(
+            F::from_u64_with_reduction(1),
+            storage.get_value_unchecked(var(13))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "waits on a place in the declared prefix")]
+    fn declared_prefix_rejects_waiting_registrations() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 20,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.add_resolution(&[var(3)], &[var(10)], |ins, outs| outs.push(ins[0]));
+
+        storage.declare_tracked_prefix(var(9));
+    }
+
+    #[test]
+    fn cancel_stops_resolution_early() {
+        let limit = 200;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit + 1,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = |i| Place::from_variable(Variable::from_variable_index(i as u64));
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+
+        for i in 0..limit {
+            storage.add_resolution(&[var(i)], &[var(i + 1)], |ins, outs| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                let mut x = ins[0];
+                outs.push(*x.add_assign(&F::ONE));
+            });
+        }
+
+        storage.get_awaiter([var(2)]).wait();
+
+        let start = std::time::Instant::now();
+
+        storage.cancel();
+
+        assert!(
+            start.elapsed() < std::time::Duration::from_millis(limit as u64 * 10 / 2),
+            "Cancellation took {:?}",
+            start.elapsed()
+        );
+
+        assert_eq!(
+            F::from_u64_with_reduction(1),
+            storage.get_value_unchecked(var(0))
+        );
+        assert_eq!(
+            F::from_u64_with_reduction(3),
+            storage.get_value_unchecked(var(2))
+        );
+        assert_eq!(None, storage.try_get_value(var(limit)));
+    }
+
+    #[test]
+    fn resolves_awaited_ahead_of_order() {
+        // Whether the slow chain ordered before the awaited one was resolved
+        // by the time the awaiter returned.
+        let run = |prioritize_awaited| {
+            let limit = 50;
+
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+                    CircuitResolverOpts::builder(limit + 8)
+                        .desired_parallelism(16)
+                        .prioritize_awaited(prioritize_awaited)
+                        .build(),
+                    1,
+                );
+
+            let var = |i| Place::from_variable(Variable::from_variable_index(i as u64));
+
+            storage.set_value(var(0), F::from_u64_with_reduction(1));
+
+            for i in 0..limit {
+                storage.add_resolution(&[var(i)], &[var(i + 1)], |ins, outs| {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    let mut x = ins[0];
+                    outs.push(*x.add_assign(&F::ONE));
+                });
+            }
+
+            storage.flush();
+
+            storage.set_value(var(limit + 1), F::ONE);
+
+            for i in limit + 1..limit + 6 {
+                storage.add_resolution(&[var(i)], &[var(i + 1)], |ins, outs| {
+                    let mut x = ins[0];
+                    outs.push(*x.double());
+                });
+            }
+
+            storage.get_awaiter([var(limit + 6)]).wait();
+
+            let slow_resolved = storage.try_get_value(var(limit)).is_some();
+
+            assert_eq!(
+                F::from_u64_with_reduction(32),
+                storage.get_value_unchecked(var(limit + 6))
+            );
+
+            storage.wait_till_resolved();
+
+            assert_eq!(
+                F::from_u64_with_reduction(limit as u64 + 1),
+                storage.get_value_unchecked(var(limit))
+            );
+
+            slow_resolved
+        };
+
+        assert!(run(false));
+        assert!(run(true) == false);
+    }
+
+    fn checkpoint_chain_populate(
+        storage: &mut MtCircuitResolver<F, LiveResolverSorter<F, Cfg>, Cfg>,
+        limit: usize,
+        invocations: &Arc<AtomicUsize>,
+    ) {
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..limit as u64 {
+            let invocations = Arc::clone(invocations);
+
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                move |ins, outs| {
+                    invocations.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+
+                    let mut x = ins[0];
+                    outs.push(*x.add_assign(&F::ONE));
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn resumes_from_checkpoint() {
+        use std::sync::atomic::Ordering;
+
+        let limit = 50;
+        let opts = CircuitResolverOpts {
+            max_variables: limit + 1,
+            desired_parallelism: 16,
+            ..Default::default()
+        };
+
+        let invocations = Arc::new(AtomicUsize::new(0));
+
+        let mut checkpoint = Vec::new();
+
+        {
+            let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(opts);
+
+            checkpoint_chain_populate(&mut storage, limit, &invocations);
+
+            storage.get_awaiter([Place::from_variable_index(10)]).wait();
+            storage.cancel();
+
+            storage.checkpoint(&mut checkpoint).unwrap();
+        }
+
+        let resolved_before = invocations.swap(0, Ordering::Relaxed);
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::resume(opts, &checkpoint[..])
+                .unwrap();
+
+        checkpoint_chain_populate(&mut storage, limit, &invocations);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(limit, resolved_before + invocations.load(Ordering::Relaxed));
+
+        for i in 0..=limit as u64 {
+            assert_eq!(
+                F::from_u64_with_reduction(i + 1),
+                storage.get_value_unchecked(Place::from_variable_index(i))
+            );
+        }
+    }
+
+    #[test]
+    fn checkpoints_running_resolution() {
+        use std::sync::atomic::Ordering;
+
+        let limit = 50;
+        let opts = CircuitResolverOpts {
+            max_variables: limit + 1,
+            desired_parallelism: 16,
+            ..Default::default()
+        };
+
+        let invocations = Arc::new(AtomicUsize::new(0));
+
+        // Checkpoints while the registration is open and the window runs.
+        let suspend = |storage: &mut MtCircuitResolver<F, LiveResolverSorter<F, Cfg>, Cfg>,
+                       place: u64| {
+            checkpoint_chain_populate(storage, limit, &invocations);
+            storage
+                .get_awaiter([Place::from_variable_index(place)])
+                .wait();
+
+            let mut checkpoint = Vec::new();
+            storage.checkpoint(&mut checkpoint).unwrap();
+
+            assert!(storage.is_registration_open() == false);
+
+            checkpoint
+        };
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(opts);
+        let checkpoint = suspend(&mut storage, 10);
+        drop(storage);
+
+        // A resumed resolver is suspended again.
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::resume(opts, &checkpoint[..])
+                .unwrap();
+        let checkpoint = suspend(&mut storage, 30);
+        drop(storage);
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::resume(opts, &checkpoint[..])
+                .unwrap();
+
+        checkpoint_chain_populate(&mut storage, limit, &invocations);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(limit, invocations.load(Ordering::Relaxed));
+
+        for i in 0..=limit as u64 {
+            assert_eq!(
+                F::from_u64_with_reduction(i + 1),
+                storage.get_value_unchecked(Place::from_variable_index(i))
+            );
+        }
+    }
+
+    #[cfg(feature = "profile-resolvers")]
+    #[test]
+    fn collects_resolver_timings() {
+        let limit = 20;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit + 1,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..limit as u64 {
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                move |ins, outs| {
+                    if i == 7 {
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                    }
+
+                    outs.push(ins[0]);
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        let histogram = storage.resolver_timing_histogram();
+
+        assert_eq!(limit as u64, histogram.total_invocations);
+        assert_eq!(limit as u64, histogram.buckets.iter().sum::<u64>());
+        assert_eq!(7, histogram.slowest[0].0);
+    }
+
+    #[cfg(feature = "profile-resolvers")]
+    #[test]
+    fn reports_slowest_resolver() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 21,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..20 {
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                move |ins, outs| {
+                    if i == 11 {
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                    }
+
+                    outs.push(ins[0]);
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        let slowest = storage.slowest_resolver().unwrap();
+
+        assert_eq!(11, slowest.registration);
+        assert_eq!(vec![Place::from_variable_index(12)], slowest.outputs);
+        assert!(slowest.duration >= std::time::Duration::from_millis(20));
+    }
+
+    #[cfg(feature = "record-timings")]
+    #[test]
+    fn records_resolver_timings() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 21,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..20 {
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                move |ins, outs| {
+                    if i == 11 {
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                    }
+
+                    outs.push(ins[0]);
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        let record = storage.retrieve_sequence().clone();
+        let timings = record.timings.as_ref().unwrap();
+
+        assert_eq!(20, timings.len());
+        assert!(timings[11] >= std::time::Duration::from_millis(20));
+
+        let mut bytes = Vec::new();
+        record.write_to(&mut bytes).unwrap();
+
+        let read = ResolutionRecord::read_from(&bytes[..]).unwrap();
+
+        assert_eq!(
+            timings.iter().map(|x| x.as_nanos()).collect_vec(),
+            read.timings
+                .unwrap()
+                .iter()
+                .map(|x| x.as_nanos())
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn get_value_reports_errors() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 4,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let release = Arc::new(AtomicBool::new(false));
+        let gate = Arc::clone(&release);
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(5));
+        storage.set_value(Place::from_variable_index(1), F::from_u64_with_reduction(7));
+        storage.add_resolution(
+            &[Place::from_variable_index(1)],
+            &[Place::from_variable_index(2)],
+            move |ins, outs| {
+                while gate.load(Ordering::Relaxed) == false {
+                    spin_loop();
+                }
+
+                outs.push(ins[0]);
+            },
+        );
+
+        assert_eq!(
+            Ok(F::from_u64_with_reduction(5)),
+            storage.get_value(Place::from_variable_index(0))
+        );
+        assert_eq!(
+            Err(GetValueError::NotResolved),
+            storage.get_value(Place::from_variable_index(2))
+        );
+        assert_eq!(
+            Err(GetValueError::NotTracked),
+            storage.get_value(Place::from_variable_index(3))
+        );
+        assert_eq!(
+            Err(GetValueError::OutOfRange),
+            storage.get_value(Place::from_variable_index(4))
+        );
+
+        release.store(true, Ordering::Relaxed);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            Ok(F::from_u64_with_reduction(7)),
+            storage.get_value(Place::from_variable_index(2))
+        );
+    }
+
+    #[test]
+    fn reports_place_status() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 4,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(5));
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1)],
+            |ins, outs| outs.push(ins[0]),
+        );
+
+        // Resolvers are only invoked on waiting, as there are no workers.
+        assert_eq!(
+            PlaceStatus::Resolved,
+            storage.place_status(Place::from_variable_index(0))
+        );
+        assert_eq!(
+            PlaceStatus::Tracked,
+            storage.place_status(Place::from_variable_index(1))
+        );
+        assert_eq!(
+            PlaceStatus::Untracked,
+            storage.place_status(Place::from_variable_index(2))
+        );
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            PlaceStatus::Resolved,
+            storage.place_status(Place::from_variable_index(1))
+        );
+    }
+
+    fn resolves_zero_input_populate<RS: ResolverSortingMode<F> + 'static>(
+        storage: &mut MtCircuitResolver<F, RS, Cfg>,
+    ) {
+        storage.add_resolution(&[], &[Place::from_variable_index(0)], |_, outs| {
+            outs.push(F::from_u64_with_reduction(42))
+        });
+
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1)],
+            |ins, outs| outs.push(ins[0]),
+        );
+
+        storage.get_awaiter([Place::from_variable_index(1)]).wait();
+
+        storage.add_resolution(&[], &[Place::from_variable_index(2)], |_, outs| {
+            outs.push(F::from_u64_with_reduction(7))
+        });
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(42),
+            storage.get_value_unchecked(Place::from_variable_index(1))
+        );
+        assert_eq!(
+            F::from_u64_with_reduction(7),
+            storage.get_value_unchecked(Place::from_variable_index(2))
+        );
+    }
+
+    #[test]
+    fn resolves_zero_input_resolutions() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 3,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_zero_input_populate(&mut storage);
+
+        let rs = TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        };
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
+
+        resolves_zero_input_populate(&mut storage);
+    }
+
+    fn record_descendants() -> TestRecordStorage {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "3 registrations were recorded, but 2 were registered")]
+    fn rejects_record_with_other_registrations() {
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(
+                record_descendants(),
+            );
+
+        resolves_siblings_populate(&mut storage);
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    #[should_panic(expected = "counts of the registrations differ")]
+    fn rejects_record_with_other_arity() {
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(
+                record_descendants(),
+            );
+
+        let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
+            outs.push(ins[0]);
+        };
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(2));
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1)],
+            res_fn,
+        );
+        storage.add_resolution(
+            &[Place::from_variable_index(0), Place::from_variable_index(1)],
+            &[Place::from_variable_index(2)],
+            res_fn,
+        );
+        storage.add_resolution(
+            &[Place::from_variable_index(2)],
+            &[Place::from_variable_index(3)],
+            res_fn,
+        );
+        storage.wait_till_resolved();
+    }
+
+    fn record_verified(salt: u64) -> TestRecordStorage {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                verify_playback: true,
+                ..Default::default()
+            });
+
+        populate_salted(&mut storage, salt);
+        storage.wait_till_resolved();
+
+        TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        }
+    }
+
+    fn populate_salted<RS: ResolverSortingMode<F>>(
+        storage: &mut MtCircuitResolver<F, RS, Cfg>,
+        salt: u64,
+    ) {
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(2));
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1)],
+            move |ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut x = ins[0];
+                outs.push(*x.add_assign(&F::from_u64_with_reduction(salt)))
+            },
+        );
+    }
+
+    #[test]
+    fn verifies_playback() {
+        let rs = record_verified(1);
+
+        assert_eq!(2, rs.record.values.as_ref().unwrap().len());
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
+
+        populate_salted(&mut storage, 1);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(3),
+            storage.get_value_unchecked(Place::from_variable_index(1))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Playback diverged from the record at")]
+    fn detects_nondeterministic_playback() {
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(
+                record_verified(1),
+            );
+
+        populate_salted(&mut storage, 2);
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn copies_transposed_columns() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(0));
+
+        // Each place holds its index, the grid is 3 columns of 4 rows
+        // starting at 10, 20 and 30.
+        for i in 1..=34 {
+            storage.add_resolution(&[var(i - 1)], &[var(i)], move |_, outs| {
+                outs.push(F::from_u64_with_reduction(i))
+            });
+        }
+
+        storage.wait_till_resolved();
+
+        let mut rows = vec![F::ZERO; 12];
+
+        storage.copy_transposed(&[10..14, 20..24, 30..34], &mut rows);
+
+        assert_eq!(
+            [10, 20, 30, 11, 21, 31, 12, 22, 32, 13, 23, 33]
+                .map(F::from_u64_with_reduction)
+                .to_vec(),
+            rows
+        );
+    }
+
+    #[test]
+    fn copies_resolved_range() {
+        let limit = 10;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit + 1,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..limit as u64 {
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                |ins, outs| {
+                    let mut x = ins[0];
+                    outs.push(*x.double());
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        let mut column = vec![F::ZERO; 5];
+
+        storage.copy_resolved_into(3..8, &mut column);
+
+        assert_eq!(
+            (3..8)
+                .map(|i| F::from_u64_with_reduction(1 << i))
+                .collect_vec(),
+            column
+        );
+    }
+
+    #[test]
+    fn copies_resolved_raw_values() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        // `p + 5` is reduced to `5`.
+        storage.set_value(var(0), F::from_u64_with_reduction(F::CHAR - 1));
+        storage.set_value(var(1), F::from_u64_with_reduction(F::CHAR + 5));
+
+        storage.add_resolution(&[var(0), var(1)], &[var(2)], |ins, outs| {
+            let mut x = ins[0];
+            outs.push(*x.add_assign(&ins[1]));
+        });
+
+        assert_eq!(None, storage.get_value_raw_u64(var(3)));
+
+        storage.wait_till_resolved();
+
+        assert_eq!(Some(F::CHAR - 1), storage.get_value_raw_u64(var(0)));
+        assert_eq!(Some(5), storage.get_value_raw_u64(var(1)));
+        assert_eq!(Some(4), storage.get_value_raw_u64(var(2)));
+
+        let mut raw = [0; 3];
+
+        storage.copy_resolved_raw_into(0..3, &mut raw);
+
+        assert_eq!([F::CHAR - 1, 5, 4], raw);
+    }
+
+    #[test]
+    fn reports_parallelism() {
+        let limit = 64;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit * 2,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        for i in 0..limit as u64 {
+            storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+        }
+
+        for i in 0..limit as u64 {
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(limit as u64 + i)],
+                |ins, outs| outs.push(ins[0]),
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        let report = storage.parallelism_report();
+
+        assert_eq!(limit, report.registrations);
+        assert!(report.min >= 1);
+        assert!(report.max <= 16);
+        assert!(report.min as f64 <= report.mean && report.mean <= report.max as f64);
+    }
+
+    #[test]
+    #[should_panic(expected = "Registration is closed")]
+    fn rejects_registration_after_resolution() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let dep_var = resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        storage.add_resolution(
+            &[dep_var],
+            &[Place::from_variable_index(4)],
+            |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]),
+        );
+    }
+
+    #[test]
+    fn reports_registration_open() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        assert!(storage.is_registration_open());
+
+        resolves_descendants_populate(&mut storage);
+
+        assert!(storage.is_registration_open());
+
+        storage.wait_till_resolved();
+
+        assert!(storage.is_registration_open() == false);
+    }
+
+    #[test]
+    fn resolves_vectorized() {
+        let len = VECTORIZED_CHUNK_LEN as u64 * 2 + 5;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: len as usize * 6,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let run = |k: u64| {
+            (0..len)
+                .map(|i| Place::from_variable_index(k * len + i))
+                .collect_vec()
+        };
+
+        let (lhs, rhs) = (run(0), run(1));
+        let (sum, diff, prod) = (run(2), run(3), run(4));
+
+        for i in 0..len as usize {
+            storage.set_value(lhs[i], F::from_u64_with_reduction(i as u64 * 3));
+            storage.set_value(rhs[i], F::from_u64_with_reduction(i as u64));
+        }
+
+        storage.add_vectorized_resolution(ElementwiseOp::Add, &lhs, &rhs, &sum);
+        storage.add_vectorized_resolution(ElementwiseOp::Sub, &lhs, &rhs, &diff);
+        storage.add_vectorized_resolution(ElementwiseOp::Mul, &sum, &diff, &prod);
+
+        storage.wait_till_resolved();
+
+        for i in 0..len as usize {
+            let i_u = i as u64;
+
+            assert_eq!(
+                F::from_u64_with_reduction(i_u * 4),
+                storage.get_value_unchecked(sum[i])
+            );
+            assert_eq!(
+                F::from_u64_with_reduction(i_u * 2),
+                storage.get_value_unchecked(diff[i])
+            );
+            assert_eq!(
+                F::from_u64_with_reduction(i_u * i_u * 8),
+                storage.get_value_unchecked(prod[i])
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "runs differ in length")]
+    fn vectorized_rejects_uneven_runs() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let places = (0..5).map(Place::from_variable_index).collect_vec();
+
+        storage.add_vectorized_resolution(
+            ElementwiseOp::Add,
+            &places[0..2],
+            &places[2..4],
+            &places[4..5],
+        );
+    }
+
+    #[test]
+    fn clears_and_resizes() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 1 << 16,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        storage.clear_and_resize(10);
+
+        assert_eq!(
+            10,
+            unsafe { storage.common.values.u_deref() }.variables.len()
+        );
+        assert!(storage.is_registration_open());
+        assert_eq!(None, storage.try_get_value(Place::from_variable_index(0)));
+
+        resolves_siblings_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(642),
+            storage.get_value_unchecked(Place::from_variable_index(3))
+        );
+
+        storage.clear_and_resize(100);
+
+        assert_eq!(
+            100,
+            unsafe { storage.common.values.u_deref() }.variables.len()
+        );
+    }
+
+    #[test]
+    fn clears_for_another_circuit() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                track_fanout: true,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        CircuitResolver::clear(&mut storage);
+
+        assert_eq!(
+            100,
+            unsafe { storage.common.values.u_deref() }.variables.len()
+        );
+        assert!(storage.is_registration_open());
+        assert_eq!(None, storage.try_get_value(Place::from_variable_index(0)));
+        assert!(storage.fanout.as_ref().unwrap().iter().all(|x| *x == 0));
+
+        resolves_siblings_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(642),
+            storage.get_value_unchecked(Place::from_variable_index(3))
+        );
+    }
+
+    #[test]
+    fn keeps_worker_threads_on_clear() {
+        let spawner = Arc::new(CountingSpawner(AtomicUsize::new(0)));
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_spawner(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            1,
+            spawner.clone(),
+        );
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        storage.clear_and_resize(10);
+
+        resolves_siblings_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        // The broker and one worker for each window.
+        assert_eq!(4, spawner.0.load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    #[test]
+    fn reports_resolver_errors() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let place = Place::from_variable_index;
+        let copy = |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]);
+
+        storage.set_value(place(0), F::from_u64_with_reduction(1));
+
+        assert!(matches!(
+            storage.try_set_value(place(0), F::from_u64_with_reduction(1)),
+            Err(CircuitResolverError::DuplicateValue { place: p }) if p == place(0)
+        ));
+
+        storage.set_value(place(1), F::from_u64_with_reduction(1));
+
+        // 2 and 3 are produced from each other.
+        storage.add_resolution(&[place(1), place(3)], &[place(2)], copy);
+        storage.add_resolution(&[place(2)], &[place(3)], copy);
+
+        match storage.try_wait_till_resolved() {
+            Err(CircuitResolverError::Cycle { places }) => {
+                assert_eq!(vec![place(3), place(2)], places)
+            }
+            e => panic!("Expected a cycle, got {:?}.", e),
+        }
+
+        storage.add_resolution(&[place(1), place(5)], &[place(6)], copy);
+
+        storage.cancel();
+
+        assert!(matches!(
+            storage.try_set_value(place(10), F::from_u64_with_reduction(1)),
+            Err(CircuitResolverError::RegistrationClosed)
+        ));
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(place(0), F::from_u64_with_reduction(1));
+        storage.add_resolution(&[place(0), place(2)], &[place(3)], copy);
+
+        match storage.try_wait_till_resolved() {
+            Err(CircuitResolverError::Hole {
+                requested,
+                max_tracked,
+            }) => {
+                assert_eq!(place(2), requested);
+                assert_eq!(Some(place(0)), max_tracked);
+            }
+            e => panic!("Expected a hole, got {:?}.", e),
+        }
+
+        storage.set_value(place(1), F::from_u64_with_reduction(1));
+        storage.set_value(place(2), F::from_u64_with_reduction(2));
+
+        storage.try_wait_till_resolved().unwrap();
+
+        assert_eq!(
+            F::from_u64_with_reduction(1),
+            storage.get_value_unchecked(place(3))
+        );
+    }
+
+    #[test]
+    fn skips_panicking_resolver() {
+        for threads in [0, 4] {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+                    CircuitResolverOpts::builder(100)
+                        .desired_parallelism(16)
+                        .on_resolver_panic(PanicPolicy::SkipAndRecord)
+                        .build(),
+                    threads,
+                );
+
+            let init_var = Place::from_variable_index(0);
+
+            storage.set_value(init_var, F::from_u64_with_reduction(1));
+
+            for i in 1..20 {
+                storage.add_resolution(
+                    &[init_var],
+                    &[Place::from_variable_index(i)],
+                    move |_, outs| {
+                        assert!(i != 5, "Resolution {} fails.", i);
+
+                        outs.push(F::from_u64_with_reduction(i));
+                    },
+                );
+            }
+
+            // Depends on the failed value.
+            storage.add_resolution(
+                &[Place::from_variable_index(5)],
+                &[Place::from_variable_index(20)],
+                |ins, outs| outs.push(ins[0]),
+            );
+
+            storage.wait_till_resolved();
+
+            for i in (1..20).filter(|x| *x != 5) {
+                assert_eq!(
+                    F::from_u64_with_reduction(i),
+                    storage.get_value_unchecked(Place::from_variable_index(i))
+                );
+            }
+
+            for i in [5, 20] {
+                assert_eq!(
+                    PlaceStatus::Failed,
+                    storage.place_status(Place::from_variable_index(i))
+                );
+            }
+
+            // The registrations are numbered from 0.
+            assert_eq!(vec![4, 19], storage.failed_registrations());
+        }
+    }
+
+    #[test]
+    fn reports_guide_order() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        let order = storage.guide_order();
+
+        assert_eq!(3, order.len());
+        assert!(order.iter().all(|x| matches!(
+            x.value.get_type(),
+            crate::dag::primitives::ResolverIxType::Resolver
+        )));
+        // The descendants form a chain.
+        assert!(order.iter().all(|x| x.metadata.parallelism() == 1));
+    }
+
+    #[test]
+    fn reports_execution_position() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        assert_eq!(ExecutionPosition::default(), storage.execution_position());
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        let position = storage.execution_position();
+
+        assert_eq!(3, position.executed);
+        assert_eq!(position.executed, position.available);
+        assert_eq!(position.available, position.total);
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        resolves_descendants_populate(&mut storage);
+        storage.resolve_until(Place::from_variable_index(2));
+
+        assert_eq!(2, storage.execution_position().executed);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(3, storage.execution_position().executed);
+    }
+
+    #[test]
+    fn resolves_with_idle_policies() {
+        use crate::dag::IdlePolicy;
+
+        for policy in [
+            IdlePolicy::Spin,
+            IdlePolicy::Yield,
+            IdlePolicy::Sleep(std::time::Duration::from_millis(1)),
+        ] {
+            let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+                CircuitResolverOpts::builder(100)
+                    .desired_parallelism(16)
+                    .window_idle_policy(policy)
+                    .build(),
+            );
+
+            resolves_descendants_populate(&mut storage);
+
+            // Idles the window between the registrations.
+            std::thread::sleep(std::time::Duration::from_millis(10));
+
+            storage.add_resolution(
+                &[Place::from_variable_index(3)],
+                &[Place::from_variable_index(4)],
+                |ins, outs| outs.push(ins[0]),
+            );
+
+            storage.wait_till_resolved();
+
+            assert_eq!(
+                Ok(F::from_u64_with_reduction(16)),
+                storage.get_value(Place::from_variable_index(4))
+            );
+        }
+    }
+
+    #[test]
+    fn iterates_resolved_values_while_resolving() {
+        let limit = 200;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit + 1,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = |i| Place::from_variable_index(i as u64);
+
+        storage.set_value(var(0), F::ZERO);
+
+        for i in 0..limit {
+            storage.add_resolution(&[var(i)], &[var(i + 1)], |ins, outs| {
+                std::thread::sleep(std::time::Duration::from_micros(100));
+                let mut x = ins[0];
+                outs.push(*x.add_assign(&F::ONE));
+            });
+        }
+
+        storage.flush();
+
+        let mut snapshots = 0;
+
+        while storage.try_get_value(var(limit)).is_none() {
+            for (place, value) in storage.iter_resolved() {
+                assert_eq!(F::from_u64_with_reduction(place.raw_ix() as u64), value);
+            }
+
+            snapshots += 1;
+        }
+
+        assert!(snapshots > 0);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(limit + 1, storage.iter_resolved().count());
+    }
+
+    #[test]
+    fn iterates_resolved_values() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(3));
+
+        storage.add_resolution(&[var(0)], &[var(1)], |ins, outs| {
+            let mut x = ins[0];
+            x.double();
+            outs.push(x);
+        });
+
+        storage.wait_till_resolved();
+
+        let resolved = storage.iter_resolved().collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (var(0), F::from_u64_with_reduction(3)),
+                (var(1), F::from_u64_with_reduction(6)),
+            ],
+            resolved
+        );
+    }
+
+    #[test]
+    fn breaks_down_values() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+        storage.set_value(var(1), F::from_u64_with_reduction(2));
+
+        storage.add_resolution(&[var(0), var(1)], &[var(2), var(3), var(4)], |ins, outs| {
+            outs.extend(ins.iter().copied());
+            outs.push(ins[0]);
+        });
+        storage.add_resolution(&[var(2)], &[var(5)], |ins, outs| outs.push(ins[0]));
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            ValueBreakdown {
+                seeded: 2,
+                derived: 4
+            },
+            storage.value_breakdown()
+        );
+    }
+
+    #[test]
+    fn finds_producers() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+        storage.add_resolution(&[var(0)], &[var(1)], |ins, outs| outs.push(ins[0]));
+        // Waits on the unset place 4.
+        storage.add_resolution(&[var(0), var(4)], &[var(5)], |ins, outs| outs.push(ins[1]));
+        storage.add_resolution(&[var(1)], &[var(2), var(3)], |ins, outs| {
+            outs.extend([ins[0], ins[0]])
+        });
+        storage.flush();
+
+        assert_eq!(None, storage.producer_of(var(0)));
+        assert_eq!(Some(0), storage.producer_of(var(1)));
+        assert_eq!(Some(1), storage.producer_of(var(5)));
+        assert_eq!(Some(2), storage.producer_of(var(3)));
+        assert_eq!(None, storage.producer_of(var(50)));
+
+        storage.set_value(var(4), F::from_u64_with_reduction(2));
+        storage.wait_till_resolved();
+
+        assert_eq!(Some(1), storage.producer_of(var(5)));
+    }
+
+    #[test]
+    fn checks_reachable_places() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 10,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+        storage.add_resolution(&[var(0)], &[var(1)], |ins, outs| outs.push(ins[0]));
+        // Behind the gap at 2.
+        storage.add_resolution(&[var(1)], &[var(3)], |ins, outs| outs.push(ins[0]));
+
+        assert_eq!(Ok(()), storage.check_reachable(&[var(0), var(1)]));
+        assert_eq!(
+            Err(vec![var(2), var(3), var(4)]),
+            storage.check_reachable(&[var(0), var(2), var(3), var(4)])
+        );
+
+        storage.declare_hole(var(2));
+
+        assert_eq!(
+            Err(vec![var(2)]),
+            storage.check_reachable(&[var(1), var(2), var(3)])
+        );
+
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn orders_ready_registrations_by_priority() {
+        let plan = |priority| {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: 100,
+                    desired_parallelism: 16,
+                    ..Default::default()
+                });
+
+            let var = Place::from_variable_index;
+
+            storage.set_value(var(0), F::from_u64_with_reduction(1));
+
+            // A low priority chain and a tail, all waiting on 1.
+            storage.add_resolution_prioritized(
+                &[var(0), var(1)],
+                &[var(2)],
+                Priority::Low,
+                |ins, outs| outs.push(ins[1]),
+            );
+            for i in 2..6 {
+                storage.add_resolution_prioritized(
+                    &[var(i)],
+                    &[var(i + 1)],
+                    Priority::Low,
+                    |ins, outs| outs.push(ins[0]),
+                );
+            }
+            storage.add_resolution_prioritized(
+                &[var(0), var(1)],
+                &[var(7)],
+                priority,
+                |ins, outs| outs.push(ins[1]),
+            );
+
+            storage.set_value(var(1), F::from_u64_with_reduction(2));
+            storage.wait_till_resolved();
+
+            assert_eq!(
+                F::from_u64_with_reduction(2),
+                storage.get_value_unchecked(var(7))
+            );
+
+            storage
+                .export_execution_plan()
+                .steps
+                .iter()
+                .map(|x| x.registration)
+                .collect_vec()
+        };
+
+        assert_eq!(vec![5, 0, 1, 2, 3, 4], plan(Priority::High));
+        assert_eq!(0, plan(Priority::Low)[0]);
+    }
+
+    #[test]
+    fn estimates_total_work() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        assert_eq!(0, storage.estimated_total_work());
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        assert_eq!(3, storage.estimated_total_work());
+        assert_eq!(storage.estimated_total_work(), storage.resolved_count());
+    }
+
+    #[test]
+    fn reads_value_assumed_resolved() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        for i in 0..4 {
+            let place = Place::from_variable_index(i);
+
+            assert_eq!(storage.get_value_unchecked(place), unsafe {
+                storage.get_value_assume_resolved(place)
+            });
+        }
+    }
+
+    #[test]
+    fn reads_value_refs() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        assert_eq!(None, storage.try_get_ref(Place::from_variable_index(0)));
+
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        for i in 0..4 {
+            let place = Place::from_variable_index(i);
+
+            assert_eq!(
+                Some(&storage.get_value_unchecked(place)),
+                storage.try_get_ref(place)
+            );
+        }
+
+        assert_eq!(None, storage.try_get_ref(Place::from_variable_index(4)));
+    }
+
+    #[test]
+    fn reports_resolved_bitset() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 200,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 1..100 {
+            storage.add_resolution(
+                &[Place::from_variable_index(i - 1)],
+                &[Place::from_variable_index(i)],
+                |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]),
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        let bitset = storage.resolved_bitset(60..130);
+
+        assert_eq!(70, bitset.len());
+        assert_eq!(40, bitset.count_ones());
+        assert!(bitset.contains(99));
+        assert!(bitset.contains(100) == false);
+        assert!(bitset.contains_all(&[u64::MAX >> 24]));
+        assert!(bitset.contains_all(&[0, 1]) == false);
+    }
+
+    #[test]
+    fn reports_pending_registrations() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let init_var = Place::from_variable_index(0);
+        let hole = Place::from_variable_index(1);
+        let dep_var = Place::from_variable_index(2);
+        let blocked_var = Place::from_variable_index(3);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(123));
+
+        storage.add_resolution(&[init_var], &[dep_var], |ins, outs| {
+            outs.push(ins[0]);
+        });
+
+        storage.add_resolution(&[init_var, hole], &[blocked_var], |ins, outs| {
+            outs.push(ins[1]);
+        });
+
+        assert_eq!(
+            vec![PendingInfo {
+                registration: 1,
+                outputs: vec![blocked_var],
+                waiting_on: hole,
+            }],
+            storage.pending_registrations()
+        );
+
+        storage.set_value(hole, F::from_u64_with_reduction(1));
+
+        assert!(storage.pending_registrations().is_empty());
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(1),
+            storage.get_value_unchecked(blocked_var)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the resolver capacity")]
+    fn panics_when_capacity_exceeded() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 4,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        storage.set_value(Place::from_variable_index(4), F::from_u64_with_reduction(1));
+    }
+
+    #[test]
+    fn reports_capacity_exceeded() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(4)
+                .on_capacity_exceeded(CapacityPolicy::Error)
+                .build(),
+        );
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        assert!(matches!(
+            storage.try_set_value(Place::from_variable_index(4), F::from_u64_with_reduction(1)),
+            Err(CircuitResolverError::CapacityExceeded(CapacityExceeded {
+                place,
+                capacity: 4
+            })) if place == Place::from_variable_index(4)
+        ));
+        assert!(matches!(
+            storage.try_add_resolution(
+                &[Place::from_variable_index(0)],
+                &[Place::from_variable_index(1), Place::from_variable_index(6)],
+                |ins: &[F], outs: &mut DstBuffer<F>| {
+                    outs.push(ins[0]);
+                    outs.push(ins[0]);
+                },
+            ),
+            Err(CircuitResolverError::CapacityExceeded(CapacityExceeded {
+                place,
+                capacity: 4
+            })) if place == Place::from_variable_index(6)
+        ));
+        assert!(storage
+            .try_add_resolution(
+                &[Place::from_variable_index(0)],
+                &[Place::from_variable_index(1)],
+                |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]),
+            )
+            .is_ok());
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(1),
+            storage.get_value_unchecked(Place::from_variable_index(1))
+        );
+    }
+
+    #[test]
+    fn grows_beyond_capacity() {
+        let limit = 100;
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(4)
+                .desired_parallelism(16)
+                .on_capacity_exceeded(CapacityPolicy::Grow)
+                .build(),
+        );
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..limit {
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                |ins: &[F], outs: &mut DstBuffer<F>| {
+                    let mut x = ins[0];
+                    x.add_assign(&F::ONE);
+                    outs.push(x);
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        for i in 0..=limit {
+            assert_eq!(
+                F::from_u64_with_reduction(i + 1),
+                storage.get_value_unchecked(Place::from_variable_index(i)),
+                "{}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_equal_duplicate_set() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(100)
+                .duplicate_set_policy(DuplicateSetPolicy::IgnoreIfEqual)
+                .build(),
+        );
+
+        let init_var = Place::from_variable_index(0);
+        let dep_var = Place::from_variable_index(1);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(123));
+        storage.set_value(init_var, F::from_u64_with_reduction(123));
+
+        storage.add_resolution(
+            &[init_var],
+            &[dep_var],
+            |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]),
+        );
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(123),
+            storage.get_value_unchecked(dep_var)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "is already set")]
+    fn panics_on_conflicting_duplicate_set() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(100)
+                .duplicate_set_policy(DuplicateSetPolicy::IgnoreIfEqual)
+                .build(),
+        );
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(2));
+    }
+
+    #[test]
+    fn overwrites_duplicate_set() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(100)
+                .duplicate_set_policy(DuplicateSetPolicy::Overwrite)
+                .build(),
+        );
+
+        let init_var = Place::from_variable_index(0);
+        let dep_var = Place::from_variable_index(1);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(1));
+        storage.set_value(init_var, F::from_u64_with_reduction(2));
+
+        storage.add_resolution(
+            &[init_var],
+            &[dep_var],
+            |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]),
+        );
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(2),
+            storage.get_value_unchecked(dep_var)
+        );
+    }
+
+    #[test]
+    fn notifies_awaiter_observer() {
+        #[derive(Default)]
+        struct Observer {
+            events: Mutex<Vec<(&'static str, u64)>>,
+        }
+
+        impl AwaiterObserver for Observer {
+            fn on_register(&self, md_tracker: u64) {
+                self.events.lock().unwrap().push(("register", md_tracker));
+            }
+
+            fn on_wake(&self, md_tracker: u64) {
+                self.events.lock().unwrap().push(("wake", md_tracker));
+            }
+        }
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let observer = Arc::new(Observer::default());
+        storage.set_awaiter_observer(Some(observer.clone()));
+
+        let dep_var = resolves_descendants_populate(&mut storage);
+
+        storage.get_awaiter([dep_var]).wait();
+        storage.get_awaiter([Place::from_variable_index(0)]).wait();
+
+        storage.wait_till_resolved();
+
+        let events = observer.events.lock().unwrap().clone();
+
+        assert_eq!(
+            vec!["register", "wake", "register", "wake"],
+            events.iter().map(|x| x.0).collect_vec()
+        );
+        assert_eq!(events[0].1, events[1].1);
+        assert_eq!(events[2].1, events[3].1);
+        assert!(events[0].1 > 0);
+    }
+
+    #[test]
+    fn reports_flush_progress() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]);
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1)],
+            res_fn,
+        );
+
+        assert_eq!(
+            FlushReport {
+                internalized: 1,
+                ordered: 1
+            },
+            storage.flush()
+        );
+        assert_eq!(1, storage.resolved_count());
+
+        // The input isn't set yet, so the registration is delayed.
+        storage.add_resolution(
+            &[Place::from_variable_index(2)],
+            &[Place::from_variable_index(3)],
+            res_fn,
+        );
+
+        assert_eq!(FlushReport::default(), storage.flush());
+
+        storage.set_value(Place::from_variable_index(2), F::from_u64_with_reduction(2));
+
+        assert_eq!(
+            FlushReport {
+                internalized: 1,
+                ordered: 1
+            },
+            storage.flush()
+        );
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(2),
+            storage.get_value_unchecked(Place::from_variable_index(3))
+        );
+    }
+
+    #[test]
+    fn batches_internalized_resolvers() {
+        let limit = 100;
+
+        let run = |batch_size| {
+            let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+                CircuitResolverOpts::builder(limit * 5)
+                    .desired_parallelism(16)
+                    .internalize_batch_size(batch_size)
+                    .build(),
+            );
+
+            populate(&mut storage, limit);
+
+            let published = storage.common.exec_order.lock().unwrap().size;
+
+            storage.wait_till_resolved();
+
+            assert_eq!(None, storage.unresolved_places().next());
+
+            (published, storage.retrieve_sequence().clone())
+        };
+
+        let (published, record) = run(1);
+        let (published_batched, record_batched) = run(1 << 20);
+
+        assert!(published > 0);
+        assert_eq!(0, published_batched);
+        assert_eq!(record.items, record_batched.items);
+    }
+
+    #[test]
+    fn exports_execution_plan() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let dep_var = resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        let plan = storage.export_execution_plan();
+
+        assert_eq!(
+            vec![
+                PlanStep {
+                    order_ix: 0,
+                    registration: 0,
+                    outputs: vec![Place::from_variable_index(1)],
+                },
+                PlanStep {
+                    order_ix: 1,
+                    registration: 1,
+                    outputs: vec![Place::from_variable_index(2)],
+                },
+                PlanStep {
+                    order_ix: 2,
+                    registration: 2,
+                    outputs: vec![dep_var],
+                },
+            ],
+            plan.steps
+        );
+        assert_eq!("0 0: v1\n1 1: v2\n2 2: v3\n", plan.to_string());
+    }
+
+    #[test]
+    fn resolves_until_target() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+        let dep_var1 = Place::from_variable_index(1);
+        let dep_var2 = Place::from_variable_index(2);
+
+        storage.resolve_until(dep_var2);
+
+        assert_eq!(PlaceStatus::Resolved, storage.place_status(dep_var1));
+        assert_eq!(PlaceStatus::Resolved, storage.place_status(dep_var2));
+        assert_eq!(PlaceStatus::Tracked, storage.place_status(dep_var3));
+        assert_eq!(
+            F::from_u64_with_reduction(8),
+            storage.get_value_unchecked(dep_var2)
+        );
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+
+        storage.resolve_until(dep_var2);
+
+        assert_eq!(
+            F::from_u64_with_reduction(8),
+            storage.get_value_unchecked(dep_var2)
+        );
+
+        storage.resolve_until(dep_var3);
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn barrier_lifts_resolve_until_bound() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_lazy(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+        );
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+
+        storage.resolve_until(Place::from_variable_index(2));
+
+        assert_eq!(PlaceStatus::Tracked, storage.place_status(dep_var3));
+
+        storage.barrier();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn awaiter_lifts_resolve_until_bound() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_lazy(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+        );
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+
+        storage.resolve_until(Place::from_variable_index(1));
+        storage.get_awaiter([dep_var3]).wait();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn grows_past_resolve_until_bound() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_lazy(
+            CircuitResolverOpts::builder(4)
+                .desired_parallelism(16)
+                .on_capacity_exceeded(CapacityPolicy::Grow)
+                .build(),
+        );
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+        let var = Place::from_variable_index(10);
+
+        storage.resolve_until(Place::from_variable_index(1));
+
+        // Growing waits for the execution order, which lies past the bound.
+        storage.add_resolution(&[dep_var3], &[var], |ins: &[F], outs: &mut DstBuffer<F>| {
+            outs.push(ins[0])
+        });
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(var)
+        );
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn renders_prometheus_metrics() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        let metrics = storage.prometheus_metrics();
+        let samples = metrics
+            .lines()
+            .filter(|x| x.starts_with('#') == false)
+            .collect::<Vec<_>>();
+
+        assert!(metrics.contains("# TYPE cr_registrations_added counter\n"));
+        assert_eq!("cr_values_added 1", samples[0]);
+        assert_eq!("cr_registrations_added 3", samples[1]);
+        assert!(samples[2].starts_with("cr_resolution_seconds "));
+        assert_eq!("cr_resolved_count 3", samples[3]);
+    }
+
+    #[test]
+    fn spawns_with_custom_spawner() {
+        let spawner = Arc::new(CountingSpawner(AtomicUsize::new(0)));
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_spawner(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            2,
+            spawner.clone(),
+        );
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        // The broker and two workers.
+        assert_eq!(3, spawner.0.load(std::sync::atomic::Ordering::Relaxed));
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+    }
+
+    #[cfg(feature = "numa")]
+    #[test]
+    fn touches_values_from_affinity_cpus() {
+        let spawner = Arc::new(CountingSpawner(AtomicUsize::new(0)));
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_spawner(
+            CircuitResolverOpts::builder(100)
+                .desired_parallelism(16)
+                .numa_first_touch(true)
+                .worker_affinity([0])
+                .build(),
+            1,
+            spawner.clone(),
+        );
+
+        // One first touch thread for the single CPU, then the broker and the
+        // worker.
+        assert_eq!(3, spawner.0.load(std::sync::atomic::Ordering::Relaxed));
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+    }
+
+    #[test]
+    fn spawns_value_store_threads_with_custom_spawner() {
+        use crate::dag::primitives::Metadata;
+
+        let spawner = Arc::new(CountingSpawner(AtomicUsize::new(0)));
+        let spawned = || spawner.0.load(std::sync::atomic::Ordering::Relaxed);
+
+        let storage = (0..100)
+            .map(|_| std::cell::UnsafeCell::new((F::ZERO, Metadata::default())))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        let mut resolver =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::with_value_store_and_spawner(
+                CircuitResolverOpts {
+                    max_variables: 100,
+                    desired_parallelism: 16,
+                    ..Default::default()
+                },
+                storage,
+                2,
+                spawner.clone(),
+            );
+
+        assert_eq!(3, spawned());
+
+        let dep_var3 = resolves_descendants_populate(&mut resolver);
+
+        // The completion future waits for the window on a spawned thread.
+        block_on(resolver.completion_future()).unwrap();
+
+        assert_eq!(4, spawned());
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            resolver.get_value_unchecked(dep_var3)
+        );
+    }
+
+    #[test]
+    fn spawns_lazy_window_on_first_wait() {
+        let spawner = Arc::new(CountingSpawner(AtomicUsize::new(0)));
+        let spawned = || spawner.0.load(std::sync::atomic::Ordering::Relaxed);
+
+        let lazy = || {
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_lazy_with_spawner(
+                CircuitResolverOpts {
+                    max_variables: 100,
+                    desired_parallelism: 16,
+                    ..Default::default()
+                },
+                2,
+                spawner.clone(),
+            )
+        };
+
+        let mut storage = lazy();
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+        storage.flush();
+
+        assert_eq!(0, spawned());
+        assert_eq!(None, storage.try_get_value(dep_var3));
+
+        storage.wait_till_resolved();
+
+        // The broker and two workers.
+        assert_eq!(3, spawned());
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+
+        // Never awaited, discarded on drop without a window.
+        let mut storage = lazy();
+        let invoked = Arc::new(AtomicUsize::new(0));
+
+        storage.set_value(Place::from_variable_index(0), F::ONE);
+
+        for i in 0..10 {
+            let invoked = invoked.clone();
+
+            storage.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                move |ins, outs| {
+                    invoked.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    outs.push(ins[0]);
+                },
+            );
+        }
+
+        storage.flush();
+        drop(storage);
+
+        assert_eq!(3, spawned());
+        assert_eq!(0, invoked.load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    #[test]
+    fn bounds_pending_resolvers() {
+        let max = 8;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 1000,
+                desired_parallelism: 16,
+                max_pending_resolvers: Some(max),
+                ..Default::default()
+            });
+
+        bounds_pending_populate(&mut storage, max);
+
+        let rs = TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        };
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
+
+        storage.set_max_pending_resolvers(Some(max));
+
+        bounds_pending_populate(&mut storage, max);
+    }
+
+    fn bounds_pending_populate<RS: ResolverSortingMode<F>>(
+        storage: &mut MtCircuitResolver<F, RS, Cfg>,
+        max: usize,
+    ) {
+        let init_var = Place::from_variable_index(0);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(1));
+
+        let mut max_pending = 0;
+
+        for i in 1..200 {
+            storage.add_resolution(
+                &[init_var],
+                &[Place::from_variable_index(i)],
+                |ins: &[F], outs: &mut DstBuffer<F>| {
+                    std::thread::sleep(std::time::Duration::from_micros(200));
+                    outs.push(ins[0]);
+                },
+            );
+
+            max_pending = std::cmp::max(
+                max_pending,
+                storage
+                    .sorter
+                    .progress()
+                    .ordered
+                    .saturating_sub(storage.resolved_count()),
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        assert!(max_pending <= max as u64);
+        assert_eq!(199, storage.resolved_count());
+    }
+
+    #[test]
+    fn notifies_on_resolve() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let init_var = Place::from_variable_index(0);
+        let dep_var3 = Place::from_variable_index(3);
+
+        storage.notify_on_resolve(dep_var3, tx.clone());
+        storage.notify_on_resolve(dep_var3, tx.clone());
+        storage.notify_on_resolve(init_var, tx.clone());
+
+        resolves_descendants_populate(&mut storage);
+
+        // Resolved already, so it fires right away.
+        storage.notify_on_resolve(init_var, tx);
+
+        storage.flush();
+
+        for _ in 0..4 {
+            rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
+        }
+
+        assert_eq!(PlaceStatus::Resolved, storage.place_status(dep_var3));
+
+        storage.wait_till_resolved();
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn times_out_at_deadline() {
+        fn populate_slow<RS: ResolverSortingMode<F>>(storage: &mut MtCircuitResolver<F, RS, Cfg>) {
+            storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+            for i in 1..100 {
+                storage.add_resolution(
+                    &[Place::from_variable_index(i - 1)],
+                    &[Place::from_variable_index(i)],
+                    |ins: &[F], outs: &mut DstBuffer<F>| {
+                        std::thread::sleep(std::time::Duration::from_millis(5));
+                        outs.push(ins[0]);
+                    },
+                );
+            }
+        }
+
+        for threads in [0, 3] {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+                    CircuitResolverOpts {
+                        max_variables: 100,
+                        desired_parallelism: 16,
+                        ..Default::default()
+                    },
+                    threads,
+                );
+
+            populate_slow(&mut storage);
+
+            let outcome = storage.wait_till_resolved_deadline(
+                std::time::Instant::now() + std::time::Duration::from_millis(50),
+            );
+
+            let ResolutionOutcome::TimedOut { resolved, pending } = outcome else {
+                panic!("Expected a timeout, got {:?}.", outcome);
+            };
+
+            assert_eq!(99, resolved + pending);
+            assert!(pending > 0);
+            assert_eq!(
+                PlaceStatus::Resolved,
+                storage.place_status(Place::from_variable_index(1))
+            );
+        }
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+
+        assert_eq!(
+            ResolutionOutcome::Completed,
+            storage.wait_till_resolved_deadline(
+                std::time::Instant::now() + std::time::Duration::from_secs(60)
+            )
+        );
+    }
+
+    #[test]
+    fn tracks_dependencies() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(100)
+                .desired_parallelism(16)
+                .track_dependencies(true)
+                .build(),
+        );
+
+        let var = Place::from_variable_index;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+        storage.set_value(var(1), F::from_u64_with_reduction(2));
+        storage.add_resolution(&[var(0), var(1)], &[var(2), var(3)], |ins, outs| {
+            outs.extend(ins.iter().copied())
+        });
+        storage.add_resolution(&[var(0), var(3)], &[var(4)], |ins, outs| outs.push(ins[1]));
+
+        storage.wait_till_resolved();
+
+        let graph = storage.dependency_graph();
+
+        assert_eq!(6, graph.edge_count());
+        assert_eq!(&[var(0), var(1)], graph.predecessors(var(2)));
+        assert_eq!(&[var(0), var(3)], graph.predecessors(var(4)));
+        assert!(graph.predecessors(var(0)).is_empty());
+        assert_eq!(&[var(2), var(3), var(4)], graph.successors(var(0)));
+        assert_eq!(&[var(4)], graph.successors(var(3)));
+        assert!(graph.successors(var(4)).is_empty());
+        assert_eq!(
+            (0..5).map(var).collect::<std::collections::HashSet<_>>(),
+            graph.places().collect::<std::collections::HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn computes_critical_path_length() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+            CircuitResolverOpts::builder(100)
+                .desired_parallelism(16)
+                .track_dependencies(true)
+                .build(),
+        );
+
+        let var = Place::from_variable_index;
+        let k = 10;
+
+        storage.set_value(var(0), F::from_u64_with_reduction(1));
+
+        // The chain, with its tail registered first.
+        for i in (0..k).rev() {
+            storage.add_resolution(&[var(i)], &[var(i + 1)], |ins, outs| outs.push(ins[0]));
+        }
+
+        // Shorter branches off the chain.
+        storage.add_resolution(&[var(0), var(3)], &[var(11)], |ins, outs| outs.push(ins[0]));
+        storage.add_resolution(&[var(11), var(5)], &[var(12)], |ins, outs| {
+            outs.push(ins[0])
+        });
+
+        storage.wait_till_resolved();
+
+        assert_eq!(k as usize, storage.critical_path_length());
+    }
+
+    #[test]
+    fn tracks_fanout() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                track_fanout: true,
+                ..Default::default()
+            });
+
+        let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
+            outs.push(ins[0]);
+        };
+
+        let init_var = Place::from_variable_index(0);
+        let dep_var1 = Place::from_variable_index(1);
+        let dep_var2 = Place::from_variable_index(2);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(1));
+        storage.add_resolution(&[init_var], &[dep_var1], res_fn);
+        storage.add_resolution(&[init_var, dep_var1], &[dep_var2], res_fn);
+        storage.add_resolution(&[init_var], &[Place::from_variable_index(3)], res_fn);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(3, storage.fanout(init_var));
+        assert_eq!(1, storage.fanout(dep_var1));
+        assert_eq!(0, storage.fanout(dep_var2));
+    }
+
+    #[test]
+    fn counts_resolved() {
+        let limit = 10;
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: limit * 5,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        populate(&mut storage, limit);
+
+        assert_eq!(0, storage.resolved_count());
+
+        storage.wait_till_resolved();
+
+        assert_eq!(limit as u64 * 2, storage.resolved_count());
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit * 5,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        populate(&mut storage, limit);
+        storage.wait_till_resolved();
+
+        assert_eq!(limit as u64 * 2, storage.resolved_count());
+    }
+
+    #[test]
+    fn gets_castable() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Limbs([F; 2]);
+
+        impl TrivialWitnessCastable<F, 2> for Limbs {
+            fn cast_from_field_elements(parts: [F; 2]) -> Self {
+                Self(parts)
+            }
+
+            fn cast_into_field_elements(self) -> [F; 2] {
+                self.0
+            }
+        }
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        let dep_var = resolves_descendants_populate(&mut storage);
+        let places = [Place::from_variable_index(0), dep_var];
+
+        assert_eq!(None, storage.get_castable::<Limbs, 2>(places));
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            Some(Limbs([
+                F::from_u64_with_reduction(2),
+                F::from_u64_with_reduction(16)
+            ])),
+            storage.get_castable(places)
+        );
+    }
+
+    #[test]
+    fn reads_resolved_handle() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Limbs([F; 2]);
+
+        impl TrivialWitnessCastable<F, 2> for Limbs {
+            fn cast_from_field_elements(parts: [F; 2]) -> Self {
+                Self(parts)
+            }
+
+            fn cast_into_field_elements(self) -> [F; 2] {
+                self.0
+            }
+        }
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let init_var = Place::from_variable_index(0);
+        let outputs = [Place::from_variable_index(1), Place::from_variable_index(2)];
+
+        let handle = storage.register_castable_output(&[init_var], outputs, |ins| {
+            let mut hi = ins[0];
+            Limbs([ins[0], *hi.double()])
+        });
+
+        assert_eq!(outputs, handle.places());
+        assert_eq!(None, handle.get(&storage));
+
+        storage.set_value(init_var, F::from_u64_with_reduction(3));
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            Some(Limbs([
+                F::from_u64_with_reduction(3),
+                F::from_u64_with_reduction(6)
+            ])),
+            handle.get(&storage)
+        );
+    }
+
+    #[test]
+    fn resolves_extension_field_values() {
+        use crate::field::{goldilocks::GoldilocksExt2, ExtensionField};
+
+        type Ext = ExtensionField<F, 2, GoldilocksExt2>;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let a =
+            Ext::from_coeff_in_base([F::from_u64_with_reduction(2), F::from_u64_with_reduction(3)]);
+        let b =
+            Ext::from_coeff_in_base([F::from_u64_with_reduction(5), F::from_u64_with_reduction(7)]);
+
+        let places = (0..6).map(Place::from_variable_index).collect_vec();
+
+        for (place, x) in places.iter().zip(a.coeffs.iter().chain(b.coeffs.iter())) {
+            storage.set_value(*place, *x);
+        }
+
+        storage.add_resolution(
+            &places[..4],
+            &places[4..],
+            |ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut x = Ext::cast_from_field_elements([ins[0], ins[1]]);
+                x.mul_assign(&Ext::cast_from_field_elements([ins[2], ins[3]]));
+
+                x.cast_into_field_elements()
+                    .into_iter()
+                    .for_each(|x| outs.push(x));
+            },
+        );
+
+        storage.wait_till_resolved();
+
+        let mut expected = a;
+        expected.mul_assign(&b);
+
+        assert_eq!(
+            Some(expected),
+            storage.get_castable::<Ext, 2>([places[4], places[5]])
+        );
+    }
+
+    #[test]
+    fn builds_opts() {
+        let opts = CircuitResolverOpts::builder(100)
+            .desired_parallelism(16)
+            .worker_affinity([1, 0])
+            .build();
+
+        assert_eq!(100, opts.max_variables);
+        assert_eq!(16, opts.desired_parallelism);
+        assert_eq!(
+            vec![0, 1],
+            opts.worker_affinity.unwrap().iter().collect::<Vec<_>>()
+        );
+        assert!(opts.numa_first_touch == false);
+
+        let opts = CircuitResolverOpts::new(100);
+
+        assert_eq!(
+            CircuitResolverOpts::default().desired_parallelism,
+            opts.desired_parallelism
+        );
+
+        let invalid = std::panic::catch_unwind(|| {
+            CircuitResolverOpts::builder(100)
+                .desired_parallelism(0)
+                .build()
+        });
+
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn playback_replays_recorded_parallelism() {
+        let limit = 64;
+
+        let populate = |storage: &mut dyn FnMut(&[Place], &[Place])| {
+            for i in 0..limit as u64 {
+                storage(
+                    &[Place::from_variable_index(i)],
+                    &[Place::from_variable_index(limit as u64 + i)],
+                );
+            }
+        };
+
+        fn parallelism<T: Default>(common: &ResolverCommonData<F, T>) -> Vec<usize> {
+            let exec_order = common.exec_order.lock().unwrap();
+
+            exec_order.items[..exec_order.size]
+                .iter()
+                .map(|x| x.metadata.parallelism())
+                .collect_vec()
+        }
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit * 2,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        for i in 0..limit as u64 {
+            storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+        }
+
+        populate(&mut |ins, outs| storage.add_resolution(ins, outs, |ins, outs| outs.push(ins[0])));
+        storage.wait_till_resolved();
+
+        let recorded = parallelism(&storage.common);
+
+        assert!(recorded.iter().any(|x| *x > 1));
+
+        let rs = TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        };
+
+        let mut storage = MtCircuitResolver::<
+            F,
+            PlaybackResolverSorter<F, TestRecordStorage, Cfg>,
+            Cfg,
+        >::new_with_threads(rs, 3);
+
+        for i in 0..limit as u64 {
+            storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+        }
+
+        populate(&mut |ins, outs| storage.add_resolution(ins, outs, |ins, outs| outs.push(ins[0])));
+        storage.wait_till_resolved();
 
-    use crate::config::Resolver;
-    use crate::cs::traits::cs::DstBuffer;
-    use crate::cs::Place;
-    use crate::dag::resolvers::mt::sorters::sorter_live::*;
-    use crate::dag::resolvers::mt::sorters::sorter_playback::PlaybackResolverSorter;
-    use crate::dag::resolvers::mt::sorters::ResolverSortingMode;
-    use crate::dag::resolvers::MtCircuitResolver;
-    use crate::dag::{
-        Awaiter, CircuitResolverOpts, WitnessSource as _, WitnessSourceAwaitable as _,
-    };
+        assert_eq!(recorded, parallelism(&storage.common));
 
-    use crate::field::SmallField;
-    use crate::log;
-    use crate::utils::PipeOp as _;
-    use crate::{
-        config::DoPerformRuntimeAsserts,
-        cs::Variable,
-        field::{goldilocks::GoldilocksField, Field},
-    };
+        for i in 0..limit as u64 {
+            assert_eq!(
+                F::from_u64_with_reduction(i),
+                storage.get_value_unchecked(Place::from_variable_index(limit as u64 + i))
+            );
+        }
+    }
 
-    use super::sorters::{ResolutionRecordSource, ResolutionRecordWriter};
-    use super::*;
+    #[test]
+    fn resolves_with_compact_track_id() {
+        use crate::dag::{primitives::Metadata, CompactGuideLoc, GuideTrackId};
 
-    type F = GoldilocksField;
-    type Cfg = Resolver<DoPerformRuntimeAsserts>;
+        assert!(
+            std::mem::size_of::<Metadata<CompactGuideLoc>>()
+                < std::mem::size_of::<Metadata<crate::dag::guide::GuideLoc>>()
+        );
 
-    pub struct TestRecordStorage {
-        record: std::rc::Rc<ResolutionRecord>,
+        let opts = CircuitResolverOpts {
+            max_variables: 100,
+            desired_parallelism: 16,
+            ..Default::default()
+        };
+
+        assert!(CompactGuideLoc::fits(&opts));
+        assert!(!CompactGuideLoc::fits(&CircuitResolverOpts::new(1 << 25)));
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg, CompactGuideLoc>, Cfg>::new(opts);
+
+        let ((_, dep_var1), (_, dep_var2)) = resolves_siblings_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(246),
+            storage.get_value_unchecked(dep_var1)
+        );
+        assert_eq!(
+            F::from_u64_with_reduction(642),
+            storage.get_value_unchecked(dep_var2)
+        );
     }
 
-    impl ResolutionRecordWriter for TestRecordStorage {
-        fn store(&mut self, _record: &ResolutionRecord) {}
+    #[test]
+    fn lists_unresolved_places() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 4,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let release = Arc::new(AtomicBool::new(false));
+        let gate = Arc::clone(&release);
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1), Place::from_variable_index(2)],
+            move |ins, outs| {
+                while gate.load(Ordering::Relaxed) == false {
+                    spin_loop();
+                }
+
+                outs.push(ins[0]);
+                outs.push(ins[0]);
+            },
+        );
+
+        assert_eq!(
+            vec![Place::from_variable_index(1), Place::from_variable_index(2)],
+            storage.unresolved_places().collect_vec()
+        );
+
+        release.store(true, Ordering::Relaxed);
+        storage.wait_till_resolved();
+
+        assert_eq!(0, storage.unresolved_places().count());
     }
 
-    impl ResolutionRecordSource for TestRecordStorage {
-        fn get(&self) -> &ResolutionRecord {
-            &self.record
+    #[test]
+    fn moves_record_out() {
+        struct ArcRecordStorage(Arc<ResolutionRecord>);
+
+        impl ResolutionRecordSource for ArcRecordStorage {
+            fn get(&self) -> &ResolutionRecord {
+                &self.0
+            }
         }
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        let summary = storage.record_summary();
+
+        assert_eq!(3, summary.registrations_count);
+        assert_eq!(4, summary.values_count);
+        assert_eq!(3, summary.items_len);
+
+        let record = storage.into_record_arc();
+
+        assert_eq!(summary, record.summary());
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, ArcRecordStorage, Cfg>, Cfg>::new(
+                ArcRecordStorage(record),
+            );
+
+        resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(summary, storage.record_summary());
     }
 
     #[test]
-    fn playground() {
-        let mut v = VecDeque::with_capacity(4);
+    fn plays_back_from_in_memory_storage() {
+        use super::sorters::{InMemoryRecordSource, InMemoryRecordStorage, InMemoryRecordWriter};
 
-        v.push_front(1);
-        v.push_front(2);
-        v.push_front(3);
-        v.push_front(4);
+        let records = InMemoryRecordStorage::new();
 
-        log!("{:#?}", v.iter().take(5).collect_vec());
+        let mut storage = MtCircuitResolver::<
+            F,
+            LiveRecordingResolverSorter<F, Cfg, InMemoryRecordWriter<&str>>,
+            Cfg,
+        >::new((
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            records.writer("descendants"),
+        ));
 
-        assert_eq!(4, v.len());
-    }
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
 
-    fn tracks_values_populate<F: SmallField, RS: ResolverSortingMode<F>>(
-        resolver: &mut MtCircuitResolver<F, RS, Cfg>,
-        limit: u64,
-    ) {
-        for i in 0..limit {
-            let a = Place::from_variable(Variable::from_variable_index(i));
+        assert_eq!(1, records.len());
+        assert!(records.source(&"siblings").is_none());
 
-            resolver.set_value(a, F::from_u64_with_reduction(i));
+        let rs = records.source(&"descendants").unwrap();
+
+        for _ in 0..2 {
+            let mut storage = MtCircuitResolver::<
+                F,
+                PlaybackResolverSorter<F, InMemoryRecordSource, Cfg>,
+                Cfg,
+            >::new(rs.clone());
+
+            let dep_var3 = resolves_descendants_populate(&mut storage);
+            storage.wait_till_resolved();
+
+            assert_eq!(
+                F::from_u64_with_reduction(16),
+                storage.get_value_unchecked(dep_var3)
+            );
         }
     }
 
     #[test]
-    fn tracks_values_record_mode() {
-        let limit = 10;
-        let mut storage =
-            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
-                max_variables: 10,
+    fn plays_back_from_file_storage() {
+        use super::sorters::{FileRecordStorage, FileRecordWriter, InMemoryRecordSource};
+
+        let dir = std::env::temp_dir().join(format!("boojum-records-{}", std::process::id()));
+        let records = FileRecordStorage::new(&dir).unwrap();
+
+        let mut storage = MtCircuitResolver::<
+            F,
+            LiveRecordingResolverSorter<F, Cfg, FileRecordWriter<u32>>,
+            Cfg,
+        >::new((
+            CircuitResolverOpts {
+                max_variables: 100,
                 desired_parallelism: 16,
-            });
+                ..Default::default()
+            },
+            records.writer(7),
+        ));
 
-        log!("Storage is ready");
+        resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
 
-        tracks_values_populate(&mut storage, limit);
+        let recorded = storage.retrieve_sequence().clone();
 
-        for i in 0..limit {
-            let a = Place::from_variable(Variable::from_variable_index(i));
-            let v = storage.get_value_unchecked(a);
+        // A fresh storage has nothing cached, so the record is loaded from
+        // the file.
+        let records = FileRecordStorage::new(&dir).unwrap();
 
-            assert_eq!(F::from_u64_with_reduction(i), v);
+        assert!(records.get(&8).is_err());
+        assert_eq!(recorded, *records.get(&7).unwrap());
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, InMemoryRecordSource, Cfg>, Cfg>::new(
+                records.source(&7).unwrap(),
+            );
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn records_identically_with_scheduling_seed() {
+        let record = |seed| {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: 200,
+                    desired_parallelism: 16,
+                    scheduling_seed: Some(seed),
+                    ..Default::default()
+                });
+
+            storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+            // Each registration is delayed on its own place, and all of them
+            // become ready at once when the gap at 1 is filled.
+            for i in 1..=50 {
+                storage.add_resolution(
+                    &[Place::from_variable_index(0), Place::from_variable_index(i)],
+                    &[Place::from_variable_index(i + 50)],
+                    |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[1]),
+                );
+            }
+
+            for i in (1..=50).rev() {
+                storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+            }
+
+            storage.wait_till_resolved();
+            storage.retrieve_sequence().clone()
+        };
+
+        assert_eq!(record(7), record(7));
+        // The ready registrations are internalized in a different order.
+        assert_ne!(record(7), record(8));
+    }
+
+    #[test]
+    fn diffs_records() {
+        let record = |seed| {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: 200,
+                    desired_parallelism: 16,
+                    scheduling_seed: Some(seed),
+                    ..Default::default()
+                });
+
+            storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+            for i in 1..=50 {
+                storage.add_resolution(
+                    &[Place::from_variable_index(0), Place::from_variable_index(i)],
+                    &[Place::from_variable_index(i + 50)],
+                    |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[1]),
+                );
+            }
+
+            for i in (1..=50).rev() {
+                storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+            }
+
+            storage.wait_till_resolved();
+            storage.retrieve_sequence().clone()
+        };
+
+        assert!(record(7).diff(&record(7)).is_empty());
+
+        let diff = record(7).diff(&record(8));
+
+        assert_eq!(None, diff.registrations_count);
+        assert_eq!(None, diff.items_len);
+
+        let (_, fields) = diff.first_item.unwrap();
+
+        assert!(fields
+            .iter()
+            .any(|x| matches!(x, super::sorters::ItemFieldDiff::OrderIx(..))));
+    }
+
+    #[test]
+    fn hashes_record_structure() {
+        let record = |populate: fn(&mut MtCircuitResolver<F, LiveResolverSorter<F, Cfg>, Cfg>)| {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: 100,
+                    desired_parallelism: 16,
+                    ..Default::default()
+                });
+
+            populate(&mut storage);
+            storage.wait_till_resolved();
+            storage.retrieve_sequence().clone()
+        };
+
+        let descendants = record(|x| {
+            resolves_descendants_populate(x);
+        });
+        let siblings = record(|x| {
+            resolves_siblings_populate(x);
+        });
+
+        assert_eq!(
+            descendants.structural_hash(),
+            record(|x| {
+                resolves_descendants_populate(x);
+            })
+            .structural_hash()
+        );
+        assert_ne!(descendants.structural_hash(), siblings.structural_hash());
+    }
+
+    #[test]
+    fn streams_record() {
+        use super::sorters::ResolutionRecordItem;
+
+        struct StreamingRecordWriter {
+            bytes: Arc<Mutex<Vec<u8>>>,
+            chunks: Arc<Mutex<Vec<usize>>>,
+        }
+
+        impl ResolutionRecordWriter for StreamingRecordWriter {
+            fn store(&mut self, _record: &ResolutionRecord) {}
+
+            fn stream_chunk_size(&self) -> Option<usize> {
+                Some(16)
+            }
+
+            fn store_items(&mut self, items: &[ResolutionRecordItem]) {
+                let mut bytes = self.bytes.lock().unwrap();
+
+                items.iter().for_each(|x| x.write_to(&mut *bytes).unwrap());
+
+                self.chunks.lock().unwrap().push(items.len());
+            }
+        }
+
+        let limit = 100;
+        let opts = CircuitResolverOpts {
+            max_variables: limit * 5,
+            desired_parallelism: 16,
+            ..Default::default()
+        };
+
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(opts);
+
+        populate(&mut storage, limit);
+        storage.wait_till_resolved();
+
+        let bytes = Arc::new(Mutex::new(Vec::new()));
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+
+        let mut streamed = MtCircuitResolver::<
+            F,
+            LiveRecordingResolverSorter<F, Cfg, StreamingRecordWriter>,
+            Cfg,
+        >::new((
+            opts,
+            StreamingRecordWriter {
+                bytes: bytes.clone(),
+                chunks: chunks.clone(),
+            },
+        ));
+
+        populate(&mut streamed, limit);
+        streamed.wait_till_resolved();
+
+        // Only the tail stays in memory, the rest went out in chunks.
+        let chunks = chunks.lock().unwrap();
+        assert!(chunks.len() > 1);
+        assert_eq!(
+            streamed.retrieve_sequence().items.len(),
+            *chunks.last().unwrap()
+        );
+
+        let bytes = bytes.lock().unwrap();
+        let mut reader = bytes.as_slice();
+        let mut items = Vec::new();
+
+        while reader.is_empty() == false {
+            items.push(ResolutionRecordItem::read_from(&mut reader).unwrap());
         }
+
+        assert_eq!(storage.retrieve_sequence().items, items);
+
+        // The report covers only the tail held in memory.
+        let report = streamed.parallelism_report();
+        assert_eq!(*chunks.last().unwrap(), report.registrations);
+        assert!(report.registrations < streamed.retrieve_sequence().registrations_count);
     }
 
     #[test]
@@ -476,6 +6571,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 10,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         tracks_values_populate(&mut storage, limit);
@@ -521,8 +6617,52 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let (init_var, dep_var) = resolves_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            storage.get_value_unchecked(init_var),
+            storage.get_value_unchecked(dep_var)
+        );
+    }
+
+    #[test]
+    fn resolves_empty() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    fn resolves_playback_mode() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
             });
 
+        let (_, _) = resolves_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        let rs = TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        };
+
+        println!("\n----- Recording finished -----\n");
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
+
         let (init_var, dep_var) = resolves_populate(&mut storage);
 
         storage.wait_till_resolved();
@@ -533,25 +6673,50 @@ mod test {
         );
     }
 
-    #[test]
-    fn resolves_empty() {
-        let mut storage =
-            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
-                max_variables: 100,
-                desired_parallelism: 16,
-            });
+    #[test]
+    fn drops_subscriptions_on_reset() {
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(
+                record_descendants(),
+            );
+
+        let dep_var3 = Place::from_variable_index(3);
+        let (tx, stale) = std::sync::mpsc::channel();
+
+        // Cancelled before the place is resolved, so the subscription is left
+        // over.
+        storage.notify_on_resolve(dep_var3, tx);
+        storage.cancel();
+
+        assert_eq!(Err(std::sync::mpsc::TryRecvError::Empty), stale.try_recv());
+
+        storage.reset();
+
+        assert_eq!(
+            Err(std::sync::mpsc::TryRecvError::Disconnected),
+            stale.try_recv()
+        );
+
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        storage.notify_on_resolve(dep_var3, tx);
+
+        resolves_descendants_populate(&mut storage);
         storage.wait_till_resolved();
+
+        rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
     }
 
     #[test]
-    fn resolves_playback_mode() {
+    fn resolves_playback_mode_after_reset() {
         let mut storage =
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
-        let (_, _) = resolves_populate(&mut storage);
+        resolves_descendants_populate(&mut storage);
 
         storage.wait_till_resolved();
 
@@ -559,19 +6724,38 @@ mod test {
             record: Rc::new(storage.retrieve_sequence().clone()),
         };
 
-        println!("\n----- Recording finished -----\n");
-
         let mut storage =
             MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
 
-        let (init_var, dep_var) = resolves_populate(&mut storage);
+        for (i, seed) in [2, 3].into_iter().enumerate() {
+            if i > 0 {
+                storage.reset();
+            }
 
-        storage.wait_till_resolved();
+            let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut x = ins[0];
 
-        assert_eq!(
-            storage.get_value_unchecked(init_var),
-            storage.get_value_unchecked(dep_var)
-        );
+                outs.push(*x.double());
+            };
+
+            let init_var = Place::from_variable(Variable::from_variable_index(0));
+            let dep_var1 = Place::from_variable(Variable::from_variable_index(1));
+            let dep_var2 = Place::from_variable(Variable::from_variable_index(2));
+            let dep_var3 = Place::from_variable(Variable::from_variable_index(3));
+
+            storage.set_value(init_var, F::from_u64_with_reduction(seed));
+
+            storage.add_resolution(&[init_var], &[dep_var1], res_fn);
+            storage.add_resolution(&[dep_var1], &[dep_var2], res_fn);
+            storage.add_resolution(&[dep_var2], &[dep_var3], res_fn);
+
+            storage.wait_till_resolved();
+
+            assert_eq!(
+                F::from_u64_with_reduction(seed * 8),
+                storage.get_value_unchecked(dep_var3)
+            );
+        }
     }
 
     fn resolves_siblings_populate<F: SmallField, RS: ResolverSortingMode<F>>(
@@ -603,6 +6787,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let ((init_var1, dep_var1), (init_var2, dep_var2)) =
@@ -626,6 +6811,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         resolves_siblings_populate(&mut storage);
@@ -683,80 +6869,542 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 2,
+                ..Default::default()
+            });
+
+        let dep_var3 = resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+    }
+
+    #[test]
+    fn resolves_descendants_playback_mode() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 2,
+                ..Default::default()
             });
 
+        resolves_descendants_populate(&mut storage);
+
+        storage.wait_till_resolved();
+
+        let rs = TestRecordStorage {
+            record: Rc::new(storage.retrieve_sequence().clone()),
+        };
+
+        let mut storage =
+            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
+
         let dep_var3 = resolves_descendants_populate(&mut storage);
 
-        storage.wait_till_resolved();
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(16),
+            storage.get_value_unchecked(dep_var3)
+        );
+    }
+
+    #[test]
+    fn resolves_with_context() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let init_var = Place::from_variable(Variable::from_variable_index(0));
+        let dep_var = Place::from_variable(Variable::from_variable_index(1));
+
+        storage.set_value(init_var, F::from_u64_with_reduction(123));
+
+        let ctx_var = F::from_u64_with_reduction(321);
+
+        storage.add_resolution(
+            &[init_var],
+            &[dep_var],
+            move |ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut result = ins[0];
+
+                Field::add_assign(&mut result, &ctx_var);
+
+                outs.push(result);
+            },
+        );
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(444),
+            storage.get_value_unchecked(dep_var)
+        );
+    }
+
+    #[test]
+    fn resolves_into_external_storage() {
+        use crate::dag::primitives::Metadata;
+
+        let storage = (0..100)
+            .map(|_| std::cell::UnsafeCell::new((F::ZERO, Metadata::default())))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        let ptr = storage.as_ptr();
+
+        let mut resolver =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::with_value_storage(
+                CircuitResolverOpts {
+                    max_variables: 100,
+                    desired_parallelism: 16,
+                    ..Default::default()
+                },
+                storage,
+            );
+
+        resolves_descendants_populate(&mut resolver);
+
+        let storage = resolver.into_value_storage();
+
+        assert_eq!(ptr, storage.as_ptr());
+
+        let (value, md) = unsafe { &*storage[3].get() };
+
+        assert!(md.is_resolved());
+        assert_eq!(F::from_u64_with_reduction(16), *value);
+    }
+
+    #[cfg(feature = "mmap-values")]
+    #[test]
+    fn resolves_into_mmap_store() {
+        use crate::dag::MmapValueStore;
+
+        let limit = 100;
+        let path = std::env::temp_dir().join(format!("boojum-values-{}", std::process::id()));
+        let store = MmapValueStore::create(&path, limit + 1).unwrap();
+
+        let mut resolver =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::with_value_store(
+                CircuitResolverOpts {
+                    max_variables: limit + 1,
+                    desired_parallelism: 16,
+                    ..Default::default()
+                },
+                store,
+            );
+
+        resolver.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+
+        for i in 0..limit as u64 {
+            resolver.add_resolution(
+                &[Place::from_variable_index(i)],
+                &[Place::from_variable_index(i + 1)],
+                |ins, outs| {
+                    let mut x = ins[0];
+                    outs.push(*x.add_assign(&F::ONE));
+                },
+            );
+        }
+
+        resolver.wait_till_resolved();
+
+        for i in 0..=limit as u64 {
+            assert_eq!(
+                Ok(F::from_u64_with_reduction(i + 1)),
+                resolver.get_value(Place::from_variable_index(i))
+            );
+        }
+
+        let storage = resolver.into_value_storage();
+
+        let (value, md) = unsafe { &*storage[limit].get() };
+
+        assert!(md.is_resolved());
+        assert_eq!(F::from_u64_with_reduction(limit as u64 + 1), *value);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "mmap-values")]
+    #[test]
+    fn rejects_oversized_mmap_store() {
+        use crate::dag::guide::GuideLoc;
+        use crate::dag::MmapValueStore;
+
+        let path = std::env::temp_dir().join(format!("boojum-oversized-{}", std::process::id()));
+
+        let e = MmapValueStore::<F, GuideLoc>::create(&path, usize::MAX)
+            .err()
+            .unwrap();
+
+        assert_eq!(std::io::ErrorKind::InvalidInput, e.kind());
+        assert!(path.exists() == false);
+    }
+
+    #[test]
+    fn reports_awaiter_hole() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        assert!(matches!(
+            storage.try_get_awaiter([Place::from_variable_index(0)]),
+            Err(CircuitResolverError::Hole {
+                max_tracked: None,
+                ..
+            })
+        ));
+
+        resolves_descendants_populate(&mut storage);
+
+        let requested = Place::from_variable_index(5);
+
+        match storage.try_get_awaiter([Place::from_variable_index(1), requested]) {
+            Err(CircuitResolverError::Hole {
+                requested: r,
+                max_tracked: Some(m),
+            }) => {
+                assert_eq!(requested, r);
+                assert_eq!(Place::from_variable_index(3), m);
+            }
+            _ => panic!("Expected a hole."),
+        }
+
+        storage
+            .try_get_awaiter([Place::from_variable_index(3)])
+            .unwrap()
+            .wait();
+    }
+
+    #[test]
+    fn resolves_with_cross_circuit_inputs() {
+        let var = Place::from_variable_index;
+
+        let mut first =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        first.set_value(var(0), F::from_u64_with_reduction(5));
+        first.add_resolution(&[var(0)], &[var(1)], |ins, outs| {
+            let mut x = ins[0];
+            x.double();
+            outs.push(x);
+        });
+        first.wait_till_resolved();
+
+        let first = Arc::new(first);
+
+        let mut second =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        second.set_value(var(0), F::from_u64_with_reduction(3));
+        second.add_resolution_cross(
+            &first,
+            &[var(0), var(1)],
+            &[var(0)],
+            &[var(1)],
+            |external: &[F], ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut x = ins[0];
+                x.add_assign(&external[0]);
+                x.add_assign(&external[1]);
+                outs.push(x);
+            },
+        );
+        second.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(18),
+            second.get_value_unchecked(var(1))
+        );
+
+        drop(second);
+        assert_eq!(1, Arc::strong_count(&first));
+    }
+
+    #[test]
+    fn releases_resolvers_keeping_values() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let ctx = Arc::new(3u64);
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(2));
+        storage.add_resolution_with_ctx(
+            &ctx,
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(1)],
+            |ctx: &u64, ins: &[F], outs: &mut DstBuffer<F>| {
+                let mut result = ins[0];
+                Field::mul_assign(&mut result, &F::from_u64_with_reduction(*ctx));
+                outs.push(result);
+            },
+        );
+
+        storage.wait_till_resolved();
+        storage.release_resolvers();
+
+        assert_eq!(1, Arc::strong_count(&ctx));
+        assert_eq!(
+            F::from_u64_with_reduction(6),
+            storage.get_value_unchecked(Place::from_variable_index(1))
+        );
+
+        let added = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            storage.add_resolution(
+                &[Place::from_variable_index(1)],
+                &[Place::from_variable_index(2)],
+                |ins: &[F], outs: &mut DstBuffer<F>| outs.push(ins[0]),
+            );
+        }));
+
+        assert!(added.is_err());
+    }
+
+    #[test]
+    fn counts_resolutions_by_arity() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        populate(&mut storage, 10);
+
+        let histogram = storage.arity_histogram();
+
+        assert_eq!(
+            vec![(2, 10), (3, 10)],
+            histogram.inputs.iter().map(|(k, v)| (*k, *v)).collect_vec()
+        );
+        assert_eq!(
+            vec![(1, 20)],
+            histogram
+                .outputs
+                .iter()
+                .map(|(k, v)| (*k, *v))
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn reports_resolution_timing() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        resolves_descendants_populate(&mut storage);
+
+        let timing = storage.wait_till_resolved_timed();
+
+        assert_eq!(timing.total, timing.registration + timing.resolution);
+        assert!(timing.registration > std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn resolves_keyed_outputs() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let init_var = Place::from_variable_index(0);
+        let dep_var1 = Place::from_variable_index(1);
+        let dep_var2 = Place::from_variable_index(2);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(3));
+        storage.add_resolution_keyed(&[init_var], &[dep_var1, dep_var2], |ins, outs| {
+            let mut x = ins[0];
+
+            outs.set(1, *x.double());
+            outs.set(0, ins[0]);
+        });
+
+        storage.wait_till_resolved();
+
+        assert_eq!(
+            F::from_u64_with_reduction(3),
+            storage.get_value_unchecked(dep_var1)
+        );
+        assert_eq!(
+            F::from_u64_with_reduction(6),
+            storage.get_value_unchecked(dep_var2)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Output 1 wasn't set by the resolution.")]
+    fn panics_on_unset_keyed_output() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        let init_var = Place::from_variable_index(0);
+
+        storage.set_value(init_var, F::from_u64_with_reduction(3));
+        storage.add_resolution_keyed(
+            &[init_var],
+            &[Place::from_variable_index(1), Place::from_variable_index(2)],
+            |ins, outs| outs.set(0, ins[0]),
+        );
+
+        storage.wait_till_resolved();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The outputs of registration 1 failed the assertion.")]
+    fn panics_on_failed_resolution_assertion() {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(
+            CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            },
+            0,
+        );
+
+        let var = Place::from_variable_index;
+        let nonzero = |outs: &[F]| outs.iter().all(|x| x.is_zero() == false);
 
-        assert_eq!(
-            F::from_u64_with_reduction(16),
-            storage.get_value_unchecked(dep_var3)
+        storage.set_value(var(0), F::from_u64_with_reduction(3));
+        storage.add_resolution_with_assert(
+            &[var(0)],
+            &[var(1)],
+            |ins, outs| outs.push(ins[0]),
+            nonzero,
+        );
+        storage.add_resolution_with_assert(
+            &[var(1)],
+            &[var(2)],
+            |ins, outs| {
+                let mut x = ins[0];
+                x.sub_assign(&ins[0]);
+                outs.push(x);
+            },
+            nonzero,
         );
+
+        storage.wait_till_resolved();
     }
 
     #[test]
-    fn resolves_descendants_playback_mode() {
+    fn resolves_with_scratch_space() {
+        let limit = 100;
+
         let mut storage =
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
-                max_variables: 100,
-                desired_parallelism: 2,
+                max_variables: limit * 2,
+                desired_parallelism: 16,
+                ..Default::default()
             });
 
-        resolves_descendants_populate(&mut storage);
+        for i in 0..limit as u64 {
+            storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+        }
 
-        storage.wait_till_resolved();
+        // Sums the input with all the previous ones, using the scratch space
+        // for the intermediate values.
+        for i in 0..limit as u64 {
+            let inputs = (0..=i).map(Place::from_variable_index).collect_vec();
 
-        let rs = TestRecordStorage {
-            record: Rc::new(storage.retrieve_sequence().clone()),
-        };
+            storage.add_resolution_scratch(
+                &inputs,
+                &[Place::from_variable_index(limit as u64 + i)],
+                |ins: &[F], outs: &mut DstBuffer<F>, scratch: &mut ScratchSpace<F>| {
+                    assert!(scratch.values.is_empty());
 
-        let mut storage =
-            MtCircuitResolver::<F, PlaybackResolverSorter<F, TestRecordStorage, Cfg>, Cfg>::new(rs);
+                    scratch.values.extend_from_slice(ins);
 
-        let dep_var3 = resolves_descendants_populate(&mut storage);
+                    let mut sum = F::ZERO;
+                    scratch.values.iter().for_each(|x| {
+                        sum.add_assign(x);
+                    });
+
+                    outs.push(sum);
+                },
+            );
+        }
 
         storage.wait_till_resolved();
 
-        assert_eq!(
-            F::from_u64_with_reduction(16),
-            storage.get_value_unchecked(dep_var3)
-        );
+        for i in 0..limit as u64 {
+            assert_eq!(
+                F::from_u64_with_reduction(i * (i + 1) / 2),
+                storage.get_value_unchecked(Place::from_variable_index(limit as u64 + i))
+            );
+        }
     }
 
     #[test]
-    fn resolves_with_context() {
+    fn resolves_with_shared_context() {
         let mut storage =
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
-        let init_var = Place::from_variable(Variable::from_variable_index(0));
-        let dep_var = Place::from_variable(Variable::from_variable_index(1));
-
-        storage.set_value(init_var, F::from_u64_with_reduction(123));
-
-        let ctx_var = F::from_u64_with_reduction(321);
-
-        storage.add_resolution(
-            &[init_var],
-            &[dep_var],
-            move |ins: &[F], outs: &mut DstBuffer<F>| {
-                let mut result = ins[0];
-
-                Field::add_assign(&mut result, &ctx_var);
+        let table = Arc::new((0..10).map(|x| x * x).collect_vec());
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(0));
+
+        for i in 1..10 {
+            storage.add_resolution_with_ctx(
+                &table,
+                &[Place::from_variable_index(i - 1)],
+                &[Place::from_variable_index(i)],
+                move |table: &Vec<u64>, ins: &[F], outs: &mut DstBuffer<F>| {
+                    let mut result = ins[0];
+                    Field::add_assign(&mut result, &F::from_u64_with_reduction(table[i as usize]));
+                    outs.push(result);
+                },
+            );
+        }
 
-                outs.push(result);
-            },
-        );
+        // A single clone is held for all the resolutions.
+        assert_eq!(2, Arc::strong_count(&table));
 
         storage.wait_till_resolved();
 
         assert_eq!(
-            F::from_u64_with_reduction(444),
-            storage.get_value_unchecked(dep_var)
+            F::from_u64_with_reduction(285),
+            storage.get_value_unchecked(Place::from_variable_index(9))
         );
+
+        drop(storage);
+
+        assert_eq!(1, Arc::strong_count(&table));
     }
 
     #[test]
@@ -765,6 +7413,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let init_var = Place::from_variable(Variable::from_variable_index(0));
@@ -814,6 +7463,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: limit * 5,
                 desired_parallelism: 2048,
+                ..Default::default()
             });
 
         populate(&mut storage, limit);
@@ -836,6 +7486,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn cloned_awaiter_unblocks_all_threads() {
+        let limit = 1 << 10;
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit * 5,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        populate(&mut storage, limit);
+
+        let place = Place::from_variable(Variable::from_variable_index(limit as u64 * 3));
+
+        let awaiter = storage.get_awaiter([place]);
+        let woken = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let awaiter = awaiter.clone();
+                let woken = &woken;
+
+                s.spawn(move || {
+                    awaiter.wait();
+                    woken.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                });
+            }
+        });
+
+        drop(awaiter);
+
+        assert_eq!(4, woken.load(std::sync::atomic::Ordering::Relaxed));
+        assert!(storage.try_get_value(place).is_some());
+    }
+
     #[test]
     fn awaiter_returns_for_resolved_value_playback_mode() {
         awaiter_returns_for_resolved_value_playback_mode_impl(15, 2);
@@ -852,6 +7537,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: limit * 5,
                 desired_parallelism,
+                ..Default::default()
             });
 
         populate(&mut storage, limit);
@@ -891,6 +7577,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -920,6 +7607,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -965,6 +7653,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -991,6 +7680,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let mut notch = std::time::Instant::now();
@@ -1021,6 +7711,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1060,6 +7751,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         storage.set_value(init_var, F::from_u64_with_reduction(123));
@@ -1098,6 +7790,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1129,6 +7822,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         storage.set_value(init_var, F::from_u64_with_reduction(123));
@@ -1156,6 +7850,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1189,6 +7884,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         storage.set_value(init_var, F::from_u64_with_reduction(123));
@@ -1217,6 +7913,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1243,6 +7940,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1282,6 +7980,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1309,6 +8008,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |_: &[F], _: &mut DstBuffer<F>| {
@@ -1334,6 +8034,33 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let res_fn = |_: &[F], _: &mut DstBuffer<F>| {
+            panic!("This is a test panic");
+        };
+
+        let init_var = Place::from_variable(Variable::from_variable_index(0));
+        let dep_var = Place::from_variable(Variable::from_variable_index(1));
+
+        storage.set_value(init_var, F::from_u64_with_reduction(123));
+
+        storage.add_resolution(&[init_var], &[dep_var], res_fn);
+
+        storage.get_awaiter([dep_var]).wait();
+    }
+
+    // The panic payload is consumed by the first awaiter, the following ones
+    // must panic as well instead of returning an unresolved value.
+    #[test]
+    #[should_panic(expected = "Resolution window panicked")]
+    fn panic_in_resolution_function_is_propagated_through_every_awaiter() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |_: &[F], _: &mut DstBuffer<F>| {
@@ -1347,15 +8074,149 @@ mod test {
 
         storage.add_resolution(&[init_var], &[dep_var], res_fn);
 
+        let first = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            storage.get_awaiter([dep_var]).wait();
+        }));
+
+        assert!(first.is_err());
+
         storage.get_awaiter([dep_var]).wait();
     }
 
+    fn block_on<Fut: Future>(fut: Fut) -> Fut::Output {
+        struct ThreadWaker(std::thread::Thread);
+
+        impl std::task::Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = std::pin::pin!(fut);
+
+        loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(x) => return x,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn completes_as_future() {
+        let limit = 100;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit * 5,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        populate(&mut storage, limit);
+
+        block_on(storage.completion_future()).unwrap();
+
+        assert_eq!(limit as u64 * 2, storage.resolved_count());
+        assert_eq!(None, storage.unresolved_places().next());
+
+        // Already completed.
+        block_on(storage.completion_future()).unwrap();
+    }
+
+    #[test]
+    fn completion_future_reports_panic() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let init_var = Place::from_variable(Variable::from_variable_index(0));
+        let dep_var = Place::from_variable(Variable::from_variable_index(1));
+
+        storage.set_value(init_var, F::from_u64_with_reduction(123));
+        storage.add_resolution(&[init_var], &[dep_var], |_: &[F], _: &mut DstBuffer<F>| {
+            panic!("This is a test panic")
+        });
+
+        let e = block_on(storage.completion_future()).unwrap_err();
+
+        assert_eq!(
+            "Resolution window panicked: This is a test panic",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn dropped_completion_future_writes_record() {
+        let limit = 100;
+
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: limit * 5,
+                desired_parallelism: 16,
+                verify_playback: true,
+                ..Default::default()
+            });
+
+        populate(&mut storage, limit);
+
+        drop(storage.completion_future());
+
+        assert_eq!(limit as u64 * 2, storage.resolved_count());
+
+        let record = storage.retrieve_sequence();
+        assert_eq!(
+            Some(record.values_count),
+            record.values.as_ref().map(|x| x.len())
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Resolution 1 produced 0 outputs, but 1 were declared.")]
+    fn missing_output_is_propagated() {
+        let mut storage =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                max_variables: 100,
+                desired_parallelism: 16,
+                ..Default::default()
+            });
+
+        let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
+            if ins[0].is_zero() == false {
+                outs.push(ins[0]);
+            }
+        };
+
+        storage.set_value(Place::from_variable_index(0), F::from_u64_with_reduction(1));
+        storage.set_value(Place::from_variable_index(1), F::ZERO);
+
+        storage.add_resolution(
+            &[Place::from_variable_index(0)],
+            &[Place::from_variable_index(2)],
+            res_fn,
+        );
+        storage.add_resolution(
+            &[Place::from_variable_index(1)],
+            &[Place::from_variable_index(3)],
+            res_fn,
+        );
+
+        storage.wait_till_resolved();
+    }
+
     #[test]
     fn non_chronological_resolution_record_mode() {
         let mut storage =
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let res_fn = |ins: &[F], outs: &mut DstBuffer<F>| {
@@ -1403,6 +8264,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: 100,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         storage.set_value(var_4, F::from_u64_with_reduction(7));
@@ -1481,6 +8343,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: limit * 5,
                 desired_parallelism: 32,
+                ..Default::default()
             });
 
         correctness_simple_linear_populate(&mut storage, limit);
@@ -1526,6 +8389,7 @@ mod test {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: limit * 5,
                 desired_parallelism: 32,
+                ..Default::default()
             });
 
         correctness_simple_linear_populate(&mut storage, limit);
@@ -1573,6 +8437,55 @@ mod test {
         }
     }
 
+    #[test]
+    fn correctness_simple_linear_inline_mode() {
+        let limit = 1 << 10;
+        let opts = CircuitResolverOpts {
+            max_variables: limit * 5,
+            desired_parallelism: 32,
+            ..Default::default()
+        };
+        let places = || Place::range(0, limit as u64 * 2);
+
+        let mut threaded =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(opts, 3);
+
+        correctness_simple_linear_populate(&mut threaded, limit);
+        threaded.wait_till_resolved();
+
+        let mut inline =
+            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new_with_threads(opts, 0);
+
+        correctness_simple_linear_populate(&mut inline, limit);
+
+        // Awaiting resolves everything registered so far on this thread.
+        let last = Place::from_variable_index(limit as u64 * 2 - 1);
+        inline.get_awaiter([last]).wait();
+        assert!(inline.try_get_value(last).is_some());
+
+        inline.wait_till_resolved();
+
+        let rs = TestRecordStorage {
+            record: Rc::new(inline.retrieve_sequence().clone()),
+        };
+
+        let mut playback = MtCircuitResolver::<
+            F,
+            PlaybackResolverSorter<F, TestRecordStorage, Cfg>,
+            Cfg,
+        >::new_with_threads(rs, 0);
+
+        correctness_simple_linear_populate(&mut playback, limit);
+        playback.wait_till_resolved();
+
+        for x in places() {
+            let exp = threaded.get_value_unchecked(x);
+
+            assert_eq!(exp, inline.get_value_unchecked(x), "{:?}", x);
+            assert_eq!(exp, playback.get_value_unchecked(x), "{:?}", x);
+        }
+    }
+
     fn populate<RS: ResolverSortingMode<F>>(
         storage: &mut MtCircuitResolver<F, RS, Cfg>,
         limit: usize,
@@ -1631,16 +8544,192 @@ mod benches {
     use crate::{
         config::{DoPerformRuntimeAsserts, Resolver},
         cs::{traits::cs::DstBuffer, Place, Variable},
-        dag::{
-            resolvers::mt::sorters::sorter_live::LiveResolverSorter, Awaiter as _,
-            CircuitResolverOpts,
-        },
+        dag::{resolvers::mt::sorters::sorter_live::LiveResolverSorter, CircuitResolverOpts},
         field::{goldilocks::GoldilocksField, Field},
         log,
     };
     type F = GoldilocksField;
     type Cfg = Resolver<DoPerformRuntimeAsserts>;
 
+    #[test]
+    #[ignore = ""]
+    fn idle_policy_bench() {
+        use crate::dag::IdlePolicy;
+
+        // User and system time of the process, in clock ticks.
+        fn cpu_ticks() -> u64 {
+            std::fs::read_to_string("/proc/self/stat")
+                .unwrap()
+                .rsplit(')')
+                .next()
+                .unwrap()
+                .split_whitespace()
+                .skip(11)
+                .take(2)
+                .map(|x| x.parse::<u64>().unwrap())
+                .sum()
+        }
+
+        for policy in [
+            IdlePolicy::Spin,
+            IdlePolicy::Yield,
+            IdlePolicy::Sleep(std::time::Duration::from_micros(100)),
+            IdlePolicy::Sleep(std::time::Duration::from_millis(1)),
+        ] {
+            let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(
+                CircuitResolverOpts::builder(1 << 10)
+                    .window_idle_policy(policy)
+                    .build(),
+            );
+
+            storage.set_value(Place::from_variable_index(0), F::ONE);
+
+            let ticks = cpu_ticks();
+            let now = std::time::Instant::now();
+
+            std::thread::sleep(std::time::Duration::from_secs(2));
+
+            log!(
+                "{:?}: {} CPU ticks over {:?} idle",
+                policy,
+                cpu_ticks() - ticks,
+                now.elapsed()
+            );
+
+            storage.wait_till_resolved();
+        }
+    }
+
+    #[test]
+    #[ignore = ""]
+    fn reserve_bench() {
+        let len = 1 << 16;
+
+        for reserve in [false, true] {
+            // Registrations without outputs outgrow the order preallocated
+            // for `max_variables`.
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: 1 << 10,
+                    desired_parallelism: 2048,
+                    ..Default::default()
+                });
+
+            if reserve {
+                storage.reserve(len);
+            }
+
+            let now = std::time::Instant::now();
+
+            storage.set_value(Place::from_variable_index(0), F::ONE);
+
+            for _ in 0..len {
+                storage.add_resolution(
+                    &[Place::from_variable_index(0)],
+                    &[],
+                    |_: &[F], _: &mut DstBuffer<F>| {},
+                );
+            }
+
+            log!("Reserved {}: {:?}", reserve, now.elapsed());
+
+            storage.wait_till_resolved();
+        }
+    }
+
+    #[test]
+    #[ignore = ""]
+    fn declared_prefix_bench() {
+        let len = 1 << 20;
+
+        for declare in [false, true] {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: len + 1,
+                    desired_parallelism: 2048,
+                    ..Default::default()
+                });
+
+            // Keeps a registration delayed through the loading.
+            storage.add_resolution(
+                &[Place::from_variable_index(len as u64)],
+                &[],
+                |_: &[F], _: &mut DstBuffer<F>| {},
+            );
+
+            let now = std::time::Instant::now();
+
+            if declare {
+                storage.declare_tracked_prefix(Place::from_variable_index(len as u64 - 1));
+            }
+
+            for i in 0..len as u64 {
+                storage.set_value(Place::from_variable_index(i), F::from_u64_with_reduction(i));
+            }
+
+            log!("Declared {}: {:?}", declare, now.elapsed());
+
+            storage.set_value(Place::from_variable_index(len as u64), F::ONE);
+            storage.wait_till_resolved();
+        }
+    }
+
+    #[test]
+    #[ignore = ""]
+    fn vectorized_bench() {
+        let len = 1 << 20;
+
+        let run = |k: u64| {
+            (0..len as u64)
+                .map(|i| Place::from_variable_index(k * len as u64 + i))
+                .collect::<Vec<_>>()
+        };
+
+        let (lhs, rhs, out) = (run(0), run(1), run(2));
+
+        let new_storage = || {
+            let mut storage =
+                MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+                    max_variables: len * 3,
+                    desired_parallelism: 2048,
+                    ..Default::default()
+                });
+
+            for i in 0..len {
+                storage.set_value(lhs[i], F::from_u64_with_reduction(i as u64));
+                storage.set_value(rhs[i], F::from_u64_with_reduction(i as u64 + 1));
+            }
+
+            storage
+        };
+
+        let mut storage = new_storage();
+        let now = std::time::Instant::now();
+
+        for i in 0..len {
+            storage.add_resolution(
+                &[lhs[i], rhs[i]],
+                &[out[i]],
+                |ins: &[F], outs: &mut DstBuffer<F>| {
+                    let mut x = ins[0];
+                    outs.push(*x.add_assign(&ins[1]));
+                },
+            );
+        }
+
+        storage.wait_till_resolved();
+
+        log!("Per element: {:?}", now.elapsed());
+
+        let mut storage = new_storage();
+        let now = std::time::Instant::now();
+
+        storage.add_vectorized_resolution(ElementwiseOp::Add, &lhs, &rhs, &out);
+        storage.wait_till_resolved();
+
+        log!("Vectorized: {:?}", now.elapsed());
+    }
+
     #[test]
     #[ignore = ""]
     fn synth_bench_m_1() {
@@ -1659,11 +8748,43 @@ mod benches {
     #[test]
     fn synth_bench_1() {
         let limit = 1 << 25;
-        let mut storage =
-            MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
+
+        synth_bench_1_run(
+            limit,
+            CircuitResolverOpts {
                 max_variables: limit * 5,
                 desired_parallelism: 2048,
-            });
+                ..Default::default()
+            },
+        )
+    }
+
+    #[cfg(feature = "numa")]
+    #[test]
+    #[ignore = ""]
+    fn numa_first_touch_bench() {
+        // A smaller `synth_bench_1`, whose values alone take 8 GB.
+        let limit = 1 << 20;
+
+        for numa_first_touch in [false, true] {
+            let now = std::time::Instant::now();
+
+            synth_bench_1_run(
+                limit,
+                CircuitResolverOpts {
+                    max_variables: limit * 5,
+                    desired_parallelism: 2048,
+                    numa_first_touch,
+                    ..Default::default()
+                },
+            );
+
+            log!("First touch {}: {:?}", numa_first_touch, now.elapsed());
+        }
+    }
+
+    fn synth_bench_1_run(limit: usize, opts: CircuitResolverOpts) {
+        let mut storage = MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(opts);
 
         log!("Storage is ready");
 
@@ -1736,6 +8857,7 @@ mod benches {
             MtCircuitResolver::<F, LiveResolverSorter<F, Cfg>, Cfg>::new(CircuitResolverOpts {
                 max_variables: limit + 1,
                 desired_parallelism: 16,
+                ..Default::default()
             });
 
         let init_var = Place::from_variable(Variable::from_variable_index(0));
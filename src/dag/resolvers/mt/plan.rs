@@ -0,0 +1,136 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    cs::{Place, VariableType, Witness},
+    dag::guide::RegistrationNum,
+};
+
+/// A resolver in the execution order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlanStep {
+    pub order_ix: u64,
+    pub registration: RegistrationNum,
+    pub outputs: Vec<Place>,
+}
+
+/// The execution order of the resolvers in terms of registrations and places,
+/// see `MtCircuitResolver::export_execution_plan`.
+///
+/// Serializes to text, with one step per line:
+/// `<order ix> <registration>: <outputs>`, where each output is written as
+/// `v<index>` for variables and `w<index>` for witnesses. The text is meant to
+/// be diffed between runs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionPlan {
+    pub steps: Vec<PlanStep>,
+}
+
+impl Display for ExecutionPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            write!(f, "{} {}:", step.order_ix, step.registration)?;
+
+            for place in &step.outputs {
+                match place.get_type() {
+                    VariableType::CopyableVariable => write!(f, " v{}", place.as_any_index())?,
+                    VariableType::Witness => write!(f, " w{}", place.as_any_index())?,
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePlanError {
+    pub line: usize,
+}
+
+impl Display for ParsePlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Malformed execution plan step at line {}.",
+            self.line + 1
+        )
+    }
+}
+
+impl std::error::Error for ParsePlanError {}
+
+impl FromStr for ExecutionPlan {
+    type Err = ParsePlanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse_step(line: &str) -> Option<PlanStep> {
+            let (head, outputs) = line.split_once(':')?;
+            let (order_ix, registration) = head.split_once(' ')?;
+
+            let outputs = outputs
+                .split_whitespace()
+                .map(|x| {
+                    if let Some(ix) = x.strip_prefix('v') {
+                        ix.parse().ok().map(Place::from_variable_index)
+                    } else if let Some(ix) = x.strip_prefix('w') {
+                        ix.parse()
+                            .ok()
+                            .map(|x| Place::from_witness(Witness::from_witness_index(x)))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(PlanStep {
+                order_ix: order_ix.parse().ok()?,
+                registration: registration.parse().ok()?,
+                outputs,
+            })
+        }
+
+        s.lines()
+            .enumerate()
+            .filter(|(_, x)| x.is_empty() == false)
+            .map(|(i, x)| parse_step(x).ok_or(ParsePlanError { line: i }))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|steps| Self { steps })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plan_roundtrips_through_text() {
+        let plan = ExecutionPlan {
+            steps: vec![
+                PlanStep {
+                    order_ix: 0,
+                    registration: 0,
+                    outputs: vec![Place::from_variable_index(2)],
+                },
+                PlanStep {
+                    order_ix: 1,
+                    registration: 3,
+                    outputs: vec![
+                        Place::from_variable_index(4),
+                        Place::from_witness(Witness::from_witness_index(1)),
+                    ],
+                },
+            ],
+        };
+
+        let text = plan.to_string();
+
+        assert_eq!("0 0: v2\n1 3: v4 w1\n", text);
+        assert_eq!(plan, text.parse().unwrap());
+        assert_eq!(
+            Err(ParsePlanError { line: 1 }),
+            "0 0: v2\n1 3: x4\n".parse::<ExecutionPlan>()
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use crate::{cs::Place, dag::guide::RegistrationNum};
+
+/// Aggregated durations of resolver invocations. Collected only with the
+/// `profile-resolvers` feature.
+#[derive(Debug, Clone, Default)]
+pub struct TimingHistogram {
+    /// Invocation counts, where bucket `i` holds the invocations that took
+    /// `[2^(i-1), 2^i)` microseconds. Bucket 0 holds the sub-microsecond ones.
+    pub buckets: Vec<u64>,
+    /// The slowest invocations as `(registration number, duration)`, slowest
+    /// first.
+    pub slowest: Vec<(u32, Duration)>,
+    pub total_invocations: u64,
+    pub total_time: Duration,
+}
+
+impl TimingHistogram {
+    /// The number of slowest invocations that are retained.
+    pub const TOP_N: usize = 16;
+
+    pub(crate) fn new(timings: &[(RegistrationNum, Duration)]) -> Self {
+        let mut buckets = Vec::new();
+
+        for (_, duration) in timings {
+            let ix = Self::bucket_ix(*duration);
+
+            if buckets.len() <= ix {
+                buckets.resize(ix + 1, 0);
+            }
+
+            buckets[ix] += 1;
+        }
+
+        let mut slowest = timings.to_vec();
+        slowest.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        slowest.truncate(Self::TOP_N);
+
+        Self {
+            buckets,
+            slowest,
+            total_invocations: timings.len() as u64,
+            total_time: timings.iter().map(|(_, x)| *x).sum(),
+        }
+    }
+
+    /// The exclusive upper bound of the durations in the bucket.
+    pub fn bucket_bound(ix: usize) -> Duration {
+        Duration::from_micros(1 << ix)
+    }
+
+    fn bucket_ix(duration: Duration) -> usize {
+        (u128::BITS - duration.as_micros().leading_zeros()) as usize
+    }
+}
+
+/// The slowest resolver invocation, see `MtCircuitResolver::slowest_resolver`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowestInfo {
+    pub registration: RegistrationNum,
+    pub outputs: Vec<Place>,
+    pub duration: Duration,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buckets_and_ranks_timings() {
+        let timings = [
+            (0, Duration::from_nanos(10)),
+            (1, Duration::from_micros(1)),
+            (2, Duration::from_micros(3)),
+            (3, Duration::from_micros(2)),
+            (4, Duration::from_micros(1000)),
+        ];
+
+        let histogram = TimingHistogram::new(&timings);
+
+        assert_eq!(vec![1, 1, 2, 0, 0, 0, 0, 0, 0, 0, 1], histogram.buckets);
+        assert_eq!(5, histogram.total_invocations);
+        assert_eq!(
+            vec![4, 2, 3, 1, 0],
+            histogram.slowest.iter().map(|x| x.0).collect::<Vec<_>>()
+        );
+
+        for (ix, (_, duration)) in timings.iter().enumerate() {
+            let bucket = TimingHistogram::bucket_ix(*duration);
+
+            assert!(*duration < TimingHistogram::bucket_bound(bucket), "{}", ix);
+        }
+    }
+}
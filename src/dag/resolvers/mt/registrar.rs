@@ -1,5 +1,6 @@
 use crate::{dag::primitives::ResolverIx, log};
-use std::collections::HashMap;
+use std::collections::{hash_map::DefaultHasher, HashMap};
+use std::hash::{BuildHasher, Hasher};
 
 use crate::cs::Place;
 
@@ -13,6 +14,22 @@ pub(crate) struct Stats {
     pub secondary_resolutions: usize,
 }
 
+/// Builds hashers keyed by a seed. The delayed resolvers are released in the
+/// iteration order of their places, so it decides the internalization order.
+/// A std `RandomState` would make it differ between runs.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SeededState(u64);
+
+impl BuildHasher for SeededState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(self.0);
+        hasher
+    }
+}
+
 /// The Registrar keeps track of all variables in accordance to their place in the resolver, and
 /// keeps tabs on how many variables are tracked in total in the circuit.
 ///
@@ -20,15 +37,16 @@ pub(crate) struct Stats {
 /// and can handle any non circular dependency among the deferred resolvers.
 pub(crate) struct Registrar {
     pub max_tracked_variable: Place,
-    vars: HashMap<Place, Vec<ResolverIx>>,
+    vars: HashMap<Place, Vec<ResolverIx>, SeededState>,
     pub stats: Stats,
 }
 
 impl Registrar {
-    pub(crate) fn new() -> Self {
+    /// Without a seed, a random one is used.
+    pub(crate) fn new(seed: Option<u64>) -> Self {
         Self {
             max_tracked_variable: Place::placeholder(),
-            vars: HashMap::new(),
+            vars: HashMap::with_hasher(SeededState(seed.unwrap_or_else(rand::random))),
             stats: Stats {
                 total_resolvers: 0,
                 total_delayed_resolvers: 0,
@@ -137,7 +155,7 @@ impl Registrar {
         self.vars.is_empty()
     }
 
-    pub(crate) fn peek_vars(&'_ self) -> &'_ HashMap<Place, Vec<ResolverIx>> {
+    pub(crate) fn peek_vars(&'_ self) -> &'_ HashMap<Place, Vec<ResolverIx>, SeededState> {
         &self.vars
     }
 }
@@ -151,7 +169,7 @@ mod test {
     fn test1() {
         let mut registrar = Registrar {
             max_tracked_variable: Place(0),
-            vars: HashMap::new(),
+            vars: HashMap::default(),
             stats: Stats {
                 total_resolvers: 0,
                 total_delayed_resolvers: 0,
@@ -175,7 +193,7 @@ mod test {
     fn test2() {
         let mut registrar = Registrar {
             max_tracked_variable: Place(3),
-            vars: HashMap::new(),
+            vars: HashMap::default(),
             stats: Stats {
                 total_resolvers: 0,
                 total_delayed_resolvers: 0,
@@ -199,7 +217,7 @@ mod test {
     fn test3() {
         let mut registrar = Registrar {
             max_tracked_variable: Place(0),
-            vars: HashMap::new(),
+            vars: HashMap::default(),
             stats: Stats {
                 total_resolvers: 0,
                 total_delayed_resolvers: 0,
@@ -227,7 +245,7 @@ mod test {
     fn test4() {
         let mut registrar = Registrar {
             max_tracked_variable: Place(0),
-            vars: HashMap::new(),
+            vars: HashMap::default(),
             stats: Stats {
                 total_resolvers: 0,
                 total_delayed_resolvers: 0,
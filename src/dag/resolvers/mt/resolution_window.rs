@@ -8,7 +8,7 @@ use std::{
     any::Any,
     cell::{Cell, UnsafeCell},
     cmp,
-    collections::VecDeque,
+    collections::{BTreeSet, VecDeque},
     hint::spin_loop,
     io::Write,
     marker::PhantomData,
@@ -29,10 +29,10 @@ use smallvec::SmallVec;
 use crate::{
     cs::Place,
     dag::{
-        guide::{GuideLoc, OrderInfo},
+        guide::{CompactGuideLoc, GuideLoc, OrderInfo},
         primitives::{OrderIx, ResolverIx},
         resolver_box::Resolver,
-        TrackId,
+        CpuSet, IdlePolicy, PanicPolicy, TrackId,
     },
     field::SmallField,
     log,
@@ -80,6 +80,42 @@ impl RWConfig<GuideLoc> for RWConfigRecord<GuideLoc> {
     const ASSERT_TRACKED_VALUES: bool = true;
 }
 
+impl RWConfig<CompactGuideLoc> for RWConfigRecord<CompactGuideLoc> {
+    type TrackId = CompactGuideLoc;
+    const ASSERT_TRACKED_VALUES: bool = true;
+}
+
+/// Spawns the threads of the resolution window: the broker and the workers.
+/// The spawned functions run until the resolution completes, and the workers
+/// park between tasks, so each one needs a dedicated thread.
+pub trait Spawner: Send + Sync {
+    fn spawn(&self, f: Box<dyn FnOnce() + Send>) -> JoinHandle<()>;
+
+    /// Same as `spawn`, with a name for the thread that may be used for
+    /// diagnostics.
+    fn spawn_named(&self, name: String, f: Box<dyn FnOnce() + Send>) -> JoinHandle<()> {
+        let _ = name;
+        self.spawn(f)
+    }
+}
+
+/// Spawns a new OS thread for each function.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, f: Box<dyn FnOnce() + Send>) -> JoinHandle<()> {
+        std::thread::spawn(f)
+    }
+
+    fn spawn_named(&self, name: String, f: Box<dyn FnOnce() + Send>) -> JoinHandle<()> {
+        std::thread::Builder::new()
+            .name(name)
+            .spawn(f)
+            .expect("Couldn't spawn resolution window thread.")
+    }
+}
+
 pub(crate) struct ResolutionWindow<V, T: TrackId, Cfg: RWConfig<T>> {
     /// Represents a sliding window over the execution order.
     range: Range<usize>,
@@ -87,6 +123,13 @@ pub(crate) struct ResolutionWindow<V, T: TrackId, Cfg: RWConfig<T>> {
     channel: Arc<LockStepChannel>,
     pool: Vec<JoinHandle<()>>,
     stats: ResolutionWindowStats,
+    idle_policy: IdlePolicy,
+    panic_policy: PanicPolicy,
+    /// The prioritized awaits registered and the end of the range when the
+    /// window last looked for them, see `resolve_awaited`.
+    awaited_seen: (usize, usize),
+    /// Some of the prioritized awaits weren't resolved yet.
+    awaited_pending: bool,
 
     comms: Arc<ResolverComms>,
     common: Arc<ResolverCommonData<V, T>>,
@@ -113,9 +156,18 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
         common: Arc<ResolverCommonData<V, T>>,
         debug_track: &[Place],
         threads: u32,
+        affinity: Option<CpuSet>,
+        panic_policy: PanicPolicy,
+        idle_policy: IdlePolicy,
+        silent: bool,
+        spawner: &dyn Spawner,
     ) -> JoinHandle<()> {
         assert!(threads <= 128, "Not enough primes for that, add additional primes to the channel. Don't forget to update this assert.");
 
+        if cfg!(not(feature = "worker_affinity")) && affinity.is_some() && silent == false {
+            log!("CR: worker affinity is ignored, `worker_affinity` feature is disabled.");
+        }
+
         use rand::distributions::Alphanumeric;
         use rand::{thread_rng, Rng};
 
@@ -135,16 +187,30 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
                     receiver,
                     common: Arc::clone(&common),
                     debug_track: debug_track.to_vec(),
+                    panic_policy,
+                    #[cfg(feature = "profile-resolvers")]
+                    timings: UnsafeCell::new(Vec::new()),
                     phantom: PhantomData,
                 };
 
-                let handle = std::thread::Builder::new()
-                    .name(format!(
-                        "CircuitResolver-{}-worker-{}",
-                        discriminant_affix, i
-                    ))
-                    .spawn(move || worker.run())
-                    .expect("Couldn't spawn resolver worker thread.");
+                #[cfg(feature = "worker_affinity")]
+                let core = affinity
+                    .and_then(|x| x.iter().nth(i as usize))
+                    .map(|id| core_affinity::CoreId { id });
+
+                let handle = spawner.spawn_named(
+                    format!("CircuitResolver-{}-worker-{}", discriminant_affix, i),
+                    Box::new(move || {
+                        #[cfg(feature = "worker_affinity")]
+                        if let Some(core) = core {
+                            if core_affinity::set_for_current(core) == false && silent == false {
+                                log!("CR: failed to pin worker {} to core {}.", i, core.id);
+                            }
+                        }
+
+                        worker.run()
+                    }),
+                );
 
                 handle
             })
@@ -158,6 +224,10 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
             channel,
             pool,
             stats: ResolutionWindowStats::default(),
+            idle_policy,
+            panic_policy,
+            awaited_seen: (0, 0),
+            awaited_pending: false,
 
             common,
             comms,
@@ -168,17 +238,37 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
             phantom: PhantomData,
         };
 
-        std::thread::Builder::new()
-            .name(format!("CircuitResolver-{}-broker", discriminant_affix))
-            .spawn(move || unsafe {
+        spawner.spawn_named(
+            format!("CircuitResolver-{}-broker", discriminant_affix),
+            Box::new(move || unsafe {
                 this.resolve();
-            })
-            .expect("Couldn't spawn resolution window broker thread.")
+            }),
+        )
     }
 
     /// Processes all items currently in the buffer.
     fn process_buffer(&mut self) {
         while self.exec_order_buffer.len() > 0 {
+            if self
+                .comms
+                .cancelled
+                .load(std::sync::atomic::Ordering::Relaxed)
+            {
+                return;
+            }
+
+            if self.awaited_requested() {
+                self.resolve_awaited();
+
+                if self
+                    .comms
+                    .rw_panicked
+                    .load(std::sync::atomic::Ordering::Relaxed)
+                {
+                    return;
+                }
+            }
+
             // Safety: the worker threads are parked, so we can safely access
             // the data.
             let data = unsafe { self.channel.data.u_deref_mut() };
@@ -194,10 +284,20 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
 
             assert!(count > 0, "At least one task must be sent.");
 
-            for (buffer_ix, data_ix) in (0..count).zip((0..data.len()).cycle()) {
+            let mut enqueued = 0;
+
+            for buffer_ix in 0..count {
                 let task = &mut self.exec_order_buffer[buffer_ix];
                 let order_ix = buffer_ix + self.range.start; // Absolute order index
 
+                // Invoked ahead of the order for an awaiter.
+                if task.state == ResolverState::Done {
+                    continue;
+                }
+
+                let data_ix = enqueued % data.len();
+                enqueued += 1;
+
                 assert!(
                     task.state == ResolverState::Pending,
                     "Selected task to be executed is not pending."
@@ -239,28 +339,30 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
             }
 
             if (cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA) && true {
-                log!("RW: Batch! {} tasks.", count);
+                log!("RW: Batch! {} tasks.", enqueued);
             }
 
-            self.channel.execute();
+            if enqueued > 0 {
+                self.channel.execute();
+            }
 
             // Check if worker has paniced, mark the window as panicked and
             // end the resolution.
             if let Some(panic) = self.channel.get_panic() {
-                self.comms.rw_panic.set(Some(panic));
+                *self.comms.rw_panic.lock().unwrap() = Some(panic);
                 self.comms
                     .rw_panicked
                     .store(true, std::sync::atomic::Ordering::Relaxed);
                 return;
             }
 
-            // Mark the tasks as done. Items are processed in order, so we can
-            // just those that are marked as enqueued.
-            // Other option is to use count. Idk which one is faster and it's
-            // not a big enough difference to benchmark it.
+            // Mark the tasks as done. The batch may skip the resolvers that
+            // were invoked ahead of the order, so the enqueued ones aren't
+            // necessarily a prefix.
             self.exec_order_buffer
                 .iter_mut()
-                .take_while(|x| x.state == ResolverState::Enqueued)
+                .take(count)
+                .filter(|x| x.state == ResolverState::Enqueued)
                 .for_each(|x| {
                     x.state = ResolverState::Done;
 
@@ -321,10 +423,19 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
                 .to(|count| {
                     self.range = self.range.start + count..self.range.end;
 
+                    self.comms
+                        .executed
+                        .store(self.range.start, std::sync::atomic::Ordering::Relaxed);
+
                     let drained = self.exec_order_buffer.drain(..count);
 
                     let awaiters = &self.common.awaiters_broker;
 
+                    // Collected only with subscriptions, to keep this loop
+                    // allocation free otherwise.
+                    let subscribed = self.common.subscriptions.is_active();
+                    let mut places = Vec::new();
+
                     drained
                         // WARNING: We're not allowed to touch the `resolve_fn` of
                         // the resolver, as it was already dropped. It is ok to
@@ -338,11 +449,27 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
                                 .get(x.order_info.value)
                                 .outputs()
                         })
+                        .inspect(|x| {
+                            if subscribed {
+                                places.push(**x)
+                            }
+                        })
                         .map(|x| unsafe { self.common.values.u_deref().get_item_ref(*x).1.tracker })
                         .for_each(|x| awaiters.notify(x));
 
+                    if subscribed {
+                        self.common.subscriptions.notify(places.into_iter());
+                    }
+
                     drop(awaiters);
 
+                    // Counted only after the values were last touched, so the
+                    // values storage may be grown once the count catches up
+                    // with the execution order.
+                    self.common
+                        .resolved_count
+                        .fetch_add(count as u64, std::sync::atomic::Ordering::Release);
+
                     if cfg!(cr_paranoia_mode) && count > 0 {
                         log!(
                             "RW: Shifted by {}, new range is: {}..{}, buffer len: {}",
@@ -356,6 +483,160 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
         }
     }
 
+    /// Checks for prioritized awaits that were registered since, or that
+    /// may have become resolvable as the buffer grew.
+    fn awaited_requested(&self) -> bool {
+        let added = self.common.awaiters_broker.prioritized_added();
+
+        added != self.awaited_seen.0
+            || self.awaited_pending && self.range.end != self.awaited_seen.1
+    }
+
+    /// Invokes the resolvers the prioritized awaits depend on ahead of the
+    /// execution order, see `CircuitResolverOpts::prioritize_awaited`. The
+    /// workers are parked between the batches, so the resolvers are invoked
+    /// on this thread. Awaits whose resolvers aren't in the buffer yet are
+    /// kept for later.
+    fn resolve_awaited(&mut self) {
+        let added = self.common.awaiters_broker.prioritized_added();
+        let awaits = self.common.awaiters_broker.take_prioritized();
+
+        // The awaited resolvers were likely published after the buffer was
+        // filled.
+        self.extend_buffer();
+
+        let mut kept = Vec::new();
+
+        for await_ in awaits {
+            match self.awaited_chain(&await_.places) {
+                Some(chain) => {
+                    for buffer_ix in chain {
+                        if self.invoke_ahead(buffer_ix) == false {
+                            return;
+                        }
+                    }
+
+                    await_
+                        .resolved
+                        .store(true, std::sync::atomic::Ordering::Release);
+                }
+                None => kept.push(await_),
+            }
+        }
+
+        self.awaited_pending = kept.is_empty() == false;
+        self.awaited_seen = (added, self.range.end);
+        self.common.awaiters_broker.keep_prioritized(kept);
+    }
+
+    /// Takes the resolvers added to the execution order since the buffer was
+    /// filled, as far as the bound and the buffer capacity allow.
+    fn extend_buffer(&mut self) {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        let exec_order = self.common.exec_order.lock().unwrap();
+        let limit = cmp::min(exec_order.size, self.comms.resolve_limit.load(Relaxed));
+        let space_left = self.exec_order_buffer.capacity() - self.exec_order_buffer.len();
+        let extend_to = cmp::min(limit, self.range.end + space_left);
+
+        if extend_to > self.range.end {
+            exec_order.items[self.range.end..extend_to]
+                .iter()
+                .map(|x| OrderBufferItem {
+                    order_info: *x,
+                    state: ResolverState::Pending,
+                })
+                .to(|x| self.exec_order_buffer.extend(x));
+
+            self.range = self.range.start..extend_to;
+            self.stats.total_consumption = extend_to as u64;
+        }
+    }
+
+    /// The buffer indices of the pending resolvers that `places` depend on,
+    /// in the execution order. `None` if one of them isn't in the buffer.
+    fn awaited_chain(&self, places: &[Place]) -> Option<Vec<usize>> {
+        // Safety: The workers are parked, so the values aren't being written,
+        // and the buffered resolvers are present.
+        let values = unsafe { self.common.values.u_deref() };
+        let resolvers = unsafe { self.common.resolvers.u_deref() };
+
+        let unresolved = |x: &Place| {
+            let md = &values.get_item_ref(*x).1;
+            md.is_resolved() == false && md.is_failed() == false
+        };
+
+        let mut chain = BTreeSet::new();
+        let mut stack = places
+            .iter()
+            .filter(|x| unresolved(x))
+            .map(|x| (*x, self.exec_order_buffer.len()))
+            .collect_vec();
+
+        while let Some((place, consumer_ix)) = stack.pop() {
+            // The producer precedes its consumers in the order.
+            let ix = (0..consumer_ix).rev().find(|x| {
+                let item = &self.exec_order_buffer[*x];
+
+                item.state == ResolverState::Pending
+                    && unsafe { resolvers.get(item.order_info.value) }
+                        .outputs()
+                        .contains(&place)
+            })?;
+
+            if chain.insert(ix) {
+                let resolver =
+                    unsafe { resolvers.get(self.exec_order_buffer[ix].order_info.value) };
+
+                resolver
+                    .inputs()
+                    .iter()
+                    .filter(|x| unresolved(x))
+                    .for_each(|x| stack.push((*x, ix)));
+            }
+        }
+
+        Some(chain.into_iter().collect())
+    }
+
+    /// Invokes a buffered resolver ahead of the order. Returns `false` if it
+    /// panicked, which ends the resolution the same way a worker panic does.
+    fn invoke_ahead(&mut self, buffer_ix: usize) -> bool {
+        let order_ix = buffer_ix + self.range.start;
+        let task = &mut self.exec_order_buffer[buffer_ix];
+
+        task.state = ResolverState::Done;
+
+        let common = &self.common;
+        let panic_policy = self.panic_policy;
+        // Safety: The resolver is pending, so it wasn't invoked.
+        let resolver = unsafe { common.resolvers.u_deref().get(task.order_info.value) };
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            match panic_policy {
+                PanicPolicy::Abort => invoke::<V, T, Cfg>(common, &[], resolver, order_ix.into()),
+                PanicPolicy::SkipAndRecord => {
+                    invoke_or_skip::<V, T, Cfg>(common, &[], resolver, order_ix.into())
+                }
+            }
+        }));
+
+        if cfg!(cr_paranoia_mode) {
+            self.execution_list[order_ix] += 1;
+        }
+
+        match result {
+            Ok(()) => true,
+            Err(panic) => {
+                *self.comms.rw_panic.lock().unwrap() = Some(panic);
+                self.comms
+                    .rw_panicked
+                    .store(true, std::sync::atomic::Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
     /// The function requires that `exec_order` is populated only by values from the
     /// `resolvers`.
     /// Also, the provided resolution functions must not ever confuse inputs with outputs.
@@ -369,6 +650,14 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
         loop {
             self.stats.total_control_iterations += 1;
 
+            if self
+                .comms
+                .cancelled
+                .load(std::sync::atomic::Ordering::Relaxed)
+            {
+                break;
+            }
+
             let registration_complete = self
                 .comms
                 .registration_complete
@@ -377,7 +666,12 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
             use std::sync::atomic::Ordering::Relaxed;
 
             let exec_order = self.common.exec_order.lock().unwrap();
-            let limit = exec_order.size;
+            let size = exec_order.size;
+            // The bound may have been set below the already taken resolvers.
+            let limit = cmp::max(
+                self.range.end,
+                cmp::min(size, self.comms.resolve_limit.load(Relaxed)),
+            );
 
             if limit - self.range.end > 0 || registration_complete {
                 // New resolvers were added since.
@@ -423,6 +717,13 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
             } else {
                 drop(exec_order);
 
+                // A sleeping window checks for the registration completion
+                // on every wakeup.
+                let check_interval = match self.idle_policy {
+                    IdlePolicy::Sleep(_) => 0,
+                    _ => 1 << 10,
+                };
+
                 let mut iters = 0;
                 loop {
                     let hint = self
@@ -437,7 +738,7 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
                         _ => {
                             iters += 1;
 
-                            if iters > (1 << 10) {
+                            if iters > check_interval {
                                 if self.comms.registration_complete.load(Relaxed) {
                                     break;
                                 }
@@ -445,7 +746,11 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
                                 iters = 0;
                             }
 
-                            yield_now();
+                            match self.idle_policy {
+                                IdlePolicy::Spin => spin_loop(),
+                                IdlePolicy::Yield => yield_now(),
+                                IdlePolicy::Sleep(duration) => std::thread::sleep(duration),
+                            }
                             continue;
                         }
                     }
@@ -455,7 +760,13 @@ impl<V: SmallField + 'static, T: TrackId + 'static, Cfg: RWConfig<T> + 'static>
 
             let exec_order_len = self.exec_order_buffer.len();
 
-            if registration_complete && exec_order_len == 0 && limit == self.range.end {
+            // The bound is lifted before the registration completes, but it
+            // may be observed later.
+            if registration_complete
+                && exec_order_len == 0
+                && limit == self.range.end
+                && limit == size
+            {
                 break;
             }
 
@@ -509,6 +820,9 @@ struct Worker<V: Copy, T: TrackId, Cfg: RWConfig<T>, const SIZE: usize> {
     receiver: LockStepWorker,
     common: Arc<ResolverCommonData<V, T>>,
     debug_track: Vec<Place>,
+    panic_policy: PanicPolicy,
+    #[cfg(feature = "profile-resolvers")]
+    timings: UnsafeCell<Vec<(crate::dag::guide::RegistrationNum, Duration)>>,
     phantom: PhantomData<Cfg>,
 }
 
@@ -554,7 +868,14 @@ impl<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>, const SIZE: usize>
                             // here, as this is an unsynchronizd access.
                             let resolver = this.common.resolvers.u_deref().get(*resolver_ix);
 
-                            if cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA {
+                            #[cfg(feature = "profile-resolvers")]
+                            let started_at = (resolver.added_at(), std::time::Instant::now());
+
+                            if this.panic_policy == PanicPolicy::SkipAndRecord {
+                                // Safety: Same as below.
+                                invoke_or_skip::<V, T, Cfg>(&this.common, &this.debug_track, resolver, *order_ix);
+                            }
+                            else if cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA {
                                 std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                                     this.invoke(resolver, *order_ix);
 
@@ -577,6 +898,9 @@ impl<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>, const SIZE: usize>
                                 // Safety: The `resolve_fn()` wasn't called on the resolver.
                                 this.invoke(resolver, *order_ix);
                             }
+
+                            #[cfg(feature = "profile-resolvers")]
+                            this.timings.u_deref_mut().push((started_at.0, started_at.1.elapsed()));
                         }
 
                     }
@@ -590,6 +914,13 @@ impl<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>, const SIZE: usize>
             });
         }
 
+        #[cfg(feature = "profile-resolvers")]
+        self.common
+            .timings
+            .lock()
+            .unwrap()
+            .append(self.timings.get_mut());
+
         if cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA {
             log!(
                 "{}\n{:#?}\n{:#?}",
@@ -602,142 +933,222 @@ impl<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>, const SIZE: usize>
 
     /// Safety: `resolve_fn()` mustn't've been called on the resolver.
     unsafe fn invoke(&self, resolver: &Resolver, order_ix: OrderIx) {
-        fence(std::sync::atomic::Ordering::Acquire);
+        invoke::<V, T, Cfg>(&self.common, &self.debug_track, resolver, order_ix)
+    }
+}
+
+/// Safety: `resolve_fn()` mustn't've been called on the resolver.
+unsafe fn invoke<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>>(
+    common: &ResolverCommonData<V, T>,
+    debug_track: &[Place],
+    resolver: &Resolver,
+    order_ix: OrderIx,
+) {
+    fence(std::sync::atomic::Ordering::Acquire);
+
+    // Safety: Using `values` in an unsynchronized manner is safe, since we are
+    // only getting items that are guaranteed to be already written and remain
+    // immutable for entire execution except this very function.
+    // Any out of order exection would not occur because the resolution window
+    // thread mutex'es with the main thread and is synched with this worker.
+
+    let ins_ixs = resolver.inputs();
+    let out_ixs = resolver.outputs();
+
+    if crate::dag::resolvers::mt::PARANOIA && false {
+        let vs = common.values.u_deref();
+
+        println!("RW: input ixs: {:#?}", ins_ixs);
+        println!("RW: variables resolved");
+        vs.variables
+            .iter()
+            .enumerate()
+            .for_each(|(i, x)| println!("[{}] => r: {}", i, x.u_deref().1.is_resolved()));
+    }
+
+    let ins_vs: SmallVec<[_; 8]> = ins_ixs
+        .iter()
+        .map(|x| {
+            let (vs, md) = common.values.u_deref().get_item_ref(*x);
+
+            if cfg!(cr_paranoia_mode) || true {
+                if Cfg::ASSERT_TRACKED_VALUES {
+                    assert!(md.is_tracked());
+                }
+                assert!(
+                    md.is_resolved(),
+                    "Not resolved at ix {:?}, order ix {:?}, thread {:?}",
+                    x,
+                    order_ix,
+                    std::thread::current().name()
+                );
+            }
 
-        // Safety: Using `values` in an unsynchronized manner is safe, since we are
-        // only getting items that are guaranteed to be already written and remain
-        // immutable for entire execution except this very function.
-        // Any out of order exection would not occur because the resolution window
-        // thread mutex'es with the main thread and is synched with this worker.
+            // Safety:
+            // 1. Rust infers this clouse as FnMut, thus we can't return the
+            // reference from the closure as it consumes `values`.
+            //
+            // 2. We also need to cast the references to consts as the
+            // resolution function expects constant inputs. The cast is safe
+            // since the items we pick up are guaranteed to be distinct between
+            // all active resolvers. All resolvers that write to those items
+            // have already done so, due to the exection ordering.
+            *(vs as *const V)
+        })
+        .collect();
+
+    // The destination buffer is backed by `out_vs`, so it is sized by the
+    // declared output count upfront and never grows during the call.
+    let mut out_vs: SmallVec<[_; 8]> = SmallVec::with_capacity(out_ixs.len());
+    let mut mds: SmallVec<[_; 8]> = SmallVec::with_capacity(out_ixs.len());
+
+    out_ixs.iter().for_each(|x| {
+        // Safety: getting mutable refs here is ok because they are puller
+        // for a globally unique `x`.
+        let (vs, md) = common.values.u_deref().get_item_ref_mut(*x);
+
+        assert!(
+            md.is_resolved() == false,
+            "Already resolved at ix {:?}, thread {:?}",
+            x,
+            std::thread::current().name()
+        );
 
-        let ins_ixs = resolver.inputs();
-        let out_ixs = resolver.outputs();
+        // Safety:
+        // 1. Same as inputs.
+        // 2. Must not point to any input.
+        out_vs.push(&mut *(vs as *mut _));
+        mds.push(md);
+    });
 
-        if crate::dag::resolvers::mt::PARANOIA && false {
-            let vs = self.common.values.u_deref();
+    let mut track = false;
 
-            println!("RW: input ixs: {:#?}", ins_ixs);
-            println!("RW: variables resolved");
-            vs.variables
-                .iter()
-                .enumerate()
-                .for_each(|(i, x)| println!("[{}] => r: {}", i, x.u_deref().1.is_resolved()));
+    if cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA {
+        if let Some(x) = debug_track.iter().find(|x| resolver.inputs().contains(x)) {
+            log!(
+                "RW: invoking at ix {:?} with tracked input {:?}",
+                order_ix,
+                x
+            );
+
+            track = true;
         }
 
-        let ins_vs: SmallVec<[_; 8]> = ins_ixs
-            .iter()
-            .map(|x| {
-                let (vs, md) = self.common.values.u_deref().get_item_ref(*x);
+        if let Some(x) = debug_track.iter().find(|x| resolver.outputs().contains(x)) {
+            log!(
+                "RW: invoking at ix {:?} with with tracked output {:?}",
+                order_ix,
+                x
+            );
 
-                if cfg!(cr_paranoia_mode) || true {
-                    if Cfg::ASSERT_TRACKED_VALUES {
-                        assert!(md.is_tracked());
-                    }
-                    assert!(
-                        md.is_resolved(),
-                        "Not resolved at ix {:?}, order ix {:?}, thread {:?}",
-                        x,
-                        order_ix,
-                        std::thread::current().name()
-                    );
-                }
+            track = true;
+        }
 
-                // Safety:
-                // 1. Rust infers this clouse as FnMut, thus we can't return the
-                // reference from the closure as it consumes `values`.
-                //
-                // 2. We also need to cast the references to consts as the
-                // resolution function expects constant inputs. The cast is safe
-                // since the items we pick up are guaranteed to be distinct between
-                // all active resolvers. All resolvers that write to those items
-                // have already done so, due to the exection ordering.
-                *(vs as *const V)
-            })
-            .collect();
+        if track {
+            log!(
+                "   Ins:\n   - {}\n   Outs:\n   - {}",
+                resolver
+                    .inputs()
+                    .iter()
+                    .map(|x| format!(
+                        "{:?} : {:?}",
+                        x,
+                        common.values.u_deref().get_item_ref(*x).0.as_raw_u64()
+                    ))
+                    .collect_vec()
+                    .join("\n   - "),
+                resolver
+                    .outputs()
+                    .iter()
+                    .map(|x| format!("{:?}", x))
+                    .collect_vec()
+                    .join("\n   - ")
+            );
+        }
+    }
 
-        let (mut out_vs, mut mds): (SmallVec<[_; 8]>, SmallVec<[_; 8]>) = out_ixs
-            .iter()
-            .map(|x| {
-                // Safety: getting mutable refs here is ok because they are puller
-                // for a globally unique `x`.
-                let (vs, md) = self.common.values.u_deref().get_item_ref_mut(*x);
+    let bind_fn =
+        std::mem::transmute::<_, fn(&Resolver, &[V], &mut [&mut V], bool)>(resolver.bind_fn_ptr());
+    bind_fn(resolver, ins_vs.as_slice(), out_vs.as_mut_slice(), track);
 
-                assert!(
-                    md.is_resolved() == false,
-                    "Already resolved at ix {:?}, thread {:?}",
-                    x,
-                    std::thread::current().name()
-                );
+    fence(std::sync::atomic::Ordering::Release);
 
-                // Safety:
-                // 1. Same as inputs.
-                // 2. Must not point to any input.
-                (&mut *(vs as *mut _), md)
-            })
-            .unzip();
+    mds.iter_mut().for_each(|x| x.mark_resolved());
+}
 
-        let mut track = false;
+/// Invokes the resolver, unless one of its inputs failed. If it panics or
+/// isn't invoked, its outputs are marked as failed and the registration is
+/// recorded, see `PanicPolicy::SkipAndRecord`.
+///
+/// Safety: `resolve_fn()` mustn't've been called on the resolver.
+unsafe fn invoke_or_skip<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>>(
+    common: &ResolverCommonData<V, T>,
+    debug_track: &[Place],
+    resolver: &Resolver,
+    order_ix: OrderIx,
+) {
+    let values = common.values.u_deref();
+
+    let failed = resolver
+        .inputs()
+        .iter()
+        .any(|x| values.get_item_ref(*x).1.is_failed())
+        || std::panic::catch_unwind(AssertUnwindSafe(|| {
+            invoke::<V, T, Cfg>(common, debug_track, resolver, order_ix)
+        }))
+        .is_err();
+
+    if failed {
+        // Safety: The outputs are written only by this resolver.
+        resolver
+            .outputs()
+            .iter()
+            .for_each(|x| values.get_item_ref_mut(*x).1.mark_failed());
 
-        if cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA {
-            if let Some(x) = self
-                .debug_track
-                .iter()
-                .find(|x| resolver.inputs().contains(x))
-            {
-                log!(
-                    "RW: invoking at ix {:?} with tracked input {:?}",
-                    order_ix,
-                    x
-                );
+        common.failed.lock().unwrap().push(resolver.added_at());
+    }
+}
 
-                track = true;
-            }
+/// Invokes the resolvers in `range` of the execution order on the calling
+/// thread, one after another, and notifies the awaiters. This is the
+/// deterministic counterpart of the resolution window, used when the resolver
+/// is configured with no worker threads.
+///
+/// Safety: The resolvers in `range` mustn't've been invoked, and no resolution
+/// window may be running over the same common data.
+pub(crate) unsafe fn resolve_inline<V: SmallField, T: TrackId + 'static, Cfg: RWConfig<T>>(
+    common: &ResolverCommonData<V, T>,
+    debug_track: &[Place],
+    range: Range<usize>,
+    panic_policy: PanicPolicy,
+) {
+    let items = common.exec_order.lock().unwrap().items[range.clone()].to_vec();
 
-            if let Some(x) = self
-                .debug_track
-                .iter()
-                .find(|x| resolver.outputs().contains(x))
-            {
-                log!(
-                    "RW: invoking at ix {:?} with with tracked output {:?}",
-                    order_ix,
-                    x
-                );
+    for (order_ix, item) in range.zip(items) {
+        let resolver = common.resolvers.u_deref().get(item.value);
 
-                track = true;
+        match panic_policy {
+            PanicPolicy::Abort => {
+                invoke::<V, T, Cfg>(common, debug_track, resolver, order_ix.into())
             }
-
-            if track {
-                log!(
-                    "   Ins:\n   - {}\n   Outs:\n   - {}",
-                    resolver
-                        .inputs()
-                        .iter()
-                        .map(|x| format!(
-                            "{:?} : {:?}",
-                            x,
-                            self.common.values.u_deref().get_item_ref(*x).0.as_raw_u64()
-                        ))
-                        .collect_vec()
-                        .join("\n   - "),
-                    resolver
-                        .outputs()
-                        .iter()
-                        .map(|x| format!("{:?}", x))
-                        .collect_vec()
-                        .join("\n   - ")
-                );
+            PanicPolicy::SkipAndRecord => {
+                invoke_or_skip::<V, T, Cfg>(common, debug_track, resolver, order_ix.into())
             }
         }
 
-        let bind_fn = std::mem::transmute::<_, fn(&Resolver, &[V], &mut [&mut V], bool)>(
-            resolver.bind_fn_ptr(),
-        );
-        bind_fn(resolver, ins_vs.as_slice(), out_vs.as_mut_slice(), track);
+        common
+            .resolved_count
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        fence(std::sync::atomic::Ordering::Release);
+        resolver
+            .outputs()
+            .iter()
+            .map(|x| common.values.u_deref().get_item_ref(*x).1.tracker)
+            .for_each(|x| common.awaiters_broker.notify(x));
 
-        mds.iter_mut().for_each(|x| x.mark_resolved());
+        common
+            .subscriptions
+            .notify(resolver.outputs().iter().copied());
     }
 }
 
@@ -0,0 +1,58 @@
+use std::{any::Any, cell::RefCell};
+
+/// Reusable buffers for the intermediate computations of a resolution, see
+/// `MtCircuitResolver::add_resolution_scratch`. Each thread invoking the
+/// resolutions has its own, so the buffers keep their capacity across
+/// invocations instead of being allocated by each one.
+#[derive(Debug)]
+pub struct ScratchSpace<V> {
+    pub values: Vec<V>,
+    pub bytes: Vec<u8>,
+}
+
+impl<V> ScratchSpace<V> {
+    fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Empties the buffers, keeping their capacity.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.bytes.clear();
+    }
+}
+
+thread_local! {
+    static SCRATCH: RefCell<Option<Box<dyn Any>>> = RefCell::new(None);
+}
+
+/// Calls `f` with the scratch space of the current thread and clears it
+/// afterwards.
+pub(crate) fn with_scratch<V: 'static, R>(f: impl FnOnce(&mut ScratchSpace<V>) -> R) -> R {
+    SCRATCH.with(|x| {
+        let mut slot = x.borrow_mut();
+
+        // A thread invokes resolutions of a single field type in practice, so
+        // only the last used space is retained.
+        if slot
+            .as_ref()
+            .map_or(true, |x| x.is::<ScratchSpace<V>>() == false)
+        {
+            *slot = Some(Box::new(ScratchSpace::<V>::new()));
+        }
+
+        let scratch = slot
+            .as_mut()
+            .and_then(|x| x.downcast_mut::<ScratchSpace<V>>())
+            .unwrap();
+
+        let r = f(scratch);
+
+        scratch.clear();
+
+        r
+    })
+}
@@ -5,32 +5,72 @@ use crate::{
     dag::{
         guide::RegistrationNum,
         primitives::{OrderIx, ResolverIx},
-        TrackId,
+        CapacityPolicy, CpuSet, DuplicateSetPolicy, IdlePolicy, PanicPolicy, Priority, TrackId,
     },
     field::SmallField,
     utils::PipeOp as _,
 };
 
-use super::{resolution_window::RWConfig, ResolverCommonData, ResolverComms};
+use super::{
+    resolution_window::{RWConfig, Spawner},
+    ResolverCommonData, ResolverComms,
+};
 
+mod record_storage;
 pub mod sorter_live;
 pub mod sorter_playback;
 
+pub use self::record_storage::{
+    FileRecordStorage, FileRecordWriter, InMemoryRecordSource, InMemoryRecordStorage,
+    InMemoryRecordWriter,
+};
+
 pub trait ResolverSortingMode<F: SmallField>: Sized {
     type Arg;
     type Config: RWConfig<Self::TrackId> + 'static;
     type TrackId: TrackId + 'static;
 
+    /// Threads the sorter needs while it is created, e.g. for the first
+    /// touch of the values, are spawned by `spawner`.
     fn new(
         opts: Self::Arg,
         comms: Arc<ResolverComms>,
         debug_track: &[Place],
+        spawner: &dyn Spawner,
     ) -> (Self, Arc<ResolverCommonData<F, Self::TrackId>>);
+    /// Replaces the sorter state and the common data with fresh ones for a
+    /// new resolution, keeping the configuration. Called once the resolution
+    /// window is joined.
+    fn clear(
+        &mut self,
+        comms: Arc<ResolverComms>,
+        debug_track: &[Place],
+        spawner: &dyn Spawner,
+    ) -> Arc<ResolverCommonData<F, Self::TrackId>>;
     fn set_value(&mut self, key: Place, value: F);
+    /// Marks the first `len` places as tracked ahead of them being set, see
+    /// `MtCircuitResolver::declare_tracked_prefix`.
+    fn declare_tracked_prefix(&mut self, len: usize);
+    fn declare_hole(&mut self, place: Place);
     fn add_resolution<Fn>(&mut self, inputs: &[Place], outputs: &[Place], f: Fn)
     where
         Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync;
 
+    /// Same as `add_resolution`, but among the registrations that become
+    /// ready together, the ones with a higher priority are ordered first.
+    /// Sorters with a fixed order, e.g. the playback, ignore the priority.
+    fn add_resolution_prioritized<Fn>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        _priority: Priority,
+        f: Fn,
+    ) where
+        Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync,
+    {
+        self.add_resolution(inputs, outputs, f)
+    }
+
     fn internalize(
         &mut self,
         resolver_ix: ResolverIx,
@@ -50,10 +90,70 @@ pub trait ResolverSortingMode<F: SmallField>: Sized {
     fn final_flush(&mut self);
     fn write_sequence(&mut self);
 
-    fn retrieve_sequence(&mut self) -> &ResolutionRecord;
+    /// The registrations internalized and the resolvers moved into the
+    /// execution order since the sorter was created.
+    fn progress(&self) -> FlushReport;
+
+    /// The registrations that are held back until an input is tracked.
+    fn pending_registrations(&self) -> Vec<PendingInfo> {
+        Vec::new()
+    }
+
+    /// CPUs to pin the resolution window workers to.
+    fn worker_affinity(&self) -> Option<CpuSet> {
+        None
+    }
+
+    fn capacity_policy(&self) -> CapacityPolicy {
+        CapacityPolicy::Panic
+    }
+
+    fn duplicate_set_policy(&self) -> DuplicateSetPolicy {
+        DuplicateSetPolicy::Panic
+    }
+
+    fn track_fanout(&self) -> bool {
+        false
+    }
+
+    fn track_dependencies(&self) -> bool {
+        false
+    }
+
+    fn max_pending_resolvers(&self) -> Option<usize> {
+        None
+    }
+
+    fn panic_policy(&self) -> PanicPolicy {
+        PanicPolicy::Abort
+    }
+
+    fn idle_policy(&self) -> IdlePolicy {
+        IdlePolicy::Yield
+    }
+
+    fn prioritize_awaited(&self) -> bool {
+        false
+    }
+
+    fn silent(&self) -> bool {
+        false
+    }
+
+    /// Pre-sizes the execution order and the sorter buffers, see
+    /// `MtCircuitResolver::reserve`.
+    fn reserve(&mut self, _expected_registrations: usize) {}
+
+    fn retrieve_sequence(&self) -> &ResolutionRecord;
+
+    /// Moves the record out of the sorter. Sorters that don't own the record
+    /// return a copy.
+    fn take_sequence(&mut self) -> ResolutionRecord {
+        self.retrieve_sequence().clone()
+    }
 }
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct ResolutionRecordItem {
     added_at: RegistrationNum,
     accepted_at: RegistrationNum,
@@ -63,11 +163,60 @@ pub struct ResolutionRecordItem {
     parallelism: u16,
 }
 
-#[derive(Clone, Debug)]
+impl ResolutionRecordItem {
+    /// Writes the item in a fixed-size little-endian layout, to be read back
+    /// with `read_from`.
+    pub fn write_to<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        w.write_all(&self.added_at.to_le_bytes())?;
+        w.write_all(&self.accepted_at.to_le_bytes())?;
+        w.write_all(&(self.order_len as u64).to_le_bytes())?;
+        w.write_all(&u32::from(self.order_ix).to_le_bytes())?;
+        w.write_all(&self.parallelism.to_le_bytes())
+    }
+
+    pub fn read_from<R: std::io::Read>(mut r: R) -> std::io::Result<Self> {
+        let mut u16_buf = [0; 2];
+        let mut u32_buf = [0; 4];
+        let mut u64_buf = [0; 8];
+
+        r.read_exact(&mut u32_buf)?;
+        let added_at = RegistrationNum::from_le_bytes(u32_buf);
+        r.read_exact(&mut u32_buf)?;
+        let accepted_at = RegistrationNum::from_le_bytes(u32_buf);
+        r.read_exact(&mut u64_buf)?;
+        let order_len = u64::from_le_bytes(u64_buf) as usize;
+        r.read_exact(&mut u32_buf)?;
+        let order_ix = u32::from_le_bytes(u32_buf).into();
+        r.read_exact(&mut u16_buf)?;
+        let parallelism = u16::from_le_bytes(u16_buf);
+
+        Ok(Self {
+            added_at,
+            accepted_at,
+            order_len,
+            order_ix,
+            parallelism,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ResolutionRecord {
     pub items: Vec<ResolutionRecordItem>,
     pub registrations_count: usize,
     pub values_count: usize,
+    /// Hash of the input and output counts of the registrations, in
+    /// registration order. Together with the counts it fingerprints the
+    /// structure of the recorded circuit, so the playback can detect a record
+    /// of a different circuit.
+    pub arity_hash: u64,
+    /// The reduced values resolved by the recording, by place index. Set with
+    /// `CircuitResolverOpts::verify_playback`.
+    pub values: Option<Vec<u64>>,
+    /// The observed invocation duration of each resolver, by registration
+    /// number. Set with the `record-timings` feature, so a playback's
+    /// profile can be compared against the recording's.
+    pub timings: Option<Vec<std::time::Duration>>,
 }
 
 impl ResolutionRecord {
@@ -77,14 +226,326 @@ impl ResolutionRecord {
         Self {
             registrations_count,
             values_count,
+            arity_hash: FNV_OFFSET_BASIS,
+            values: None,
+            timings: None,
             items: Vec::with_capacity(size)
                 .op(|x| x.resize_with(size, ResolutionRecordItem::default)),
         }
     }
+
+    /// Folds a registration into `arity_hash`. Uses FNV-1a, so the hash is
+    /// stable across builds and platforms.
+    pub(crate) fn hash_arity(hash: u64, inputs: usize, outputs: usize) -> u64 {
+        let hash = fnv1a(hash, &(inputs as u32).to_le_bytes());
+        fnv1a(hash, &(outputs as u32).to_le_bytes())
+    }
+
+    /// Hashes the structure of the record: the counts, the arity hash and the
+    /// registration number, order index and parallelism of each item. The
+    /// hash doesn't depend on timings or on the recorded values, and is
+    /// stable across builds and platforms, so it can key a record cache.
+    ///
+    /// Only the items held by the record are hashed, which for a streamed
+    /// record is the tail that wasn't streamed.
+    pub fn structural_hash(&self) -> u64 {
+        let hash = [
+            self.registrations_count as u64,
+            self.values_count as u64,
+            self.arity_hash,
+        ]
+        .iter()
+        .fold(FNV_OFFSET_BASIS, |h, x| fnv1a(h, &x.to_le_bytes()));
+
+        self.items.iter().fold(hash, |h, x| {
+            let h = fnv1a(h, &x.added_at.to_le_bytes());
+            let h = fnv1a(h, &u32::from(x.order_ix).to_le_bytes());
+            fnv1a(h, &x.parallelism.to_le_bytes())
+        })
+    }
+
+    /// Writes the record counts followed by the items, each in the layout of
+    /// `ResolutionRecordItem::write_to`, and the optional values and timings,
+    /// to be read back with `read_from`. The registrations count is that of
+    /// the whole recording, while a streamed record writes only its tail
+    /// items, the rest having gone through `store_items`.
+    pub fn write_to<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        w.write_all(&(self.registrations_count as u64).to_le_bytes())?;
+        w.write_all(&(self.values_count as u64).to_le_bytes())?;
+        w.write_all(&self.arity_hash.to_le_bytes())?;
+        w.write_all(&(self.items.len() as u64).to_le_bytes())?;
+
+        self.items.iter().try_for_each(|x| x.write_to(&mut w))?;
+
+        match &self.values {
+            None => w.write_all(&[0])?,
+            Some(values) => {
+                w.write_all(&[1])?;
+                values
+                    .iter()
+                    .try_for_each(|x| w.write_all(&x.to_le_bytes()))?;
+            }
+        }
+
+        match &self.timings {
+            None => w.write_all(&[0]),
+            Some(timings) => {
+                w.write_all(&[1])?;
+                timings
+                    .iter()
+                    .try_for_each(|x| w.write_all(&(x.as_nanos() as u64).to_le_bytes()))
+            }
+        }
+    }
+
+    pub fn read_from<R: std::io::Read>(mut r: R) -> std::io::Result<Self> {
+        let mut u64_buf = [0; 8];
+
+        r.read_exact(&mut u64_buf)?;
+        let registrations_count = u64::from_le_bytes(u64_buf) as usize;
+        r.read_exact(&mut u64_buf)?;
+        let values_count = u64::from_le_bytes(u64_buf) as usize;
+        r.read_exact(&mut u64_buf)?;
+        let arity_hash = u64::from_le_bytes(u64_buf);
+        r.read_exact(&mut u64_buf)?;
+        let items_len = u64::from_le_bytes(u64_buf) as usize;
+
+        let items = (0..items_len)
+            .map(|_| ResolutionRecordItem::read_from(&mut r))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let mut u8_buf = [0; 1];
+
+        r.read_exact(&mut u8_buf)?;
+        let values = match u8_buf[0] {
+            0 => None,
+            _ => (0..values_count)
+                .map(|_| {
+                    r.read_exact(&mut u64_buf)
+                        .map(|_| u64::from_le_bytes(u64_buf))
+                })
+                .collect::<std::io::Result<Vec<_>>>()?
+                .to(Some),
+        };
+
+        r.read_exact(&mut u8_buf)?;
+        let timings = match u8_buf[0] {
+            0 => None,
+            _ => (0..registrations_count)
+                .map(|_| {
+                    r.read_exact(&mut u64_buf)
+                        .map(|_| std::time::Duration::from_nanos(u64::from_le_bytes(u64_buf)))
+                })
+                .collect::<std::io::Result<Vec<_>>>()?
+                .to(Some),
+        };
+
+        Ok(Self {
+            items,
+            registrations_count,
+            values_count,
+            arity_hash,
+            values,
+            timings,
+        })
+    }
+
+    /// Compares the record with `other`: the counts, the number of items and
+    /// the first item at which they diverge. The values and timings aren't
+    /// compared.
+    pub fn diff(&self, other: &Self) -> RecordDiff {
+        let pair = |a: usize, b: usize| match a == b {
+            true => None,
+            false => Some((a, b)),
+        };
+
+        let first_item = self
+            .items
+            .iter()
+            .zip(other.items.iter())
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+            .map(|(ix, (a, b))| {
+                let mut fields = Vec::new();
+
+                if a.added_at != b.added_at {
+                    fields.push(ItemFieldDiff::AddedAt(a.added_at, b.added_at));
+                }
+                if a.accepted_at != b.accepted_at {
+                    fields.push(ItemFieldDiff::AcceptedAt(a.accepted_at, b.accepted_at));
+                }
+                if a.order_len != b.order_len {
+                    fields.push(ItemFieldDiff::OrderLen(a.order_len, b.order_len));
+                }
+                if a.order_ix != b.order_ix {
+                    fields.push(ItemFieldDiff::OrderIx(a.order_ix, b.order_ix));
+                }
+                if a.parallelism != b.parallelism {
+                    fields.push(ItemFieldDiff::Parallelism(a.parallelism, b.parallelism));
+                }
+
+                (ix, fields)
+            });
+
+        RecordDiff {
+            registrations_count: pair(self.registrations_count, other.registrations_count),
+            values_count: pair(self.values_count, other.values_count),
+            items_len: pair(self.items.len(), other.items.len()),
+            first_item,
+        }
+    }
+
+    pub fn summary(&self) -> RecordSummary {
+        RecordSummary {
+            registrations_count: self.registrations_count,
+            values_count: self.values_count,
+            items_len: self.items.len(),
+        }
+    }
+
+    /// Summarizes the parallelism the sorter assigned to the registrations.
+    /// A streamed record only holds the items that weren't passed to the
+    /// writer yet, so the report covers just that tail.
+    pub fn parallelism_report(&self) -> ParallelismReport {
+        let items = &self.items[..self.registrations_count.min(self.items.len())];
+
+        match items.is_empty() {
+            true => ParallelismReport::default(),
+            false => ParallelismReport {
+                registrations: items.len(),
+                min: items.iter().map(|x| x.parallelism).min().unwrap(),
+                max: items.iter().map(|x| x.parallelism).max().unwrap(),
+                mean: items.iter().map(|x| x.parallelism as f64).sum::<f64>() / items.len() as f64,
+            },
+        }
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// Folds `bytes` into an FNV-1a hash.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |h, x| (h ^ *x as u64).wrapping_mul(0x100000001b3))
+}
+
+/// The circuit registered for playback doesn't match the structure of the
+/// record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordMismatch {
+    Registrations { recorded: usize, registered: usize },
+    Place { place: Place, values_count: usize },
+    Arity,
+}
+
+impl std::fmt::Display for RecordMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordMismatch::Registrations {
+                recorded,
+                registered,
+            } => write!(
+                f,
+                "Record mismatch: {} registrations were recorded, but {} were registered.",
+                recorded, registered
+            ),
+            RecordMismatch::Place {
+                place,
+                values_count,
+            } => write!(
+                f,
+                "Record mismatch: place {:?} is beyond the {} recorded values.",
+                place, values_count
+            ),
+            RecordMismatch::Arity => write!(
+                f,
+                "Record mismatch: the input and output counts of the registrations differ from the recorded ones."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordMismatch {}
+
+/// The differences between two records, see `ResolutionRecord::diff`. The
+/// counts are given as `(self, other)` pairs, set only when they differ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordDiff {
+    pub registrations_count: Option<(usize, usize)>,
+    pub values_count: Option<(usize, usize)>,
+    pub items_len: Option<(usize, usize)>,
+    /// The index of the first item that differs, with its differing fields.
+    /// Only the items both records hold are compared.
+    pub first_item: Option<(usize, Vec<ItemFieldDiff>)>,
+}
+
+impl RecordDiff {
+    pub fn is_empty(&self) -> bool {
+        self.registrations_count.is_none()
+            && self.values_count.is_none()
+            && self.items_len.is_none()
+            && self.first_item.is_none()
+    }
+}
+
+/// A differing field of a record item, as a `(self, other)` pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemFieldDiff {
+    AddedAt(RegistrationNum, RegistrationNum),
+    AcceptedAt(RegistrationNum, RegistrationNum),
+    OrderLen(usize, usize),
+    OrderIx(OrderIx, OrderIx),
+    Parallelism(u16, u16),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordSummary {
+    pub registrations_count: usize,
+    pub values_count: usize,
+    pub items_len: usize,
+}
+
+/// Counts of the registrations internalized, i.e. that had all their inputs
+/// tracked and were passed to the guide, and of the resolvers moved into the
+/// execution order.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlushReport {
+    pub internalized: u64,
+    pub ordered: u64,
+}
+
+/// A registration that wasn't internalized yet, because `waiting_on`, its
+/// greatest input, isn't tracked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingInfo {
+    pub registration: RegistrationNum,
+    pub outputs: Vec<Place>,
+    pub waiting_on: Place,
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ParallelismReport {
+    pub registrations: usize,
+    pub min: u16,
+    pub max: u16,
+    pub mean: f64,
 }
 
 pub trait ResolutionRecordWriter {
+    /// Called once the resolution is done. When streaming, the record holds
+    /// only the items that weren't streamed before, and those were just passed
+    /// to `store_items`.
     fn store(&mut self, record: &ResolutionRecord);
+
+    /// When set, the recording sorter streams the record during registration:
+    /// completed items are passed to `store_items` in registration order, in
+    /// chunks of at least this many items, and dropped from memory.
+    fn stream_chunk_size(&self) -> Option<usize> {
+        None
+    }
+
+    fn store_items(&mut self, _items: &[ResolutionRecordItem]) {}
 }
 
 pub trait ResolutionRecordSource {
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    hash::Hash,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use super::{ResolutionRecord, ResolutionRecordSource, ResolutionRecordWriter};
+
+/// Keeps resolution records in memory by a user id. Clones share the same
+/// records, so a storage can hand out a writer for a recording run and the
+/// sources for the playback runs of the same circuit.
+#[derive(Debug)]
+pub struct InMemoryRecordStorage<Id> {
+    records: Arc<Mutex<HashMap<Id, Arc<ResolutionRecord>>>>,
+}
+
+impl<Id> Clone for InMemoryRecordStorage<Id> {
+    fn clone(&self) -> Self {
+        Self {
+            records: Arc::clone(&self.records),
+        }
+    }
+}
+
+impl<Id: Eq + Hash> Default for InMemoryRecordStorage<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id: Eq + Hash> InMemoryRecordStorage<Id> {
+    pub fn new() -> Self {
+        Self {
+            records: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Stores `record` under `id`, returning the record it replaces.
+    pub fn insert(&self, id: Id, record: ResolutionRecord) -> Option<Arc<ResolutionRecord>> {
+        self.records.lock().unwrap().insert(id, Arc::new(record))
+    }
+
+    pub fn get(&self, id: &Id) -> Option<Arc<ResolutionRecord>> {
+        self.records.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn remove(&self, id: &Id) -> Option<Arc<ResolutionRecord>> {
+        self.records.lock().unwrap().remove(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A writer for a recording run, which stores the record under `id` once
+    /// the resolution is done.
+    pub fn writer(&self, id: Id) -> InMemoryRecordWriter<Id> {
+        InMemoryRecordWriter {
+            storage: self.clone(),
+            id,
+        }
+    }
+
+    /// A source for a playback run of the record stored under `id`.
+    pub fn source(&self, id: &Id) -> Option<InMemoryRecordSource> {
+        self.get(id).map(InMemoryRecordSource)
+    }
+}
+
+/// Stores the record into an `InMemoryRecordStorage`. Doesn't stream, so the
+/// whole record is kept in memory during the recording.
+#[derive(Debug)]
+pub struct InMemoryRecordWriter<Id> {
+    storage: InMemoryRecordStorage<Id>,
+    id: Id,
+}
+
+impl<Id: Eq + Hash + Clone> ResolutionRecordWriter for InMemoryRecordWriter<Id> {
+    fn store(&mut self, record: &ResolutionRecord) {
+        self.storage.insert(self.id.clone(), record.clone());
+    }
+}
+
+/// A shared handle to a record in an `InMemoryRecordStorage`.
+#[derive(Clone, Debug)]
+pub struct InMemoryRecordSource(pub Arc<ResolutionRecord>);
+
+impl ResolutionRecordSource for InMemoryRecordSource {
+    fn get(&self) -> &ResolutionRecord {
+        &self.0
+    }
+}
+
+/// Persists resolution records to a directory, one file per id, so a record
+/// can be played back across process restarts. Records are loaded lazily on
+/// `get` and cached. Clones share the cache.
+#[derive(Debug)]
+pub struct FileRecordStorage<Id> {
+    dir: PathBuf,
+    cache: InMemoryRecordStorage<Id>,
+}
+
+impl<Id> Clone for FileRecordStorage<Id> {
+    fn clone(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<Id: Eq + Hash + Clone + Display> FileRecordStorage<Id> {
+    /// Opens the storage in `dir`, creating the directory if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            cache: InMemoryRecordStorage::new(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file the record of `id` is stored in.
+    pub fn path(&self, id: &Id) -> PathBuf {
+        self.dir.join(format!("{}.rrec", id))
+    }
+
+    /// Writes `record` to the file of `id`. The file is replaced atomically,
+    /// so a concurrent `get` reads either the previous or the new record.
+    pub fn insert(&self, id: Id, record: ResolutionRecord) -> std::io::Result<()> {
+        let path = self.path(&id);
+        let tmp = path.with_extension("rrec.tmp");
+
+        let mut w = BufWriter::new(std::fs::File::create(&tmp)?);
+        record.write_to(&mut w)?;
+        w.flush()?;
+        drop(w);
+
+        std::fs::rename(&tmp, &path)?;
+
+        self.cache.insert(id, record);
+
+        Ok(())
+    }
+
+    /// The record of `id`, loaded from its file unless it's already cached.
+    pub fn get(&self, id: &Id) -> std::io::Result<Arc<ResolutionRecord>> {
+        if let Some(record) = self.cache.get(id) {
+            return Ok(record);
+        }
+
+        let r = BufReader::new(std::fs::File::open(self.path(id))?);
+        let record = ResolutionRecord::read_from(r)?;
+
+        self.cache.insert(id.clone(), record);
+
+        Ok(self.cache.get(id).unwrap())
+    }
+
+    /// Drops the cached record of `id`, keeping its file.
+    pub fn evict(&self, id: &Id) {
+        self.cache.remove(id);
+    }
+
+    /// A writer for a recording run, which writes the record to the file of
+    /// `id` once the resolution is done.
+    pub fn writer(&self, id: Id) -> FileRecordWriter<Id> {
+        FileRecordWriter {
+            storage: self.clone(),
+            id,
+        }
+    }
+
+    /// A source for a playback run of the record of `id`.
+    pub fn source(&self, id: &Id) -> std::io::Result<InMemoryRecordSource> {
+        self.get(id).map(InMemoryRecordSource)
+    }
+}
+
+/// Writes the record into a `FileRecordStorage`. Panics if the record can't be
+/// written, as `ResolutionRecordWriter::store` can't report errors.
+#[derive(Debug)]
+pub struct FileRecordWriter<Id> {
+    storage: FileRecordStorage<Id>,
+    id: Id,
+}
+
+impl<Id: Eq + Hash + Clone + Display> ResolutionRecordWriter for FileRecordWriter<Id> {
+    fn store(&mut self, record: &ResolutionRecord) {
+        if let Err(e) = self.storage.insert(self.id.clone(), record.clone()) {
+            panic!(
+                "Couldn't write the resolution record to {:?}: {}",
+                self.storage.path(&self.id),
+                e
+            );
+        }
+    }
+}
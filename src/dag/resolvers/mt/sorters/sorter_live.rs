@@ -3,8 +3,12 @@
 
 use std::{
     cell::UnsafeCell,
+    collections::BTreeMap,
     marker::PhantomData,
-    sync::{atomic::AtomicIsize, Arc, Mutex},
+    sync::{
+        atomic::{AtomicIsize, AtomicU64},
+        Arc, Mutex,
+    },
 };
 
 use itertools::Itertools;
@@ -14,21 +18,29 @@ use crate::{
     cs::{traits::cs::DstBuffer, Place, Variable, VariableType},
     dag::{
         awaiters::AwaitersBroker,
-        guide::{BufferGuide, GuideLoc, GuideMetadata, GuideOrder, OrderInfo, RegistrationNum},
-        primitives::{ExecOrder, Metadata, ResolverIx, Values},
+        guide::{
+            BufferGuide, GuideLoc, GuideMetadata, GuideOrder, GuideTrackId, OrderInfo,
+            RegistrationNum,
+        },
+        primitives::{ExecOrder, Metadata, ResolverIx, ValueCells, ValueStore, Values},
         resolver_box::{invocation_binder, ResolverBox},
         resolvers::mt::{
-            registrar::Registrar, resolution_window::RWConfigRecord, sorters::ResolutionRecordItem,
-            ResolverCommonData, ResolverComms,
+            registrar::Registrar,
+            resolution_window::{RWConfig, RWConfigRecord, Spawner},
+            sorters::ResolutionRecordItem,
+            ResolveSubscriptions, ResolverCommonData, ResolverComms,
         },
-        CircuitResolverOpts,
+        CapacityPolicy, CircuitResolverOpts, CpuSet, DuplicateSetPolicy, IdlePolicy, PanicPolicy,
+        Priority,
     },
     field::SmallField,
     log,
     utils::{PipeOp, UnsafeCellEx},
 };
 
-use super::{ResolutionRecord, ResolutionRecordWriter, ResolverSortingMode};
+use super::{
+    FlushReport, PendingInfo, ResolutionRecord, ResolutionRecordWriter, ResolverSortingMode,
+};
 
 #[derive(Debug)]
 struct Stats {
@@ -53,35 +65,135 @@ impl Stats {
     }
 }
 
+/// Tracks the record items that were streamed to the writer, see
+/// `ResolutionRecordWriter::stream_chunk_size`.
+#[derive(Default)]
+struct RecordStream {
+    /// Zero when the record isn't streamed.
+    chunk_size: usize,
+    /// The number of leading items that were passed to the writer and dropped
+    /// from the record.
+    base: usize,
+    /// Whether the retained items were written to the execution order.
+    written: Vec<bool>,
+    /// The number of leading retained items that are written.
+    done: usize,
+}
+
+impl RecordStream {
+    fn item<'a>(
+        &mut self,
+        record: &'a mut ResolutionRecord,
+        added_at: usize,
+    ) -> &'a mut ResolutionRecordItem {
+        let ix = added_at - self.base;
+
+        // The record is preallocated when not streamed, but may still run out
+        // if the values storage grows.
+        if record.items.len() <= ix {
+            record
+                .items
+                .resize_with(ix + 1, ResolutionRecordItem::default);
+        }
+
+        if self.chunk_size > 0 && self.written.len() <= ix {
+            self.written.resize(ix + 1, false);
+        }
+
+        &mut record.items[ix]
+    }
+
+    fn mark_written(&mut self, added_at: usize) {
+        if self.chunk_size > 0 {
+            self.written[added_at - self.base] = true;
+        }
+    }
+}
+
+/// The part of the execution order that is not yet published to the
+/// resolution window, see `CircuitResolverOpts::internalize_batch_size`.
+struct PendingOrder {
+    items: Vec<OrderInfo<ResolverIx>>,
+    batch_size: usize,
+}
+
 pub struct NullRecordWriter();
 impl ResolutionRecordWriter for NullRecordWriter {
     fn store(&mut self, _record: &ResolutionRecord) {}
 }
 
-pub struct LiveResolverSorter<F: SmallField, Cfg: CSResolverConfig>(
-    LiveRecordingResolverSorter<F, Cfg, NullRecordWriter>,
+/// `T` is the tracker stored in the metadata of each value. A
+/// `CompactGuideLoc` reduces the metadata size for resolvers it fits.
+pub struct LiveResolverSorter<F: SmallField, Cfg: CSResolverConfig, T: GuideTrackId = GuideLoc>(
+    LiveRecordingResolverSorter<F, Cfg, NullRecordWriter, T>,
 );
 
-impl<F: SmallField, Cfg: CSResolverConfig> ResolverSortingMode<F> for LiveResolverSorter<F, Cfg> {
+impl<F: SmallField, Cfg: CSResolverConfig, T: GuideTrackId + 'static> LiveResolverSorter<F, Cfg, T>
+where
+    RWConfigRecord<T>: RWConfig<T>,
+{
+    pub(crate) fn new_with_storage(
+        opts: CircuitResolverOpts,
+        comms: Arc<ResolverComms>,
+        debug_track: &[Place],
+        spawner: &dyn Spawner,
+        storage: Option<Box<dyn ValueStore<F, T>>>,
+    ) -> (Self, Arc<ResolverCommonData<F, T>>) {
+        let (this, common) = LiveRecordingResolverSorter::new_with_storage(
+            (opts, NullRecordWriter()),
+            comms,
+            debug_track,
+            spawner,
+            storage,
+        );
+
+        (Self(this), common)
+    }
+
+    pub(crate) fn options(&self) -> &CircuitResolverOpts {
+        &self.0.options
+    }
+}
+
+impl<F: SmallField, Cfg: CSResolverConfig, T: GuideTrackId + 'static> ResolverSortingMode<F>
+    for LiveResolverSorter<F, Cfg, T>
+where
+    RWConfigRecord<T>: RWConfig<T>,
+{
     type Arg = CircuitResolverOpts;
-    type Config = RWConfigRecord<GuideLoc>;
-    type TrackId = GuideLoc;
+    type Config = RWConfigRecord<T>;
+    type TrackId = T;
 
     fn new(
         opts: Self::Arg,
         comms: Arc<ResolverComms>,
         debug_track: &[Place],
+        spawner: &dyn Spawner,
     ) -> (Self, Arc<ResolverCommonData<F, Self::TrackId>>) {
-        let (this, common) =
-            LiveRecordingResolverSorter::new((opts, NullRecordWriter()), comms, debug_track);
+        Self::new_with_storage(opts, comms, debug_track, spawner, None)
+    }
 
-        (Self(this), common)
+    fn clear(
+        &mut self,
+        comms: Arc<ResolverComms>,
+        debug_track: &[Place],
+        spawner: &dyn Spawner,
+    ) -> Arc<ResolverCommonData<F, Self::TrackId>> {
+        self.0.clear(comms, debug_track, spawner)
     }
 
     fn set_value(&mut self, key: Place, value: F) {
         self.0.set_value(key, value)
     }
 
+    fn declare_tracked_prefix(&mut self, len: usize) {
+        self.0.declare_tracked_prefix(len)
+    }
+
+    fn declare_hole(&mut self, place: Place) {
+        self.0.declare_hole(place)
+    }
+
     fn add_resolution<Fn>(&mut self, inputs: &[Place], outputs: &[Place], f: Fn)
     where
         Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync,
@@ -89,6 +201,19 @@ impl<F: SmallField, Cfg: CSResolverConfig> ResolverSortingMode<F> for LiveResolv
         self.0.add_resolution(inputs, outputs, f)
     }
 
+    fn add_resolution_prioritized<Fn>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        priority: Priority,
+        f: Fn,
+    ) where
+        Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync,
+    {
+        self.0
+            .add_resolution_prioritized(inputs, outputs, priority, f)
+    }
+
     fn internalize(
         &mut self,
         resolver_ix: ResolverIx,
@@ -114,139 +239,220 @@ impl<F: SmallField, Cfg: CSResolverConfig> ResolverSortingMode<F> for LiveResolv
         self.0.flush()
     }
 
+    fn progress(&self) -> FlushReport {
+        self.0.progress()
+    }
+
+    fn pending_registrations(&self) -> Vec<PendingInfo> {
+        self.0.pending_registrations()
+    }
+
     fn final_flush(&mut self) {
         self.0.final_flush()
     }
 
-    fn retrieve_sequence(&mut self) -> &ResolutionRecord {
+    fn retrieve_sequence(&self) -> &ResolutionRecord {
         self.0.retrieve_sequence()
     }
 
+    fn take_sequence(&mut self) -> ResolutionRecord {
+        self.0.take_sequence()
+    }
+
     fn write_sequence(&mut self) {
         self.0.write_sequence()
     }
+
+    fn worker_affinity(&self) -> Option<CpuSet> {
+        self.0.worker_affinity()
+    }
+
+    fn capacity_policy(&self) -> CapacityPolicy {
+        self.0.capacity_policy()
+    }
+
+    fn duplicate_set_policy(&self) -> DuplicateSetPolicy {
+        self.0.duplicate_set_policy()
+    }
+
+    fn track_fanout(&self) -> bool {
+        self.0.track_fanout()
+    }
+
+    fn track_dependencies(&self) -> bool {
+        self.0.track_dependencies()
+    }
+
+    fn max_pending_resolvers(&self) -> Option<usize> {
+        self.0.max_pending_resolvers()
+    }
+
+    fn panic_policy(&self) -> PanicPolicy {
+        self.0.panic_policy()
+    }
+
+    fn idle_policy(&self) -> IdlePolicy {
+        self.0.idle_policy()
+    }
+
+    fn prioritize_awaited(&self) -> bool {
+        self.0.prioritize_awaited()
+    }
+
+    fn silent(&self) -> bool {
+        self.0.silent()
+    }
+
+    fn reserve(&mut self, expected_registrations: usize) {
+        self.0.reserve(expected_registrations)
+    }
 }
 
 pub struct LiveRecordingResolverSorter<
     F: SmallField,
     Cfg: CSResolverConfig,
     RW: ResolutionRecordWriter,
+    T: GuideTrackId = GuideLoc,
 > {
     stats: Stats,
     comms: Arc<ResolverComms>,
     options: CircuitResolverOpts,
     debug_track: Vec<Place>,
-    pub(crate) common: Arc<ResolverCommonData<F, GuideLoc>>,
+    pub(crate) common: Arc<ResolverCommonData<F, T>>,
     pub(crate) registrar: Registrar,
     pub(crate) guide: BufferGuide<ResolverIx, F, Cfg>,
     record: ResolutionRecord,
     record_writer: RW,
-    /// Tracks the size of the execution order written.
+    record_stream: RecordStream,
+    /// Tracks the size of the execution order written, including the pending
+    /// part.
     order_len: usize,
+    pending_order: PendingOrder,
+    internalized: u64,
+    /// The priorities of the delayed registrations that aren't `Normal`.
+    priorities: BTreeMap<ResolverIx, Priority>,
     field: PhantomData<F>,
 }
 
-impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter>
-    LiveRecordingResolverSorter<F, Cfg, RW>
+impl<
+        F: SmallField,
+        Cfg: CSResolverConfig,
+        RW: ResolutionRecordWriter,
+        T: GuideTrackId + 'static,
+    > LiveRecordingResolverSorter<F, Cfg, RW, T>
 {
     fn write_order<'a, GO: GuideOrder<'a, ResolverIx>>(
         tgt: &Mutex<ExecOrder>,
         record: &mut ResolutionRecord,
+        record_stream: &mut RecordStream,
         tgt_len: &mut usize,
+        pending: &mut PendingOrder,
         resolvers: &UnsafeCell<ResolverBox<F>>,
         order: &GO,
         buffer_hint: &AtomicIsize,
+        force: bool,
     ) {
         if order.size() > 0 {
-            let mut exec_order = tgt.lock().unwrap();
-            let tgt = &mut exec_order.items;
-            let len = tgt.len();
-            tgt.resize(
-                len + order.size(),
+            let len = *tgt_len;
+            let start = pending.items.len();
+            pending.items.resize(
+                start + order.size(),
                 OrderInfo::new(ResolverIx::default(), GuideMetadata::new(0, 0, 0)),
             );
 
-            order.write(&mut tgt[..]);
+            order.write_from(&mut pending.items[..], len - start);
 
-            for (i, nfo) in tgt[len..].iter().enumerate() {
-                let ri = &mut record.items[nfo.metadata.added_at() as usize];
+            for (i, nfo) in pending.items[start..].iter().enumerate() {
+                let ri = record_stream.item(record, nfo.metadata.added_at() as usize);
 
                 ri.added_at = nfo.metadata.added_at();
                 ri.accepted_at = nfo.metadata.accepted_at();
                 ri.order_ix = (i + len).into();
                 ri.parallelism = nfo.metadata.parallelism() as u16;
+
+                record_stream.mark_written(nfo.metadata.added_at() as usize);
             }
 
-            if crate::dag::resolvers::mt::PARANOIA {
-                for i in len..len + order.size() {
-                    if tgt[i].value == ResolverIx(0) {
-                        log!(
-                            "CR: resolver {} added to order at ix {}, during write {}..{}.",
-                            tgt[i].value.0,
-                            i,
-                            len,
-                            len + order.size()
-                        );
-                    }
+            // This value is an optimization, it is not behind a mutex and used on each
+            // registration for record purposes.
+            *tgt_len = len + order.size();
+        }
+
+        if pending.items.len() == 0 || (pending.items.len() < pending.batch_size && force == false)
+        {
+            return;
+        }
+
+        let mut exec_order = tgt.lock().unwrap();
+        let tgt = &mut exec_order.items;
+        let len = tgt.len();
+        tgt.extend_from_slice(&pending.items);
+
+        if crate::dag::resolvers::mt::PARANOIA {
+            for i in len..tgt.len() {
+                if tgt[i].value == ResolverIx(0) {
+                    log!(
+                        "CR: resolver {} added to order at ix {}, during write {}..{}.",
+                        tgt[i].value.0,
+                        i,
+                        len,
+                        tgt.len()
+                    );
                 }
             }
+        }
 
-            if cfg!(cr_paranoia_mode) {
-                // This ugly block checks that the calculated parallelism is
-                // correct. It's a bit slower than O(n^2). Also note, that it
-                // checks only the last 1050 items, so it's not a full check,
-                // 'twas when the desired parallelism was set to 1024, but it's
-                // not anymore.
-                unsafe {
-                    for r_ix in std::cmp::max(0, len as i32 - 1050) as usize..tgt.len() {
-                        let r = resolvers.u_deref().get(tgt[r_ix].value);
-
-                        for derivative in
-                            r_ix..std::cmp::min(r_ix + tgt[r_ix].metadata.parallelism(), tgt.len())
-                        {
-                            let r_d = resolvers.u_deref().get(tgt[derivative].value);
-
-                            assert!(r.outputs().iter().all(|x| r_d.inputs().contains(x) == false),
-                                "Parallelism violation at ix {}, val {:#?}, derivative ix {} , val {:#?}: {:#?}",
-                                r_ix,
-                                tgt[r_ix],
-                                derivative,
-                                tgt[derivative],
-                                (std::cmp::max(0, len as i32 - 1050) as usize..tgt.len())
-                                    .map(|x| (x, resolvers.u_deref().get(tgt[x].value)))
-                                    .map(|(i, r)| (i, tgt[i], r.inputs().to_vec(), r.outputs().to_vec()))
-                                    .collect_vec()
-                            );
-                        }
+        if cfg!(cr_paranoia_mode) {
+            // This ugly block checks that the calculated parallelism is
+            // correct. It's a bit slower than O(n^2). Also note, that it
+            // checks only the last 1050 items, so it's not a full check,
+            // 'twas when the desired parallelism was set to 1024, but it's
+            // not anymore.
+            unsafe {
+                for r_ix in std::cmp::max(0, len as i32 - 1050) as usize..tgt.len() {
+                    let r = resolvers.u_deref().get(tgt[r_ix].value);
+
+                    for derivative in
+                        r_ix..std::cmp::min(r_ix + tgt[r_ix].metadata.parallelism(), tgt.len())
+                    {
+                        let r_d = resolvers.u_deref().get(tgt[derivative].value);
+
+                        assert!(r.outputs().iter().all(|x| r_d.inputs().contains(x) == false),
+                            "Parallelism violation at ix {}, val {:#?}, derivative ix {} , val {:#?}: {:#?}",
+                            r_ix,
+                            tgt[r_ix],
+                            derivative,
+                            tgt[derivative],
+                            (std::cmp::max(0, len as i32 - 1050) as usize..tgt.len())
+                                .map(|x| (x, resolvers.u_deref().get(tgt[x].value)))
+                                .map(|(i, r)| (i, tgt[i], r.inputs().to_vec(), r.outputs().to_vec()))
+                                .collect_vec()
+                        );
                     }
                 }
             }
+        }
 
-            // This value is an optimization, it is not behind a mutex and used on each
-            // registration for record purposes.
-            *tgt_len = tgt.len();
+        debug_assert_eq!(*tgt_len, tgt.len());
 
-            exec_order.size = *tgt_len;
+        exec_order.size = tgt.len();
 
-            drop(exec_order);
+        drop(exec_order);
 
-            buffer_hint.store(1, std::sync::atomic::Ordering::Relaxed);
-        }
-    }
-}
+        pending.items.clear();
 
-impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverSortingMode<F>
-    for LiveRecordingResolverSorter<F, Cfg, RW>
-{
-    type Arg = (CircuitResolverOpts, RW);
-    type Config = RWConfigRecord<GuideLoc>;
-    type TrackId = GuideLoc;
+        buffer_hint.store(1, std::sync::atomic::Ordering::Relaxed);
+    }
 
-    fn new(
-        arg: Self::Arg,
+    /// Uses `storage` for the values instead of allocating them, see
+    /// `MtCircuitResolver::with_value_storage`.
+    pub(crate) fn new_with_storage(
+        arg: (CircuitResolverOpts, RW),
         comms: Arc<ResolverComms>,
         debug_track: &[Place],
-    ) -> (Self, Arc<ResolverCommonData<F, Self::TrackId>>) {
+        spawner: &dyn Spawner,
+        storage: Option<Box<dyn ValueStore<F, T>>>,
+    ) -> (Self, Arc<ResolverCommonData<F, T>>) {
         fn new_values<V>(size: usize, default: fn() -> V) -> Box<[V]> {
             // TODO: ensure mem-page multiple capacity.
             let mut values = Vec::with_capacity(size);
@@ -255,12 +461,107 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
             values.into_boxed_slice()
         }
 
+        /// Initializes the values from all available cores, so the OS places
+        /// the pages on the NUMA nodes of the touching threads. With an
+        /// `affinity`, from a thread pinned to each of its CPUs instead, so
+        /// the pages land on the nodes of the resolution window workers.
+        fn new_values_first_touch<V: Send + 'static>(
+            size: usize,
+            default: fn() -> V,
+            spawner: &dyn Spawner,
+            affinity: Option<CpuSet>,
+            silent: bool,
+        ) -> Box<[V]> {
+            let cores = affinity.map(|x| x.iter().collect_vec()).unwrap_or_default();
+            let threads = match cores.len() {
+                0 => std::thread::available_parallelism()
+                    .map(|x| x.get())
+                    .unwrap_or(1),
+                x => x,
+            };
+            let chunk_size = std::cmp::max(1, (size + threads - 1) / threads);
+
+            let mut values = Vec::<V>::with_capacity(size);
+            let base = values.as_mut_ptr() as usize;
+
+            let handles = (0..threads)
+                .map(|i| (i, i * chunk_size, std::cmp::min((i + 1) * chunk_size, size)))
+                .filter(|(_, start, end)| start < end)
+                .map(|(i, start, end)| {
+                    #[cfg(feature = "worker_affinity")]
+                    let core = cores.get(i).map(|&id| core_affinity::CoreId { id });
+                    #[cfg(not(feature = "worker_affinity"))]
+                    let _ = (i, silent);
+
+                    spawner.spawn(Box::new(move || {
+                        #[cfg(feature = "worker_affinity")]
+                        if let Some(core) = core {
+                            if core_affinity::set_for_current(core) == false && silent == false {
+                                log!("CR: failed to pin the first touch to core {}.", core.id);
+                            }
+                        }
+
+                        let base = base as *mut V;
+
+                        // Safety: The chunks are disjoint and within the
+                        // capacity, and the vector isn't touched until all
+                        // the threads are joined.
+                        (start..end).for_each(|i| unsafe { base.add(i).write(default()) });
+                    }))
+                })
+                .collect_vec();
+
+            handles
+                .into_iter()
+                .for_each(|x| x.join().expect("First touch of the values panicked."));
+
+            // Safety: All `size` items were initialized by the threads above.
+            unsafe { values.set_len(size) };
+            values.into_boxed_slice()
+        }
+
         let (opts, rw) = arg;
 
+        assert!(
+            T::fits(&opts),
+            "The resolver options exceed the range of the track id."
+        );
+
+        if cfg!(not(feature = "numa")) && opts.numa_first_touch && opts.silent == false {
+            log!("CR: NUMA first touch is ignored, `numa` feature is disabled.");
+        }
+
+        let default_value = || UnsafeCell::new((F::from_u64_unchecked(0), Metadata::default()));
+
         let values = Values {
-            variables: new_values(opts.max_variables, || {
-                UnsafeCell::new((F::from_u64_unchecked(0), Metadata::default()))
-            }),
+            variables: match storage.map(ValueCells::new) {
+                Some(storage) => {
+                    assert_eq!(
+                        opts.max_variables,
+                        storage.len(),
+                        "The value storage size doesn't match `max_variables`."
+                    );
+                    debug_assert!(
+                        storage
+                            .iter()
+                            .all(|x| unsafe { &*x.get() }.1.is_tracked() == false),
+                        "The value storage contains tracked values."
+                    );
+
+                    storage
+                }
+                None => match cfg!(feature = "numa") && opts.numa_first_touch {
+                    true => new_values_first_touch(
+                        opts.max_variables,
+                        default_value,
+                        spawner,
+                        opts.worker_affinity,
+                        opts.silent,
+                    ),
+                    false => new_values(opts.max_variables, default_value),
+                }
+                .into(),
+            },
             max_tracked: -1,
         };
 
@@ -274,21 +575,39 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
             values: UnsafeCell::new(values),
             exec_order: Mutex::new(exec_order),
             awaiters_broker: AwaitersBroker::new(),
+            resolved_count: AtomicU64::new(0),
+            subscriptions: ResolveSubscriptions::new(),
+            failed: Mutex::new(Vec::new()),
+            #[cfg(feature = "profile-resolvers")]
+            timings: Mutex::new(Vec::new()),
         }
         .to(Arc::new);
 
         let s = Self {
             stats: Stats::new(),
-            options: opts,
             debug_track: debug_track.to_vec(),
             common,
             comms,
-            record: ResolutionRecord::new(0, 0, opts.max_variables),
+            record: match rw.stream_chunk_size() {
+                Some(_) => ResolutionRecord::new(0, 0, 0),
+                None => ResolutionRecord::new(0, 0, opts.max_variables),
+            },
+            record_stream: RecordStream {
+                chunk_size: rw.stream_chunk_size().unwrap_or(0),
+                ..Default::default()
+            },
             record_writer: rw,
             guide: BufferGuide::new(opts.desired_parallelism),
-            registrar: Registrar::new(),
+            pending_order: PendingOrder {
+                items: Vec::new(),
+                batch_size: opts.internalize_batch_size,
+            },
+            registrar: Registrar::new(opts.scheduling_seed),
+            options: opts,
             field: PhantomData,
             order_len: 0,
+            internalized: 0,
+            priorities: BTreeMap::new(),
         };
 
         let c = Arc::clone(&s.common);
@@ -296,38 +615,74 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
         (s, c)
     }
 
-    fn set_value(&mut self, key: crate::cs::Place, value: F) {
-        if (cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA)
-            && self.debug_track.contains(&key)
-            && false
-        {
-            log!("CR: setting {:?} -> {:?}", key, value);
+    /// Passes the leading completed items to the writer once there are enough
+    /// of them. The last registration is held back, as `flush` updates it.
+    fn stream_record(&mut self) {
+        let stream = &mut self.record_stream;
+
+        if stream.chunk_size == 0 {
+            return;
         }
 
-        match key.get_type() {
-            VariableType::CopyableVariable => self.stats.values_added += 1,
-            VariableType::Witness => self.stats.witnesses_added += 1,
+        let limit = self.stats.registrations_added as usize - 1 - stream.base;
+
+        while stream.done < limit && stream.written[stream.done] {
+            stream.done += 1;
         }
 
-        // Safety: Dereferencing as &mut in mutable context. This thread doesn't hold any
-        // references to `self.resolvers`. Other thread may hold shared references, but
-        // are guaranteed to not access the same underlying data.
-        let values = unsafe { self.common.values.u_deref_mut() };
+        if stream.done >= stream.chunk_size {
+            self.record_writer
+                .store_items(&self.record.items[..stream.done]);
 
-        values.set_value(key, value);
+            self.record.items.drain(..stream.done);
+            stream.written.drain(..stream.done);
+            stream.base += stream.done;
+            stream.done = 0;
+        }
+    }
+}
 
-        // Safety: using as shared, assuming no &mut references to
-        // `self.resolvers` (Only this thread requires mut, and we're not
-        // currently doing that).
-
-        let delayed_resolvers =
-            if values.max_tracked >= 0 {
-                self.registrar.advance(values.max_tracked.to(|x| {
-                    Place::from_variable(Variable::from_variable_index(x.try_into().unwrap()))
-                }))
-            } else {
-                vec![]
-            };
+impl<F, Cfg, RW, T> LiveRecordingResolverSorter<F, Cfg, RW, T>
+where
+    F: SmallField,
+    Cfg: CSResolverConfig,
+    RW: ResolutionRecordWriter,
+    T: GuideTrackId + 'static,
+    RWConfigRecord<T>: RWConfig<T>,
+{
+    /// Orders released registrations by priority, the highest first, or last
+    /// when `reversed`. Registrations of the same priority keep their order.
+    fn take_priorities(&mut self, resolvers: &mut [ResolverIx], reversed: bool) {
+        if self.priorities.is_empty() {
+            return;
+        }
+
+        let mut priority = |x: &ResolverIx| self.priorities.remove(x).unwrap_or_default();
+
+        match reversed {
+            false => resolvers.sort_by_cached_key(|x| priority(x)),
+            true => resolvers.sort_by_cached_key(|x| std::cmp::Reverse(priority(x))),
+        }
+    }
+
+    /// Internalizes the delayed registrations whose inputs are all tracked
+    /// now.
+    fn release_delayed(&mut self) {
+        // Safety: Dereferencing as shared, the values are written only by
+        // this thread.
+        let max_tracked = unsafe { self.common.values.u_deref() }.max_tracked;
+
+        // This values starts from -1, which is illegal.
+        if max_tracked < 0 {
+            return;
+        }
+
+        let mut delayed_resolvers =
+            self.registrar.advance(max_tracked.to(|x| {
+                Place::from_variable(Variable::from_variable_index(x.try_into().unwrap()))
+            }));
+
+        self.take_priorities(&mut delayed_resolvers, false);
 
         unsafe {
             // Safety: Dereferencing as shared, not accessing `resolve_fn`.
@@ -347,14 +702,196 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
                 .for_each(|(r, i, o, a)| self.internalize(r, i, o, a));
         }
     }
+}
+
+impl<F, Cfg, RW, T> ResolverSortingMode<F> for LiveRecordingResolverSorter<F, Cfg, RW, T>
+where
+    F: SmallField,
+    Cfg: CSResolverConfig,
+    RW: ResolutionRecordWriter,
+    T: GuideTrackId + 'static,
+    RWConfigRecord<T>: RWConfig<T>,
+{
+    type Arg = (CircuitResolverOpts, RW);
+    type Config = RWConfigRecord<T>;
+    type TrackId = T;
+
+    fn new(
+        arg: Self::Arg,
+        comms: Arc<ResolverComms>,
+        debug_track: &[Place],
+        spawner: &dyn Spawner,
+    ) -> (Self, Arc<ResolverCommonData<F, Self::TrackId>>) {
+        Self::new_with_storage(arg, comms, debug_track, spawner, None)
+    }
+
+    /// Rewinds the sorter in place, keeping the values allocation and the
+    /// record writer, which then stores the record of the next resolution.
+    fn clear(
+        &mut self,
+        _comms: Arc<ResolverComms>,
+        _debug_track: &[Place],
+        _spawner: &dyn Spawner,
+    ) -> Arc<ResolverCommonData<F, Self::TrackId>> {
+        // Safety: The resolution window is joined, so this thread is the only
+        // one accessing the common data.
+        unsafe { self.common.reset() };
+
+        // The items are appended, unlike in the playback.
+        self.common.exec_order.lock().unwrap().items.clear();
+
+        self.stats = Stats::new();
+        self.record = match self.record_writer.stream_chunk_size() {
+            Some(_) => ResolutionRecord::new(0, 0, 0),
+            None => ResolutionRecord::new(0, 0, self.options.max_variables),
+        };
+        self.record_stream = RecordStream {
+            chunk_size: self.record_writer.stream_chunk_size().unwrap_or(0),
+            ..Default::default()
+        };
+        self.guide = BufferGuide::new(self.options.desired_parallelism);
+        self.pending_order.items.clear();
+        self.registrar = Registrar::new(self.options.scheduling_seed);
+        self.order_len = 0;
+        self.internalized = 0;
+        self.priorities.clear();
+
+        self.common.clone()
+    }
+
+    fn set_value(&mut self, key: crate::cs::Place, value: F) {
+        if (cfg!(cr_paranoia_mode) || crate::dag::resolvers::mt::PARANOIA)
+            && self.debug_track.contains(&key)
+            && false
+        {
+            log!("CR: setting {:?} -> {:?}", key, value);
+        }
+
+        match key.get_type() {
+            VariableType::CopyableVariable => self.stats.values_added += 1,
+            VariableType::Witness => self.stats.witnesses_added += 1,
+        }
+
+        // Safety: Dereferencing as &mut in mutable context. This thread doesn't hold any
+        // references to `self.resolvers`. Other thread may hold shared references, but
+        // are guaranteed to not access the same underlying data.
+        let values = unsafe { self.common.values.u_deref_mut() };
+
+        let max_tracked = values.max_tracked;
+
+        values.set_value(key, value);
+
+        // The registrations up to an unchanged `max_tracked` were released
+        // already, e.g. for values set within a declared prefix.
+        if values.max_tracked > max_tracked {
+            self.release_delayed();
+        }
+    }
+
+    fn declare_tracked_prefix(&mut self, len: usize) {
+        // Safety: Dereferencing as &mut in mutable context, see `set_value`.
+        let values = unsafe { self.common.values.u_deref_mut() };
+        // Safety: Dereferencing as shared, not accessing `resolve_fn`.
+        let rb = unsafe { self.common.resolvers.u_deref() };
+
+        // Those would be released with unset inputs.
+        let waiting = self
+            .registrar
+            .peek_vars()
+            .values()
+            .flatten()
+            // Safety: `x` was pushed to the box and is still held by the
+            // registrar.
+            .map(|x| unsafe { rb.get(*x) })
+            .find(|x| {
+                x.inputs()
+                    .iter()
+                    .any(|x| x.raw_ix() < len && values.get_item_ref(*x).1.is_tracked() == false)
+            });
+
+        if let Some(r) = waiting {
+            // Stops the resolution, so the panic isn't followed by another one
+            // on drop.
+            self.comms
+                .cancelled
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            self.comms
+                .registration_complete
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+
+            panic!(
+                "Registration {} waits on a place in the declared prefix of {} places. \
+                 Declare the prefix before registering resolutions on it.",
+                r.added_at(),
+                len
+            );
+        }
+
+        values.declare_prefix(len);
+
+        self.release_delayed();
+    }
+
+    fn declare_hole(&mut self, place: Place) {
+        // Safety: Dereferencing as shared, not accessing `resolve_fn`.
+        let rb = unsafe { self.common.resolvers.u_deref() };
+
+        // Those would be released with the hole as an input.
+        let waiting = self
+            .registrar
+            .peek_vars()
+            .values()
+            .flatten()
+            // Safety: `x` was pushed to the box and is still held by the
+            // registrar.
+            .map(|x| unsafe { rb.get(*x) })
+            .find(|x| x.inputs().contains(&place));
+
+        if let Some(r) = waiting {
+            // Stops the resolution, so the panic isn't followed by another one
+            // on drop.
+            self.comms
+                .cancelled
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            self.comms
+                .registration_complete
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+
+            panic!(
+                "Registration {} takes {:?} as an input, which is declared as a hole.",
+                r.added_at(),
+                place
+            );
+        }
+
+        // Safety: Dereferencing as &mut in mutable context, see `set_value`.
+        unsafe { self.common.values.u_deref_mut() }.declare_hole(place);
+
+        self.release_delayed();
+    }
 
     fn add_resolution<Fn>(&mut self, inputs: &[Place], outputs: &[Place], f: Fn)
     where
         Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync,
+    {
+        self.add_resolution_prioritized(inputs, outputs, Priority::Normal, f)
+    }
+
+    fn add_resolution_prioritized<Fn>(
+        &mut self,
+        inputs: &[Place],
+        outputs: &[Place],
+        priority: Priority,
+        f: Fn,
+    ) where
+        Fn: FnOnce(&[F], &mut DstBuffer<'_, '_, F>) + Send + Sync,
     {
         debug_assert!(inputs
             .iter()
-            .all(|x| x.0 < self.options.max_variables as u64));
+            .all(|x| x.raw_ix() < unsafe { self.common.values.u_deref() }.variables.len()));
+
+        self.record.arity_hash =
+            ResolutionRecord::hash_arity(self.record.arity_hash, inputs.len(), outputs.len());
 
         // Safety: This thread is the only one to use `push` on the resolvers
         // and is the only thread to do so. `push` is the only mutable function
@@ -430,6 +967,10 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
             }
         }
 
+        if registrar_answer.is_err() && priority != Priority::Normal {
+            self.priorities.insert(resolver_ix, priority);
+        }
+
         if let Ok(resolver_ix) = registrar_answer {
             self.internalize(
                 resolver_ix,
@@ -439,8 +980,12 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
             );
         }
 
-        self.record.items[self.stats.registrations_added as usize].order_len = self.order_len;
+        self.record_stream
+            .item(&mut self.record, self.stats.registrations_added as usize)
+            .order_len = self.order_len;
         self.stats.registrations_added += 1;
+
+        self.stream_record();
     }
 
     fn internalize(
@@ -464,7 +1009,10 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
         while resolvers.len() > 0 {
             let (resolver_ix, inputs, outputs, added_at) = resolvers.pop().unwrap();
 
-            let new_resolvers = self.internalize_one(resolver_ix, inputs, outputs, added_at);
+            let mut new_resolvers = self.internalize_one(resolver_ix, inputs, outputs, added_at);
+
+            // The resolvers are popped from the back.
+            self.take_priorities(&mut new_resolvers, true);
 
             #[allow(clippy::collapsible_if)]
             if crate::dag::resolvers::mt::PARANOIA {
@@ -537,9 +1085,13 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
             println!("CR: resolver_ix {} pushed to guide.", resolver_ix.0);
         }
 
+        self.internalized += 1;
+
         let (guide_loc, order) = self.guide.push(
             resolver_ix,
-            deps.map(|x| x.tracker).reduce(std::cmp::max),
+            deps.map(|x| x.tracker)
+                .reduce(std::cmp::max)
+                .map(Into::into),
             // This stat represents the registration in which this registration
             // had all its dependencies tracked and safe to use. Thus, once we
             // reach this registration in playback, the resolution window can
@@ -553,13 +1105,16 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
         Self::write_order(
             &self.common.exec_order,
             &mut self.record,
+            &mut self.record_stream,
             &mut self.order_len,
+            &mut self.pending_order,
             &self.common.resolvers,
             &order,
             &self.comms.exec_order_buffer_hint,
+            false,
         );
 
-        values.track_values(outputs, guide_loc);
+        values.track_values(outputs, guide_loc.into());
 
         // This values starts from -1, which is illegal.
         if values.max_tracked >= 0 {
@@ -579,32 +1134,83 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
         Self::write_order(
             &self.common.exec_order,
             &mut self.record,
+            &mut self.record_stream,
             &mut self.order_len,
+            &mut self.pending_order,
             &self.common.resolvers,
             &order,
             &self.comms.exec_order_buffer_hint,
+            true,
         );
 
         drop(order);
 
         // `max` is here cause some tests aren't registering any resolvers.
         // Since flushing is an extremely rare operation, we just fix it here.
-        self.record.items[std::cmp::max(1, self.stats.registrations_added) as usize - 1]
+        self.record_stream
+            .item(
+                &mut self.record,
+                std::cmp::max(1, self.stats.registrations_added) as usize - 1,
+            )
             .order_len = self.order_len;
     }
 
+    fn progress(&self) -> FlushReport {
+        FlushReport {
+            internalized: self.internalized,
+            ordered: self.order_len as u64,
+        }
+    }
+
+    fn pending_registrations(&self) -> Vec<PendingInfo> {
+        // Safety: Dereferencing as shared, not accessing `resolve_fn`. The
+        // resolvers are pushed only by this thread.
+        let rb = unsafe { self.common.resolvers.u_deref() };
+
+        let mut pending = self
+            .registrar
+            .peek_vars()
+            .iter()
+            .flat_map(|(place, resolvers)| {
+                resolvers.iter().map(|x| {
+                    // Safety: `x` was pushed to the box and is still held by
+                    // the registrar.
+                    let r = unsafe { rb.get(*x) };
+
+                    PendingInfo {
+                        registration: r.added_at(),
+                        outputs: r.outputs().to_vec(),
+                        waiting_on: *place,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        pending.sort_by_key(|x| x.registration);
+
+        pending
+    }
+
     fn final_flush(&mut self) {
-        assert!(self.registrar.is_empty());
+        assert!(
+            self.registrar.is_empty(),
+            "Registration completed with {} registrations waiting on untracked inputs.",
+            self.registrar
+                .peek_vars()
+                .values()
+                .map(|x| x.len())
+                .sum::<usize>()
+        );
 
         self.flush();
 
         self.record.items.resize_with(
-            self.stats.registrations_added as usize,
+            self.stats.registrations_added as usize - self.record_stream.base,
             ResolutionRecordItem::default,
         );
 
         for (i, item) in self.record.items[..].iter_mut().enumerate() {
-            debug_assert_eq!(i, item.added_at as usize);
+            debug_assert_eq!(i + self.record_stream.base, item.added_at as usize);
         }
 
         self.record.values_count = unsafe { self.common.values.u_deref().max_tracked + 1 } as usize;
@@ -625,11 +1231,103 @@ impl<F: SmallField, Cfg: CSResolverConfig, RW: ResolutionRecordWriter> ResolverS
         }
     }
 
-    fn retrieve_sequence(&mut self) -> &ResolutionRecord {
+    fn retrieve_sequence(&self) -> &ResolutionRecord {
         &self.record
     }
 
+    fn take_sequence(&mut self) -> ResolutionRecord {
+        std::mem::replace(&mut self.record, ResolutionRecord::new(0, 0, 0))
+    }
+
     fn write_sequence(&mut self) {
+        if self.options.verify_playback {
+            // Safety: The resolution is complete, so no other thread is
+            // accessing the values.
+            let values = unsafe { self.common.values.u_deref() };
+
+            self.record.values = (0..self.record.values_count)
+                .map(|i| {
+                    values
+                        .get_item_ref(Place::from_variable_index(i as u64))
+                        .0
+                        .as_u64_reduced()
+                })
+                .collect_vec()
+                .to(Some);
+        }
+
+        #[cfg(feature = "record-timings")]
+        {
+            let mut timings = vec![std::time::Duration::ZERO; self.record.registrations_count];
+
+            for (registration, duration) in self.common.timings.lock().unwrap().iter() {
+                timings[*registration as usize] = *duration;
+            }
+
+            self.record.timings = Some(timings);
+        }
+
+        if self.record_stream.chunk_size > 0 {
+            self.record_writer.store_items(&self.record.items);
+        }
+
         self.record_writer.store(&self.record)
     }
+
+    fn worker_affinity(&self) -> Option<CpuSet> {
+        self.options.worker_affinity
+    }
+
+    fn capacity_policy(&self) -> CapacityPolicy {
+        self.options.on_capacity_exceeded
+    }
+
+    fn duplicate_set_policy(&self) -> DuplicateSetPolicy {
+        self.options.duplicate_set_policy
+    }
+
+    fn track_fanout(&self) -> bool {
+        self.options.track_fanout
+    }
+
+    fn track_dependencies(&self) -> bool {
+        self.options.track_dependencies
+    }
+
+    fn max_pending_resolvers(&self) -> Option<usize> {
+        self.options.max_pending_resolvers
+    }
+
+    fn panic_policy(&self) -> PanicPolicy {
+        self.options.on_resolver_panic
+    }
+
+    fn idle_policy(&self) -> IdlePolicy {
+        self.options.window_idle_policy
+    }
+
+    fn prioritize_awaited(&self) -> bool {
+        self.options.prioritize_awaited
+    }
+
+    fn silent(&self) -> bool {
+        self.options.silent
+    }
+
+    fn reserve(&mut self, expected_registrations: usize) {
+        // The order has gaps, so it's somewhat larger than the number of
+        // resolvers.
+        let len = expected_registrations + expected_registrations / 8;
+
+        let items = &mut self.common.exec_order.lock().unwrap().items;
+        items.reserve(len.saturating_sub(items.len()));
+
+        if self.record_stream.chunk_size == 0 {
+            let items = &mut self.record.items;
+            items.reserve(len.saturating_sub(items.len()));
+        }
+
+        let items = &mut self.pending_order.items;
+        items.reserve(self.pending_order.batch_size.saturating_sub(items.len()));
+    }
 }
@@ -1,7 +1,7 @@
 use std::{
     cell::UnsafeCell,
     marker::PhantomData,
-    sync::{Arc, Mutex},
+    sync::{atomic::AtomicU64, Arc, Mutex},
 };
 
 use crate::{
@@ -12,25 +12,42 @@ use crate::{
         guide::{GuideMetadata, OrderInfo, RegistrationNum},
         primitives::{ExecOrder, Metadata, OrderIx, ResolverIx, Values},
         resolver_box::{invocation_binder, ResolverBox},
-        resolvers::mt::{ResolverCommonData, ResolverComms},
+        resolvers::mt::{
+            resolution_window::Spawner, ResolveSubscriptions, ResolverCommonData, ResolverComms,
+        },
     },
     field::SmallField,
     utils::{PipeOp, UnsafeCellEx},
 };
 
-use super::{ResolutionRecord, ResolutionRecordItem, ResolutionRecordSource, ResolverSortingMode};
+use super::{
+    FlushReport, RecordMismatch, ResolutionRecord, ResolutionRecordItem, ResolutionRecordSource,
+    ResolverSortingMode,
+};
 
 struct OrderBufferItem {
     resolver_ix: ResolverIx,
     record_item: ResolutionRecordItem,
 }
 
+/// Replays the execution order of a record. Each order item carries the
+/// parallelism recorded for it, so the resolution window batches the replayed
+/// resolvers across the workers the same way as in the recording run.
+///
+/// The registered circuit is checked against the record structure as the
+/// registrations come in. On a mismatch the resolution is cancelled and the
+/// sorter panics with a `RecordMismatch`.
 pub struct PlaybackResolverSorter<F, Rrs: ResolutionRecordSource, Cfg> {
     common: Arc<ResolverCommonData<F, OrderIx>>,
     comms: Arc<ResolverComms>,
     exec_order_buffer: Vec<OrderBufferItem>,
     record: Rrs,
     registrations_added: usize,
+    ordered: usize,
+    /// The arity hash of the registrations so far, see
+    /// `ResolutionRecord::arity_hash`.
+    arity_hash: u64,
+    max_pending_resolvers: Option<usize>,
     phantom: PhantomData<Cfg>,
 }
 
@@ -39,6 +56,8 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig>
 {
     #[inline(always)]
     fn write_buffer(&mut self, size_override: Option<usize>) {
+        self.ordered += self.exec_order_buffer.len();
+
         let mut exec_order = self.common.exec_order.lock().unwrap();
 
         for i in &self.exec_order_buffer {
@@ -70,6 +89,55 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig>
 
         self.exec_order_buffer.clear();
     }
+
+    /// Cancels the resolution and panics if the circuit diverged from the
+    /// record. The resolution window would otherwise run resolvers that were
+    /// never registered.
+    fn check(&self, r: Result<(), RecordMismatch>) {
+        if let Err(e) = r {
+            self.comms
+                .cancelled
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            self.comms
+                .registration_complete
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+
+            panic!("{}", e);
+        }
+    }
+
+    fn check_places(&self, places: &[Place]) -> Result<(), RecordMismatch> {
+        let values_count = self.record.get().values_count;
+
+        match places.iter().find(|x| x.raw_ix() >= values_count) {
+            Some(place) => Err(RecordMismatch::Place {
+                place: *place,
+                values_count,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Rewinds the sorter to the start of the record, so the same recorded
+    /// order can be played back for a new set of inputs.
+    ///
+    /// Must be called only when the resolution window is not running, as the
+    /// common data is reset in an unsynchronized manner.
+    pub fn reset(&mut self) {
+        // Safety: The resolution window is not running, so this thread is the
+        // only one accessing the common data. The execution order is written
+        // by index, so its items are overwritten by the next resolution.
+        unsafe { self.common.reset() };
+
+        self.exec_order_buffer.clear();
+        self.registrations_added = 0;
+        self.ordered = 0;
+        self.arity_hash = super::FNV_OFFSET_BASIS;
+    }
+
+    pub(crate) fn set_max_pending_resolvers(&mut self, value: Option<usize>) {
+        self.max_pending_resolvers = value;
+    }
 }
 
 impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> ResolverSortingMode<F>
@@ -83,6 +151,7 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
         arg: Self::Arg,
         comms: Arc<ResolverComms>,
         _debug_track: &[Place],
+        _spawner: &dyn Spawner,
     ) -> (Self, Arc<ResolverCommonData<F, OrderIx>>) {
         fn new_values<V>(size: usize, default: fn() -> V) -> Box<[V]> {
             // TODO: ensure mem-page multiple capacity.
@@ -99,7 +168,8 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
         let values = Values {
             variables: new_values(record.values_count, || {
                 UnsafeCell::new((F::from_u64_unchecked(0), Metadata::default()))
-            }),
+            })
+            .into(),
             // We know that all values are ultimately tracked, since otherwise
             // the record wouldn't've been created.
             // max_tracked: record.values_count as i64 - 1,
@@ -121,6 +191,11 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
             values: UnsafeCell::new(values),
             exec_order: Mutex::new(exec_order),
             awaiters_broker: AwaitersBroker::new(),
+            resolved_count: AtomicU64::new(0),
+            subscriptions: ResolveSubscriptions::new(),
+            failed: Mutex::new(Vec::new()),
+            #[cfg(feature = "profile-resolvers")]
+            timings: Mutex::new(Vec::new()),
         }
         .to(Arc::new);
 
@@ -135,6 +210,9 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
             record: rrs,
             exec_order_buffer: Vec::with_capacity(buf_size),
             registrations_added: 0,
+            ordered: 0,
+            arity_hash: super::FNV_OFFSET_BASIS,
+            max_pending_resolvers: None,
             phantom: PhantomData,
         };
 
@@ -143,7 +221,19 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
         (s, c)
     }
 
+    fn clear(
+        &mut self,
+        _comms: Arc<ResolverComms>,
+        _debug_track: &[Place],
+        _spawner: &dyn Spawner,
+    ) -> Arc<ResolverCommonData<F, Self::TrackId>> {
+        self.reset();
+        self.common.clone()
+    }
+
     fn set_value(&mut self, key: Place, value: F) {
+        self.check(self.check_places(&[key]));
+
         // NOTE: Common with other sorter
         // Safety: Dereferencing as &mut in mutable context. This thread doesn't hold any
         // references to `self.resolvers`. Other thread may hold shared references, but
@@ -153,6 +243,22 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
         values.set_value(key, value);
     }
 
+    fn declare_tracked_prefix(&mut self, len: usize) {
+        if len > 0 {
+            self.check(self.check_places(&[Place::from_variable_index(len as u64 - 1)]));
+        }
+
+        // Safety: Dereferencing as &mut in mutable context, see `set_value`.
+        unsafe { self.common.values.u_deref_mut() }.declare_prefix(len);
+    }
+
+    fn declare_hole(&mut self, place: Place) {
+        self.check(self.check_places(&[place]));
+
+        // Safety: Dereferencing as &mut in mutable context, see `set_value`.
+        unsafe { self.common.values.u_deref_mut() }.declare_hole(place);
+    }
+
     fn add_resolution<Fn>(
         &mut self,
         inputs: &[crate::cs::Place],
@@ -161,6 +267,21 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
     ) where
         Fn: FnOnce(&[F], &mut crate::cs::traits::cs::DstBuffer<'_, '_, F>) + Send + Sync,
     {
+        let recorded = self.record.get().registrations_count;
+
+        self.check(match self.registrations_added < recorded {
+            true => self
+                .check_places(inputs)
+                .and_then(|_| self.check_places(outputs)),
+            false => Err(RecordMismatch::Registrations {
+                recorded,
+                registered: self.registrations_added + 1,
+            }),
+        });
+
+        self.arity_hash =
+            ResolutionRecord::hash_arity(self.arity_hash, inputs.len(), outputs.len());
+
         let record = &self.record.get().items[self.registrations_added];
 
         let values = unsafe { self.common.values.u_deref_mut() };
@@ -220,12 +341,65 @@ impl<F: SmallField, Rrs: ResolutionRecordSource, Cfg: CSResolverConfig> Resolver
     }
 
     fn final_flush(&mut self) {
+        let record = self.record.get();
+
+        self.check(
+            match (
+                self.registrations_added == record.registrations_count,
+                self.arity_hash == record.arity_hash,
+            ) {
+                (false, _) => Err(RecordMismatch::Registrations {
+                    recorded: record.registrations_count,
+                    registered: self.registrations_added,
+                }),
+                (true, false) => Err(RecordMismatch::Arity),
+                (true, true) => Ok(()),
+            },
+        );
+
         self.write_buffer(Some(self.record.get().registrations_count));
     }
 
-    fn retrieve_sequence(&mut self) -> &ResolutionRecord {
+    fn max_pending_resolvers(&self) -> Option<usize> {
+        self.max_pending_resolvers
+    }
+
+    // Playback internalizes every registration as it's added.
+    fn progress(&self) -> FlushReport {
+        FlushReport {
+            internalized: self.registrations_added as u64,
+            ordered: self.ordered as u64,
+        }
+    }
+
+    fn retrieve_sequence(&self) -> &ResolutionRecord {
         self.record.get()
     }
 
-    fn write_sequence(&mut self) {}
+    /// Checks the resolved values against the recorded ones, if the record
+    /// has them. Only values resolved in both runs are compared, and the
+    /// playback must be given the same inputs as the recording.
+    fn write_sequence(&mut self) {
+        let Some(recorded) = &self.record.get().values else {
+            return;
+        };
+
+        // Safety: The resolution is complete, so no other thread is accessing
+        // the values.
+        let values = unsafe { self.common.values.u_deref() };
+
+        for (i, recorded) in recorded.iter().enumerate() {
+            let place = Place::from_variable_index(i as u64);
+            let (value, md) = values.get_item_ref(place);
+
+            if md.is_resolved() && value.as_u64_reduced() != *recorded {
+                panic!(
+                    "Playback diverged from the record at {:?}: recorded {}, resolved {}.",
+                    place,
+                    recorded,
+                    value.as_u64_reduced()
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,41 @@
+use crate::field::SmallField;
+
+/// An elementwise operation over two runs of places, see
+/// `MtCircuitResolver::add_vectorized_resolution`.
+// The resolver box stores closures in multiples of 4 bytes, and the resolvers
+// capture just the operation.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementwiseOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+/// The number of elements resolved by a single resolver of a vectorized
+/// resolution. Chunks of the run are resolved in parallel, and each one is
+/// resolved as soon as its own inputs are.
+pub const VECTORIZED_CHUNK_LEN: usize = 1 << 10;
+
+impl ElementwiseOp {
+    /// Writes `lhs[i] op rhs[i]` into `dst`. The operation is matched once per
+    /// call, so each kernel is a branch-free loop over the slices.
+    pub(crate) fn apply<F: SmallField>(self, lhs: &[F], rhs: &[F], dst: &mut Vec<F>) {
+        debug_assert_eq!(lhs.len(), rhs.len());
+
+        match self {
+            ElementwiseOp::Add => dst.extend(lhs.iter().zip(rhs).map(|(a, b)| {
+                let mut a = *a;
+                *a.add_assign(b)
+            })),
+            ElementwiseOp::Sub => dst.extend(lhs.iter().zip(rhs).map(|(a, b)| {
+                let mut a = *a;
+                *a.sub_assign(b)
+            })),
+            ElementwiseOp::Mul => dst.extend(lhs.iter().zip(rhs).map(|(a, b)| {
+                let mut a = *a;
+                *a.mul_assign(b)
+            })),
+        }
+    }
+}
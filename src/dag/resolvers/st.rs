@@ -249,7 +249,7 @@ impl<F: SmallField, CFG: CSResolverConfig> CircuitResolver<F, CFG> for StCircuit
                         UnsafeCell::new((F::from_u64_unchecked(0), Metadata::default()))
                     })
                 })
-                .to(|x| x.into_boxed_slice()),
+                .to(|x| x.into_boxed_slice().into()),
             max_tracked: -1,
         };
 
@@ -393,4 +393,16 @@ mod test {
         assert!(resolver.try_get_value(Place(2)).is_some());
         assert!(resolver.get_value_unchecked(Place(2)) == new_f(123));
     }
+
+    #[test]
+    fn resolves_zero_inputs() {
+        let mut resolver = StCircuitResolver::<F, Cfg>::new(StCircuitResolverParams::new(111));
+
+        resolver.add_resolution(&[], &[Place(0)], |_: &[F], outs: &mut DstBuffer<F>| {
+            outs.push(new_f(123));
+        });
+
+        assert!(resolver.try_get_value(Place(0)).is_some());
+        assert!(resolver.get_value_unchecked(Place(0)) == new_f(123));
+    }
 }